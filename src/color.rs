@@ -1,8 +1,19 @@
+use core::marker::PhantomData;
+
 pub use embedded_graphics::pixelcolor::{Gray2, Gray4, Gray8};
-use embedded_graphics::prelude::{GrayColor, PixelColor};
+use embedded_graphics::{
+    pixelcolor::{
+        raw::{RawData, RawU4},
+        BinaryColor,
+    },
+    prelude::{GrayColor, PixelColor},
+};
+
+use crate::drivers::MultiColorDriver;
 
 /// 3 color display
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TriColor {
     White,
     Black,
@@ -14,6 +25,43 @@ impl PixelColor for TriColor {
     type Raw = ();
 }
 
+/// `BinaryColor::On` is white, as on the black/white displays.
+///
+/// Monochrome content, e.g. fonts and images, is drawn onto a `TriColorEpd` through
+/// `DrawTargetExt::color_converted`.
+impl From<BinaryColor> for TriColor {
+    fn from(color: BinaryColor) -> Self {
+        match color {
+            BinaryColor::On => TriColor::White,
+            BinaryColor::Off => TriColor::Black,
+        }
+    }
+}
+
+/// Bits of a color in each of the `PLANES` framebuffers sent to the controller, e.g. the black/white
+/// and the red RAM of a three color panel.
+///
+/// Panels with an encoding of their own, e.g. four colors in two planes, implement it on a marker type
+/// and are drawn through `TriColorEpd::encoded_target`.
+pub trait ColorEncoder<C: PixelColor, const PLANES: usize> {
+    fn encode(color: C) -> [BinaryColor; PLANES];
+}
+
+/// `TriColor` in the black/white and chromatic planes of `D`, the encoding of `TriColorEpd`.
+pub struct TriColorEncoder<D>(PhantomData<D>);
+
+impl<D: MultiColorDriver> ColorEncoder<TriColor, 2> for TriColorEncoder<D> {
+    fn encode(color: TriColor) -> [BinaryColor; 2] {
+        let white = BinaryColor::from(D::WHITE_BIT);
+        let colored = BinaryColor::from(D::CHROMATIC_BIT);
+        match color {
+            TriColor::White => [white, colored.invert()],
+            TriColor::Black => [white.invert(), colored.invert()],
+            TriColor::Red => [white, colored],
+        }
+    }
+}
+
 // BITS_PER_PIXEL is hidden behind RawData. RawData for Gray3 is not possible now.
 pub trait GrayColorInBits {
     const BITS_PER_PIXEL: usize;
@@ -60,10 +108,16 @@ impl From<u8> for Gray3 {
         Self::from_u8(value)
     }
 }
-// fake impl, to let compile pass
-impl From<()> for Gray3 {
-    fn from(_: ()) -> Self {
-        Gray3(0)
+/// Raw values above 7 are clamped to white.
+impl From<RawU4> for Gray3 {
+    fn from(raw: RawU4) -> Self {
+        Self::from_u8(raw.into_inner())
+    }
+}
+
+impl From<Gray3> for RawU4 {
+    fn from(color: Gray3) -> Self {
+        RawU4::new(color.0)
     }
 }
 
@@ -78,10 +132,10 @@ impl GrayColorInBits for Gray3 {
     }
 }
 
-// NOTE: RawData is a sealed trait in embedded-graphics, so we can't implement it for Gray3.
-// Do not use Gray3 as storage for images. As it is not aligned to 8 bit boundaries.
+// NOTE: RawData is a sealed trait in embedded-graphics, so there is no 3 bit raw type.
+// Images of Gray3 are stored with 4 bits per pixel, `GrayFrameBuffer` still packs 3 bits.
 impl PixelColor for Gray3 {
-    type Raw = ();
+    type Raw = RawU4;
 }
 
 impl GrayColor for Gray3 {
@@ -0,0 +1,29 @@
+//! Compatibility with embedded-hal 0.2 HALs.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal_02::blocking::delay::DelayUs;
+
+/// Use an embedded-hal 0.2 `DelayUs<u32>` where the drivers expect a `DelayNs`.
+///
+/// Delays are rounded up to whole microseconds.
+pub struct Delay02<D>(pub D);
+
+impl<D> Delay02<D> {
+    pub fn new(delay: D) -> Self {
+        Self(delay)
+    }
+
+    pub fn release(self) -> D {
+        self.0
+    }
+}
+
+impl<D: DelayUs<u32>> DelayNs for Delay02<D> {
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.delay_us(ns.div_ceil(1_000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.0.delay_us(us);
+    }
+}
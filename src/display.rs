@@ -3,20 +3,46 @@
 //! The buffer has to be flushed to update the display after a group of draw calls has been completed.
 //! The flush is not part of embedded-graphics API.
 
-use core::convert::TryInto;
-use core::mem;
+use core::convert::{TryFrom, TryInto};
+use core::marker::PhantomData;
+#[cfg(feature = "nightly")]
+use core::ops::Range;
 
 use embedded_graphics::{
     draw_target::DrawTarget,
-    pixelcolor::{BinaryColor, Gray2, Gray4, Gray8},
+    framebuffer::Framebuffer,
+    image::{ImageDrawable, ImageRaw},
+    pixelcolor::{
+        raw::{ByteOrder, RawU1},
+        BinaryColor,
+    },
     prelude::*,
     primitives::Rectangle,
 };
 
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec};
+
+#[cfg(feature = "nightly")]
 use crate::color::GrayColorInBits;
+use crate::interface::DisplayError;
+use crate::rle;
+
+#[cfg(feature = "tinybmp")]
+mod bmp;
+mod encoded;
+mod inverted;
+mod row_writer;
+mod sram;
+
+pub use self::encoded::EncodedTarget;
+pub use self::inverted::InvertedTarget;
+pub use self::row_writer::RowWriter;
+pub use self::sram::{SpiSramFrameBuffer, SRAM_SIZE};
 
 /// Rotation of the display.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum DisplayRotation {
     /// No rotation, normal display
@@ -29,7 +55,8 @@ pub enum DisplayRotation {
     Rotate270,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Mirroring {
     None,
     Horizontal,
@@ -45,122 +72,335 @@ pub trait DisplaySize {
     const HEIGHT: usize;
 
     const N: usize;
-}
-
-/// 2in9
-#[derive(Clone, Copy)]
-pub struct DisplaySize128x296;
-
-impl DisplaySize for DisplaySize128x296 {
-    const WIDTH: usize = 128;
-    const HEIGHT: usize = 296;
-
-    const N: usize = (Self::WIDTH / 8) * Self::HEIGHT;
-}
-
-/// SSD1608/IL3820 in cascade mode 2x 200x300
-#[derive(Clone, Copy)]
-pub struct DisplaySize200x300;
 
-impl DisplaySize for DisplaySize200x300 {
-    const WIDTH: usize = 200;
-    const HEIGHT: usize = 300;
-
-    const N: usize = (Self::WIDTH / 8) * Self::HEIGHT;
+    /// First RAM column of the visible area, a multiple of 8, for panels mapped at an offset.
+    ///
+    /// Non-zero offsets need a driver writing RAM windows, see `Driver::update_frame_window`.
+    const X_OFFSET: usize = 0;
+    /// First RAM row of the visible area
+    const Y_OFFSET: usize = 0;
 }
 
-/// For 2in13 PPD with Black, Red/Yellow and White, WIDTH=104, HEIGHT=212.
-#[derive(Clone, Copy)]
-pub struct DisplaySize212x104;
-
-impl DisplaySize for DisplaySize212x104 {
-    const WIDTH: usize = 104;
-    const HEIGHT: usize = 212;
+/// Define a unit struct implementing `DisplaySize`, with `N` derived from the width and height.
+///
+/// Width and height are in the controller's native orientation, offsets are optional:
+/// `display_size!(pub DisplaySize152x296, 152, 296, x_offset = 8);`
+#[macro_export]
+macro_rules! display_size {
+    (
+        $(#[$meta:meta])*
+        $vis:vis $name:ident, $width:expr, $height:expr
+        $(, x_offset = $x_offset:expr)?
+        $(, y_offset = $y_offset:expr)?
+        $(,)?
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy)]
+        $vis struct $name;
+
+        impl $crate::display::DisplaySize for $name {
+            const WIDTH: usize = $width;
+            const HEIGHT: usize = $height;
+
+            // rows are padded to whole bytes
+            const N: usize = usize::div_ceil($width, 8) * $height;
+            $(const X_OFFSET: usize = $x_offset;)?
+            $(const Y_OFFSET: usize = $y_offset;)?
+        }
 
-    const N: usize = (Self::WIDTH / 8 + 1) * Self::HEIGHT;
+        const _: () = $crate::display::check_display_size::<$name>();
+    };
 }
 
-#[derive(Clone, Copy)]
-pub struct DisplaySize104x201;
-
-impl DisplaySize for DisplaySize104x201 {
-    const WIDTH: usize = 104;
-    const HEIGHT: usize = 201;
-
-    const N: usize = (Self::WIDTH / 8 + 1) * Self::HEIGHT;
+/// Check the consistency of a `DisplaySize` at compile time, when called in a `const`.
+///
+/// `N` must be the byte padded width times the height, and `X_OFFSET` a multiple of 8.
+pub const fn check_display_size<S: DisplaySize>() {
+    assert!(S::WIDTH > 0 && S::HEIGHT > 0, "empty display size");
+    assert!(
+        S::N == S::WIDTH.div_ceil(8) * S::HEIGHT,
+        "N must be WIDTH.div_ceil(8) * HEIGHT"
+    );
+    assert!(S::X_OFFSET % 8 == 0, "X_OFFSET must be a multiple of 8");
 }
 
-/// For 2in13 EPD with Black and White, WIDTH=122, HEIGHT=250.
+display_size!(
+    /// 2in9
+    pub DisplaySize128x296, 128, 296
+);
+
+display_size!(
+    /// SSD1608/IL3820 in cascade mode 2x 200x300
+    pub DisplaySize200x300, 200, 300
+);
+
+display_size!(
+    /// For 1in54 EPD with SSD1681 or JD79653A, WIDTH=200, HEIGHT=200.
+    pub DisplaySize200x200, 200, 200
+);
+
+display_size!(
+    /// For 1in54 EPD, WIDTH=152, HEIGHT=152.
+    pub DisplaySize152x152, 152, 152
+);
+
+display_size!(
+    /// For 2in13 PPD with Black, Red/Yellow and White, WIDTH=104, HEIGHT=212.
+    pub DisplaySize212x104, 104, 212
+);
+
+display_size!(
+    /// WIDTH=104, HEIGHT=201.
+    pub DisplaySize104x201, 104, 201
+);
+
+display_size!(
+    /// For 2in66 EPD with SSD1680, WIDTH=152, HEIGHT=296.
+    ///
+    /// The 152 columns are wired to SSD1680 sources 8 to 159 of 176, so the RAM X window starts at column 8.
+    pub DisplaySize296x152, 152, 296, x_offset = 8
+);
+
+display_size!(
+    /// For 2in13 EPD with Black and White, WIDTH=122, HEIGHT=250.
+    ///
+    /// Mapped at RAM column and row 0, as the vendor code of the Waveshare V2 to V4 and GDEH0213B7x modules
+    /// addresses it. The offset of the modules showing the image shifted is not known yet, define their size
+    /// with `display_size!` and the measured `x_offset`/`y_offset` instead.
+    pub DisplaySize122x250, 122, 250
+);
+
+display_size!(
+    /// For 3in7 EPD with UC8253 or SSD1677, WIDTH=240, HEIGHT=416.
+    ///
+    /// The native orientation is already portrait, as these panels are usually mounted, so the
+    /// default rotation of 0 fits.
+    pub DisplaySize240x416, 240, 416
+);
+
+display_size!(
+    /// 4in2
+    pub DisplaySize400x300, 400, 300
+);
+
+display_size!(
+    /// For 7in5 V2 EPD with UC8179 (GD7965), WIDTH=800, HEIGHT=480.
+    pub DisplaySize800x480, 800, 480
+);
+
+/// Size marker of framebuffers whose width and height are only known at runtime.
 #[derive(Clone, Copy)]
-pub struct DisplaySize122x250;
+pub struct DynamicSize;
 
-impl DisplaySize for DisplaySize122x250 {
-    const WIDTH: usize = 122;
-    const HEIGHT: usize = 250;
+#[cfg(feature = "nightly")]
+/// Owned storage of a framebuffer, embedded by value.
+#[derive(Clone)]
+pub struct ArrayStorage<SIZE: DisplaySize>([u8; SIZE::N])
+where
+    [(); SIZE::N]:;
 
-    const N: usize = (Self::WIDTH / 8 + 1) * Self::HEIGHT;
+#[cfg(feature = "nightly")]
+impl<SIZE: DisplaySize> AsRef<[u8]> for ArrayStorage<SIZE>
+where
+    [(); SIZE::N]:,
+{
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
 }
 
-// 4in2
-#[derive(Clone, Copy)]
-pub struct DisplaySize400x300;
-
-impl DisplaySize for DisplaySize400x300 {
-    const WIDTH: usize = 400;
-    const HEIGHT: usize = 300;
-
-    const N: usize = (Self::WIDTH / 8) * Self::HEIGHT;
+#[cfg(feature = "nightly")]
+impl<SIZE: DisplaySize> AsMut<[u8]> for ArrayStorage<SIZE>
+where
+    [(); SIZE::N]:,
+{
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
 }
 
-#[derive(Clone, Copy)]
-pub struct DisplaySize800x480;
-
-impl DisplaySize for DisplaySize800x480 {
-    const WIDTH: usize = 800;
-    const HEIGHT: usize = 480;
-
-    const N: usize = (Self::WIDTH / 8) * Self::HEIGHT;
-}
 /// Framebuffer with rotation support
+///
+/// The frame data is stored in `B`, with the `nightly` feature by default an array embedded in the
+/// framebuffer. Use `new_in` to place it in user supplied memory instead, e.g. a `&'static mut [u8]`.
 #[derive(Clone)]
-pub struct FrameBuffer<SIZE: DisplaySize>
-where
-    [(); SIZE::N]:,
-{
-    buf: [u8; SIZE::N],
+pub struct FrameBuffer<
+    SIZE,
+    #[cfg(feature = "nightly")] B = ArrayStorage<SIZE>,
+    #[cfg(not(feature = "nightly"))] B,
+> {
+    buf: B,
+    width: usize,
+    height: usize,
+    /// Native row stored at the start of `buf`, non-zero for strips of paged rendering
+    first_row: usize,
     rotation: DisplayRotation,
+    /// Rotation applied when the frame is sent, see `set_flush_rotation`
+    flush_rotation: DisplayRotation,
+    /// Leave vertical flips to the controller, see `set_hardware_flip`
+    hardware_flip: bool,
     mirroring: Mirroring,
     inverted: bool,
+    strict: bool,
+    /// RAM column and row of the visible area
+    ram_offset: (usize, usize),
+    _size: PhantomData<SIZE>,
+}
+
+impl<SIZE, B> FrameBuffer<SIZE, B> {
+    /// Framebuffer of a `width` x `height` panel in `buf`, not rotated.
+    const fn from_parts(buf: B, width: usize, height: usize, ram_offset: (usize, usize)) -> Self {
+        Self {
+            buf,
+            width,
+            height,
+            first_row: 0,
+            rotation: DisplayRotation::Rotate0,
+            flush_rotation: DisplayRotation::Rotate0,
+            hardware_flip: false,
+            mirroring: Mirroring::None,
+            inverted: false,
+            strict: false,
+            ram_offset,
+            _size: PhantomData,
+        }
+    }
 }
 
+#[cfg(feature = "nightly")]
 impl<SIZE: DisplaySize> FrameBuffer<SIZE>
 where
     [(); SIZE::N]:,
 {
-    pub fn new() -> Self {
-        let buf = unsafe { mem::zeroed() };
+    /// All bits cleared, `const` to allow placing the framebuffer in a `static`.
+    pub const fn new() -> Self {
+        Self::from_bytes([0; SIZE::N])
+    }
 
-        Self {
+    /// All bits set.
+    pub const fn new_ones() -> Self {
+        Self::from_bytes([0xff; SIZE::N])
+    }
+
+    /// Create a framebuffer from raw 1bpp data, e.g. a frame stored with `include_bytes!`.
+    pub const fn from_bytes(buf: [u8; SIZE::N]) -> Self {
+        Self::from_parts(
+            ArrayStorage(buf),
+            SIZE::WIDTH,
+            SIZE::HEIGHT,
+            (SIZE::X_OFFSET, SIZE::Y_OFFSET),
+        )
+    }
+}
+
+impl<SIZE: DisplaySize, B: AsRef<[u8]> + AsMut<[u8]>> FrameBuffer<SIZE, B> {
+    /// Create a framebuffer in user supplied storage, which must be exactly `SIZE::N` bytes.
+    ///
+    /// The content of `buf` is kept as is.
+    pub fn new_in(buf: B) -> Result<Self, DisplayError> {
+        if buf.as_ref().len() != SIZE::N {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        Ok(Self::from_parts(
             buf,
-            rotation: DisplayRotation::Rotate0,
-            mirroring: Mirroring::None,
-            inverted: false,
+            SIZE::WIDTH,
+            SIZE::HEIGHT,
+            (SIZE::X_OFFSET, SIZE::Y_OFFSET),
+        ))
+    }
+
+    /// Create a framebuffer holding only a strip of whole native rows, for paged rendering.
+    ///
+    /// The length of `buf` must be a non-zero multiple of the row size, and at most `SIZE::N` bytes.
+    pub fn new_strip(buf: B) -> Result<Self, DisplayError> {
+        let width_in_byte = SIZE::WIDTH.div_ceil(8);
+        let len = buf.as_ref().len();
+        if len == 0 || len % width_in_byte != 0 || len > SIZE::N {
+            return Err(DisplayError::InvalidFormatError);
         }
+        Ok(Self::from_parts(
+            buf,
+            SIZE::WIDTH,
+            SIZE::HEIGHT,
+            (SIZE::X_OFFSET, SIZE::Y_OFFSET),
+        ))
     }
+}
 
-    pub fn new_ones() -> Self {
-        let mut this = Self::new();
+/// Framebuffer sized at runtime, stored on the heap.
+#[cfg(feature = "alloc")]
+pub type DynFrameBuffer = FrameBuffer<DynamicSize, Box<[u8]>>;
+
+#[cfg(feature = "alloc")]
+impl FrameBuffer<DynamicSize, Box<[u8]>> {
+    /// Allocate a zeroed framebuffer for a `width` x `height` panel, in native orientation.
+    pub fn with_size(width: usize, height: usize) -> Self {
+        let width_in_byte = width.div_ceil(8);
+        Self::from_parts(
+            vec![0; width_in_byte * height].into_boxed_slice(),
+            width,
+            height,
+            (0, 0),
+        )
+    }
+
+    pub fn with_size_ones(width: usize, height: usize) -> Self {
+        let mut this = Self::with_size(width, height);
         this.buf.fill(0xff);
-        // this.inverted = true;
         this
     }
+}
+
+/// Difference between two framebuffers, see `FrameBuffer::diff_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    pub changed_pixels: usize,
+    /// Smallest rectangle containing all changed pixels in native orientation, as used
+    /// for the RAM window of the controller. `None` if nothing changed.
+    pub bounding_rect: Option<Rectangle>,
+}
+
+impl<SIZE, B: AsRef<[u8]> + AsMut<[u8]>> FrameBuffer<SIZE, B> {
+    /// Size of the panel in its native orientation, `OriginDimensions::size` is the rotated one.
+    pub fn native_size(&self) -> Size {
+        match self.flush_rotation {
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                Size::new(self.height as _, self.width as _)
+            }
+            _ => Size::new(self.width as _, self.height as _),
+        }
+    }
+
+    /// RAM column and row of the visible area, see `DisplaySize::X_OFFSET`.
+    pub fn ram_offset(&self) -> (usize, usize) {
+        self.ram_offset
+    }
+
+    /// Number of native rows held by the buffer, less than the panel height for strips.
+    pub fn rows(&self) -> usize {
+        self.buf.as_ref().len() / self.width.div_ceil(8)
+    }
+
+    /// Move the strip to start at native row `first_row`, pixels outside of it are dropped.
+    pub(crate) fn set_first_row(&mut self, first_row: usize) {
+        self.first_row = first_row;
+    }
+
+    /// Overwrite the buffer with raw 1bpp data, `data` must be exactly as long as the buffer.
+    pub fn copy_from_slice(&mut self, data: &[u8]) -> Result<(), DisplayError> {
+        if data.len() != self.buf.as_ref().len() {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        self.buf.as_mut().copy_from_slice(data);
+        Ok(())
+    }
 
     pub fn fill(&mut self, color: BinaryColor) {
         let color_raw = match (color, self.inverted) {
             (BinaryColor::On, true) | (BinaryColor::Off, false) => 0xff,
             (BinaryColor::Off, true) | (BinaryColor::On, false) => 0x00,
         };
-        self.buf.fill(color_raw)
+        self.buf.as_mut().fill(color_raw)
     }
 
     pub fn set_rotation(&mut self, rotation: i32) {
@@ -173,103 +413,506 @@ where
         };
     }
 
+    /// Store the frame in logical orientation and rotate it while it is sent, instead of transforming
+    /// every drawn pixel, e.g. to use the fast paths of native drawing on a rotated display.
+    ///
+    /// The content must be drawn again. `set_rotation`, `set_mirroring` and the raw data, e.g. `as_bytes`,
+    /// then apply to the logical orientation. Only `Epd::display_frame` rotates the frame, which is sent whole.
+    /// Fails with `DisplayError::InvalidFormatError` if the buffer cannot hold the rotated rows.
+    pub fn set_flush_rotation(&mut self, rotation: i32) -> Result<(), DisplayError> {
+        let rotation = match rotation {
+            90 => DisplayRotation::Rotate90,
+            180 => DisplayRotation::Rotate180,
+            270 => DisplayRotation::Rotate270,
+            _ => DisplayRotation::Rotate0,
+        };
+        let native = self.native_size();
+        let (width, height) = match rotation {
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (native.height, native.width),
+            _ => (native.width, native.height),
+        };
+        let (width, height) = (width as usize, height as usize);
+        if width.div_ceil(8) * height > self.buf.as_ref().len() {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        self.width = width;
+        self.height = height;
+        self.flush_rotation = rotation;
+        Ok(())
+    }
+
+    /// The frame is stored in logical orientation, see `set_flush_rotation`.
+    pub(crate) fn flush_rotated(&self) -> bool {
+        self.flush_rotation != DisplayRotation::Rotate0
+    }
+
     pub fn set_mirroring(&mut self, mirroring: Mirroring) {
         self.mirroring = mirroring;
     }
 
+    /// Leave the vertical flip of `Rotate180` and vertical mirroring to the controller, which writes the rows
+    /// bottom-up, instead of flipping every drawn pixel. Rows are then stored in reverse order.
+    ///
+    /// The content must be drawn again. Only used by `Epd` with a driver setting `Driver::DATA_ENTRY_FLIP`,
+    /// the frame is then sent whole. Bytes hold 8 pixels of a row, horizontal flips are left to software.
+    pub fn set_hardware_flip(&mut self, on: bool) {
+        self.hardware_flip = on;
+    }
+
+    /// Rows are stored in reverse order, see `set_hardware_flip`.
+    pub(crate) fn rows_flipped(&self) -> bool {
+        let vertical = matches!(self.mirroring, Mirroring::Vertical | Mirroring::Origin)
+            ^ (self.rotation == DisplayRotation::Rotate180);
+        self.hardware_flip
+            && vertical
+            && !self.flush_rotated()
+            && matches!(
+                self.rotation,
+                DisplayRotation::Rotate0 | DisplayRotation::Rotate180
+            )
+    }
+
+    /// Rotation and mirroring applied while drawing, without the flip of rows left to the controller.
+    fn orientation(&self) -> (DisplayRotation, Mirroring) {
+        if !self.rows_flipped() {
+            return (self.rotation, self.mirroring);
+        }
+        // Rotate180 is both flips
+        let horizontal = matches!(self.mirroring, Mirroring::Horizontal | Mirroring::Origin)
+            ^ (self.rotation == DisplayRotation::Rotate180);
+        let mirroring = if horizontal {
+            Mirroring::Horizontal
+        } else {
+            Mirroring::None
+        };
+        (DisplayRotation::Rotate0, mirroring)
+    }
+
+    /// Invert the meaning of colors, the current content is inverted as well.
     pub fn set_inverted(&mut self, inverted: bool) {
+        if self.inverted == inverted {
+            return;
+        }
         self.inverted = inverted;
-        self.buf.iter_mut().for_each(|b| *b = !*b);
+        self.buf.as_mut().iter_mut().for_each(|b| *b = !*b);
     }
 
-    fn set_pixel(&mut self, x: usize, y: usize, pixel: bool) {
-        let width_in_byte = SIZE::WIDTH / 8 + (SIZE::WIDTH % 8 != 0) as usize;
+    /// Return `DisplayError::OutOfBounds` for drawing outside of the display, instead of clipping it silently.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, pixel: bool) -> Result<(), DisplayError> {
+        let width_in_byte = self.width.div_ceil(8);
+
+        let Some((x, y)) = self.transform().transform(x, y) else {
+            if self.strict {
+                return Err(DisplayError::OutOfBounds);
+            }
+            warn!("overflow set {},{}  {}", x, y, pixel);
+            return Ok(());
+        };
 
-        let (width, height) = match self.rotation {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (SIZE::WIDTH, SIZE::HEIGHT),
-            _ => (SIZE::HEIGHT, SIZE::WIDTH),
+        // Pixels outside of the current strip are dropped silently
+        let Some(y) = y.checked_sub(self.first_row) else {
+            return Ok(());
         };
+        if y >= self.rows() {
+            return Ok(());
+        }
 
-        if x >= width || y >= height {
-            defmt::warn!("overflow set {},{}  {}", x, y, pixel);
+        // The logic is for For black white color
+        let byte_offset = y * width_in_byte + x / 8;
+        if byte_offset >= self.buf.as_ref().len() {
+            error!("set {},{}  {}", x, y, pixel);
 
-            return; // TODO: signal this type of error
+            return Ok(()); // TODO: signal error
         }
+        if pixel ^ self.inverted {
+            self.buf.as_mut()[byte_offset] |= 0x80 >> (x % 8);
+        } else {
+            self.buf.as_mut()[byte_offset] &= !(0x80 >> (x % 8));
+        }
+        Ok(())
+    }
 
-        let (mut x, mut y) = match self.rotation {
-            DisplayRotation::Rotate0 => (x, y),
-            DisplayRotation::Rotate90 => (SIZE::WIDTH - y - 1, x),
-            DisplayRotation::Rotate180 => (SIZE::WIDTH - x - 1, SIZE::HEIGHT - y - 1),
-            DisplayRotation::Rotate270 => (y, SIZE::HEIGHT - x - 1),
+    /// In strict mode, fail for areas not fully inside of the display.
+    fn check_area(&self, area: &Rectangle) -> Result<(), DisplayError> {
+        if self.strict && !area.is_zero_sized() && area.intersection(&self.bounding_box()) != *area
+        {
+            return Err(DisplayError::OutOfBounds);
+        }
+        Ok(())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buf.as_ref()
+    }
+
+    /// Native area of `area`, as `(x0, y0, x1, y1)` with exclusive ends, clipped to the display.
+    pub(crate) fn native_rect(&self, area: &Rectangle) -> Option<(usize, usize, usize, usize)> {
+        self.transform().transform_rect(area)
+    }
+
+    /// Compare the raw content with `other`, e.g. to choose between a partial and a full refresh.
+    ///
+    /// Panics if the framebuffers differ in size.
+    pub fn diff_stats<B2: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        other: &FrameBuffer<SIZE, B2>,
+    ) -> DiffStats {
+        assert!(
+            self.native_size() == other.native_size()
+                && self.as_bytes().len() == other.as_bytes().len()
+        );
+        let width_in_byte = self.width.div_ceil(8);
+        // padding bits at the end of each row are ignored
+        let last_mask = match self.width % 8 {
+            0 => 0xff,
+            n => 0xffu8 << (8 - n),
         };
 
-        match self.mirroring {
-            Mirroring::Horizontal => {
-                x = SIZE::WIDTH - x - 1;
-            }
-            Mirroring::Vertical => {
-                y = SIZE::HEIGHT - y - 1;
-            }
-            Mirroring::Origin => {
-                x = SIZE::WIDTH - x - 1;
-                y = SIZE::HEIGHT - y - 1;
+        let mut stats = DiffStats::default();
+        let (mut x0, mut y0, mut x1, mut y1) = (usize::MAX, usize::MAX, 0, 0);
+        let rows = self.as_bytes().chunks_exact(width_in_byte);
+        let other_rows = other.as_bytes().chunks_exact(width_in_byte);
+        for (y, (row, other_row)) in rows.zip(other_rows).enumerate() {
+            for (i, (a, b)) in row.iter().zip(other_row).enumerate() {
+                let mut diff = a ^ b;
+                if i == width_in_byte - 1 {
+                    diff &= last_mask;
+                }
+                if diff == 0 {
+                    continue;
+                }
+                stats.changed_pixels += diff.count_ones() as usize;
+                x0 = x0.min(i * 8 + diff.leading_zeros() as usize);
+                x1 = x1.max(i * 8 + 8 - diff.trailing_zeros() as usize);
+                y0 = y0.min(y);
+                y1 = y + 1;
             }
-            _ => (),
         }
 
-        if x > SIZE::WIDTH || y > SIZE::HEIGHT {
-            defmt::error!("set {},{}  {}", x, y, pixel);
+        if stats.changed_pixels != 0 {
+            stats.bounding_rect = Some(Rectangle::new(
+                Point::new(x0 as i32, (self.first_row + y0) as i32),
+                Size::new((x1 - x0) as u32, (y1 - y0) as u32),
+            ));
+        }
+        stats
+    }
+
+    /// Draw a horizontal line of `width` pixels starting at `start`, see `draw_rect_filled`.
+    pub fn draw_hline(&mut self, start: Point, width: u32, color: BinaryColor) {
+        self.draw_rect_filled(&Rectangle::new(start, Size::new(width, 1)), color)
+    }
 
-            return; // TODO: signal error
+    /// Fill `area` with `color`, clipped to the display.
+    ///
+    /// Native rows are filled a byte at a time, with masks for the partial bytes at both ends;
+    /// with a rotation of 90 or 270 degrees a horizontal line covers one bit of several rows.
+    pub fn draw_rect_filled(&mut self, area: &Rectangle, color: BinaryColor) {
+        let Some((x0, y0, x1, y1)) = self.transform().transform_rect(area) else {
+            return;
+        };
+        let width_in_byte = self.width.div_ceil(8);
+        let value = color.is_on() ^ self.inverted;
+
+        for row in self
+            .buf
+            .as_mut()
+            .chunks_exact_mut(width_in_byte)
+            .take(y1.saturating_sub(self.first_row))
+            .skip(y0.saturating_sub(self.first_row))
+        {
+            fill_bits(row, x0, x1, value);
         }
+    }
 
-        // The logic is for For black white color
-        let byte_offset = y * width_in_byte + x / 8;
-        if byte_offset >= self.buf.len() {
-            defmt::error!("set {},{}  {}", x, y, pixel);
+    /// Copy a 1bpp MSB-first image into the buffer at `dest`.
+    ///
+    /// `src_stride` is the number of bytes per row of `src`. Set bits are drawn as `BinaryColor::On`.
+    /// Parts outside the display are clipped. In native orientation rows are copied byte by byte,
+    /// otherwise the image is drawn pixel by pixel.
+    pub fn blit(&mut self, src: &[u8], src_stride: usize, dest: Point, size: Size) {
+        self.blit_inner(src, src_stride, dest, size, false)
+    }
+
+    /// Copy an embedded-graphics `Framebuffer`, e.g. filled by another component, with its top left corner at `dest`.
+    ///
+    /// Both store MSB-first rows, see `blit`.
+    pub fn draw_framebuffer<BO: ByteOrder, const W: usize, const H: usize, const N: usize>(
+        &mut self,
+        src: &Framebuffer<BinaryColor, RawU1, BO, W, H, N>,
+        dest: Point,
+    ) {
+        self.blit(
+            src.data(),
+            W.div_ceil(8),
+            dest,
+            Size::new(W as u32, H as u32),
+        )
+    }
 
-            return; // TODO: signal error
+    /// Copy the content in native orientation into an embedded-graphics `Framebuffer` of the panel size.
+    ///
+    /// Fails with `DisplayError::InvalidFormatError` if the sizes differ, e.g. for strips.
+    pub fn copy_to_framebuffer<BO: ByteOrder, const W: usize, const H: usize, const N: usize>(
+        &self,
+        dst: &mut Framebuffer<BinaryColor, RawU1, BO, W, H, N>,
+    ) -> Result<(), DisplayError> {
+        if (W, H, N) != (self.width, self.height, self.buf.as_ref().len()) {
+            return Err(DisplayError::InvalidFormatError);
         }
-        if pixel ^ self.inverted {
-            self.buf.as_mut_slice()[byte_offset] |= 0x80 >> (x % 8);
+        dst.data_mut().copy_from_slice(self.buf.as_ref());
+        if self.inverted {
+            dst.data_mut().iter_mut().for_each(|b| *b = !*b);
+        }
+        Ok(())
+    }
+
+    /// The raw content as an `ImageRaw` in native orientation, e.g. to draw it onto another target.
+    ///
+    /// Set bits are `BinaryColor::On`, colors are swapped if the framebuffer is inverted.
+    pub fn as_image_raw(&self) -> ImageRaw<'_, BinaryColor> {
+        ImageRaw::new(self.buf.as_ref(), self.width as u32)
+    }
+
+    /// Draw an image in the `rle` format with its top left corner at `dest`.
+    pub fn draw_rle(&mut self, data: &[u8], dest: Point) -> Result<(), DisplayError> {
+        let (size, mut bytes) = rle::decode(data)?;
+        let stride = (size.width as usize).div_ceil(8);
+        let mut row = [0u8; rle::MAX_WIDTH / 8];
+        let row = &mut row[..stride];
+
+        for y in 0..size.height as i32 {
+            for b in row.iter_mut() {
+                *b = bytes.next().ok_or(DisplayError::InvalidFormatError)?;
+            }
+            self.blit(
+                row,
+                stride,
+                dest + Point::new(0, y),
+                Size::new(size.width, 1),
+            );
+        }
+        Ok(())
+    }
+
+    /// `blit`, optionally treating set bits of `src` as `BinaryColor::Off`.
+    fn blit_inner(&mut self, src: &[u8], src_stride: usize, dest: Point, size: Size, invert: bool) {
+        let area = Rectangle::new(dest, size);
+        let clipped = area.intersection(&self.bounding_box());
+        let Some(bottom_right) = clipped.bottom_right() else {
+            return;
+        };
+        let width_in_byte = self.width.div_ceil(8);
+        let native = matches!(
+            self.orientation(),
+            (DisplayRotation::Rotate0, Mirroring::None)
+        );
+        let inverted = self.inverted;
+
+        for y in clipped.rows() {
+            let src_y = (y - dest.y) as usize;
+            let Some(src_row) = src.get(src_y * src_stride..(src_y + 1) * src_stride) else {
+                break;
+            };
+
+            if !native {
+                for x in clipped.columns() {
+                    let src_x = (x - dest.x) as usize;
+                    let pixel = src_row[src_x / 8] & (0x80 >> (src_x % 8)) != 0;
+                    self.set_pixel(x as usize, y as usize, pixel ^ invert).ok();
+                }
+                continue;
+            }
+
+            let (x0, x1) = (clipped.top_left.x as usize, bottom_right.x as usize + 1);
+            let Some(row) = (y as usize)
+                .checked_sub(self.first_row)
+                .and_then(|y| self.buf.as_mut().chunks_exact_mut(width_in_byte).nth(y))
+            else {
+                continue;
+            };
+            for (byte_x, byte) in row
+                .iter_mut()
+                .enumerate()
+                .take((x1 - 1) / 8 + 1)
+                .skip(x0 / 8)
+            {
+                let first = (byte_x * 8).max(x0);
+                let last = (byte_x * 8 + 8).min(x1);
+                let mask = (0xff >> (first % 8)) & (0xff << (7 - (last - 1) % 8));
+
+                // source bit aligned to the first pixel of this byte, may be negative
+                let src_bit = byte_x as isize * 8 - dest.x as isize;
+                let mut bits = read_bits(src_row, src_bit);
+                if inverted ^ invert {
+                    bits = !bits;
+                }
+                apply_bits(byte, mask, bits);
+            }
+        }
+    }
+
+    /// Native rows starting at `y` into `out`, as whole rows, rotated as set by `set_flush_rotation`
+    /// and moved by `dx`, `dy` pixels.
+    ///
+    /// Pixels at the edges are repeated into the area uncovered by the move, see `PixelShift`.
+    pub(crate) fn native_rows(&self, y: usize, (dx, dy): (i32, i32), out: &mut [u8]) {
+        let native = self.native_size();
+        let (width, height) = (native.width as usize, native.height as usize);
+        let last_row = height as isize - 1;
+        let pixel = |row: &[u8], x: usize| row[x / 8] & (0x80 >> (x % 8)) != 0;
+        for (i, row) in out.chunks_exact_mut(width.div_ceil(8)).enumerate() {
+            let src_y = ((y + i) as isize - dy as isize).clamp(0, last_row) as usize;
+            self.native_row(src_y, row);
+
+            // in place, reading bytes not moved yet
+            let len = row.len();
+            if dx > 0 {
+                let edge = pixel(row, 0);
+                for x in (0..len).rev() {
+                    row[x] = read_bits(row, x as isize * 8 - dx as isize);
+                }
+                fill_bits(row, 0, dx as usize, edge);
+            } else if dx < 0 {
+                let edge = pixel(row, width - 1);
+                for x in 0..len {
+                    row[x] = read_bits(row, x as isize * 8 - dx as isize);
+                }
+                fill_bits(
+                    row,
+                    width.saturating_sub(dx.unsigned_abs() as usize),
+                    width,
+                    edge,
+                );
+            }
+        }
+    }
+
+    /// Native row `y` into `row`, bits beyond the panel width are cleared for a rotated frame.
+    fn native_row(&self, y: usize, row: &mut [u8]) {
+        let stride = self.width.div_ceil(8);
+        let buf = self.buf.as_ref();
+        let y = if self.rows_flipped() {
+            self.height - 1 - y
         } else {
-            self.buf.as_mut_slice()[byte_offset] &= !(0x80 >> (x % 8));
+            y
+        };
+        if !self.flush_rotated() {
+            if let Some(src) = buf.chunks_exact(stride).nth(y) {
+                row.copy_from_slice(src);
+            }
+            return;
+        }
+
+        let native = self.native_size();
+        let (w, h) = (native.width as usize, native.height as usize);
+        row.fill(0);
+        for x in 0..w {
+            let (lx, ly) = match self.flush_rotation {
+                DisplayRotation::Rotate90 => (y, w - 1 - x),
+                DisplayRotation::Rotate180 => (w - 1 - x, h - 1 - y),
+                DisplayRotation::Rotate270 => (h - 1 - y, x),
+                DisplayRotation::Rotate0 => (x, y),
+            };
+            if buf[ly * stride + lx / 8] & (0x80 >> (lx % 8)) != 0 {
+                row[x / 8] |= 0x80 >> (x % 8);
+            }
         }
     }
 
-    pub fn as_bytes(&self) -> &[u8] {
-        &self.buf
+    /// Write the raw buffer as a binary PBM (P4) image.
+    ///
+    /// Bit 0 is written as black, matching drivers where `BLACK_BIT` is false.
+    #[cfg(feature = "std")]
+    pub fn write_pbm<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        let width_in_byte = self.width.div_ceil(8);
+        write!(w, "P4\n{} {}\n", self.width, self.height)?;
+        for row in self
+            .buf
+            .as_ref()
+            .chunks_exact(width_in_byte)
+            .take(self.height)
+        {
+            for &byte in row {
+                w.write_all(&[!byte])?;
+            }
+        }
+        Ok(())
     }
 
-    fn size(&self) -> Size {
-        Size::new(SIZE::WIDTH as _, SIZE::HEIGHT as _)
+    /// Color of the pixel at logical `p`, `Off` outside of the display or the current strip.
+    fn pixel(&self, p: Point) -> BinaryColor {
+        let width_in_byte = self.width.div_ceil(8);
+        let (Ok(x), Ok(y)) = (usize::try_from(p.x), usize::try_from(p.y)) else {
+            return BinaryColor::Off;
+        };
+        self.transform()
+            .transform(x, y)
+            .and_then(|(x, y)| {
+                let y = y.checked_sub(self.first_row)?;
+                let byte = self.buf.as_ref().get(y * width_in_byte + x / 8)?;
+                Some(BinaryColor::from(
+                    (byte & (0x80 >> (x % 8)) != 0) ^ self.inverted,
+                ))
+            })
+            .unwrap_or(BinaryColor::Off)
+    }
+
+    fn transform(&self) -> Transform {
+        let (rotation, mirroring) = self.orientation();
+        Transform::new(self.width, self.height, rotation, mirroring)
     }
 }
 
-impl<SIZE: DisplaySize> Dimensions for FrameBuffer<SIZE>
+#[cfg(feature = "nightly")]
+impl<SIZE: DisplaySize> Default for FrameBuffer<SIZE>
 where
     [(); SIZE::N]:,
 {
-    fn bounding_box(&self) -> Rectangle {
-        match self.rotation {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => Rectangle::new(
-                Point::zero(),
-                Size::new(SIZE::WIDTH as _, SIZE::HEIGHT as _),
-            ),
-            _ => Rectangle::new(
-                Point::zero(),
-                Size::new(SIZE::HEIGHT as _, SIZE::WIDTH as _),
-            ),
-        }
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl<SIZE: DisplaySize> DrawTarget for FrameBuffer<SIZE>
-where
-    [(); SIZE::N]:,
-{
+impl<SIZE, B: AsRef<[u8]> + AsMut<[u8]>> OriginDimensions for FrameBuffer<SIZE, B> {
+    /// Size in the current rotation, unlike `native_size`.
+    fn size(&self) -> Size {
+        self.transform().bounding_box().size
+    }
+}
+
+/// Draw the buffer with `Image`, e.g. a widget rendered once off-screen and drawn at an offset
+/// into another framebuffer or onto the display.
+impl<SIZE, B: AsRef<[u8]> + AsMut<[u8]>> ImageDrawable for FrameBuffer<SIZE, B> {
     type Color = BinaryColor;
-    type Error = core::convert::Infallible;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let area = self.bounding_box();
+        target.fill_contiguous(&area, area.points().map(|p| self.pixel(p)))
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let area = area.intersection(&self.bounding_box());
+        target.fill_contiguous(
+            &Rectangle::new(Point::zero(), area.size),
+            area.points().map(|p| self.pixel(p)),
+        )
+    }
+}
+
+impl<SIZE, B: AsRef<[u8]> + AsMut<[u8]>> DrawTarget for FrameBuffer<SIZE, B> {
+    type Color = BinaryColor;
+    type Error = DisplayError;
 
     fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
     where
@@ -277,15 +920,272 @@ where
     {
         for Pixel(coord, color) in pixels.into_iter() {
             match TryInto::<(u32, u32)>::try_into(coord) {
-                Ok((x, y)) => self.set_pixel(x as _, y as _, color.is_on()),
-                _ => (),
+                Ok((x, y)) => self.set_pixel(x as _, y as _, color.is_on())?,
+                Err(_) if self.strict => return Err(DisplayError::OutOfBounds),
+                Err(_) => (),
             }
         }
 
         Ok(())
     }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.check_area(area)?;
+        self.draw_rect_filled(area, color);
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.check_area(area)?;
+        // Only the native orientation maps rows of the area onto rows of the buffer
+        if !matches!(
+            self.orientation(),
+            (DisplayRotation::Rotate0, Mirroring::None)
+        ) {
+            return self.draw_iter(
+                area.points()
+                    .zip(colors)
+                    .map(|(pos, color)| Pixel(pos, color)),
+            );
+        }
+
+        let clipped = area.intersection(&self.bounding_box());
+        let width_in_byte = self.width.div_ceil(8);
+        let strip = self.first_row..self.first_row + self.rows();
+        let mut colors = colors.into_iter();
+
+        for y in area.rows() {
+            let row_in_range = clipped.rows().contains(&y) && strip.contains(&(y as usize));
+            // bits are collected per byte, then written back at once
+            let (mut offset, mut mask, mut bits) = (0, 0u8, 0u8);
+
+            for x in area.columns() {
+                let Some(color) = colors.next() else {
+                    break;
+                };
+                if !row_in_range || !clipped.columns().contains(&x) {
+                    continue;
+                }
+                let (x, y) = (x as usize, y as usize - self.first_row);
+                let byte_offset = y * width_in_byte + x / 8;
+                if byte_offset != offset {
+                    if mask != 0 {
+                        apply_bits(&mut self.buf.as_mut()[offset], mask, bits);
+                    }
+                    (offset, mask, bits) = (byte_offset, 0, 0);
+                }
+
+                let bit = 0x80 >> (x % 8);
+                mask |= bit;
+                if color.is_on() ^ self.inverted {
+                    bits |= bit;
+                }
+            }
+
+            if mask != 0 {
+                apply_bits(&mut self.buf.as_mut()[offset], mask, bits);
+            }
+        }
+        Ok(())
+    }
 }
 
+/// Mapping from logical coordinates of a rotated and mirrored display to raw buffer
+/// coordinates of a `width` x `height` panel.
+///
+/// Rotation is applied first, mirroring then flips the result in the native orientation of the panel.
+#[derive(Clone, Copy, Debug)]
+struct Transform {
+    width: usize,
+    height: usize,
+    rotation: DisplayRotation,
+    mirroring: Mirroring,
+}
+
+impl Transform {
+    fn new(width: usize, height: usize, rotation: DisplayRotation, mirroring: Mirroring) -> Self {
+        Self {
+            width,
+            height,
+            rotation,
+            mirroring,
+        }
+    }
+
+    /// Size of the display after rotation.
+    fn size(&self) -> Size {
+        match self.rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => {
+                Size::new(self.width as _, self.height as _)
+            }
+            _ => Size::new(self.height as _, self.width as _),
+        }
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(Point::zero(), self.size())
+    }
+
+    /// Map a logical point to raw coordinates, `None` for points outside of the display.
+    fn transform(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let size = self.size();
+        if x >= size.width as usize || y >= size.height as usize {
+            return None;
+        }
+        let (w, h) = (self.width, self.height);
+
+        let (mut x, mut y) = match self.rotation {
+            DisplayRotation::Rotate0 => (x, y),
+            DisplayRotation::Rotate90 => (w - y - 1, x),
+            DisplayRotation::Rotate180 => (w - x - 1, h - y - 1),
+            DisplayRotation::Rotate270 => (y, h - x - 1),
+        };
+        if matches!(self.mirroring, Mirroring::Horizontal | Mirroring::Origin) {
+            x = w - x - 1;
+        }
+        if matches!(self.mirroring, Mirroring::Vertical | Mirroring::Origin) {
+            y = h - y - 1;
+        }
+
+        Some((x, y))
+    }
+
+    /// Map a logical area, clipped to the display, to raw coordinates as (x0, y0, x1, y1), end exclusive.
+    fn transform_rect(&self, area: &Rectangle) -> Option<(usize, usize, usize, usize)> {
+        let area = area.intersection(&self.bounding_box());
+        let bottom_right = area.bottom_right()?;
+        let (x0, y0) = (area.top_left.x as usize, area.top_left.y as usize);
+        let (x1, y1) = (bottom_right.x as usize + 1, bottom_right.y as usize + 1);
+        let (w, h) = (self.width, self.height);
+
+        let (mut x0, mut y0, mut x1, mut y1) = match self.rotation {
+            DisplayRotation::Rotate0 => (x0, y0, x1, y1),
+            DisplayRotation::Rotate90 => (w - y1, x0, w - y0, x1),
+            DisplayRotation::Rotate180 => (w - x1, h - y1, w - x0, h - y0),
+            DisplayRotation::Rotate270 => (y0, h - x1, y1, h - x0),
+        };
+        if matches!(self.mirroring, Mirroring::Horizontal | Mirroring::Origin) {
+            (x0, x1) = (w - x1, w - x0);
+        }
+        if matches!(self.mirroring, Mirroring::Vertical | Mirroring::Origin) {
+            (y0, y1) = (h - y1, h - y0);
+        }
+
+        Some((x0, y0, x1, y1))
+    }
+}
+
+/// Areas kept by `DirtyRects` before the closest ones are merged.
+const MAX_DIRTY_RECTS: usize = 8;
+
+/// Native area as `(x0, y0, x1, y1)`, with exclusive ends.
+type NativeRect = (usize, usize, usize, usize);
+
+/// Areas queued by `Epd::invalidate`, in native coordinates with X aligned to bytes.
+///
+/// Overlapping areas are merged, so that no byte is sent twice.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct DirtyRects {
+    rects: [Option<NativeRect>; MAX_DIRTY_RECTS],
+}
+
+impl DirtyRects {
+    pub(crate) fn add(&mut self, (x0, y0, x1, y1): NativeRect) {
+        let mut rect = (x0 / 8 * 8, y0, x1.div_ceil(8) * 8, y1);
+        // the union may overlap areas the new one did not
+        while let Some(slot) = self
+            .rects
+            .iter_mut()
+            .find(|r| r.is_some_and(|r| overlaps(r, rect)))
+        {
+            rect = union(slot.take().unwrap(), rect);
+        }
+        if let Some(slot) = self.rects.iter_mut().find(|r| r.is_none()) {
+            *slot = Some(rect);
+            return;
+        }
+
+        // full, merge with the area growing the least
+        let growth = |r: NativeRect| area(union(r, rect)) - area(r);
+        let slot = self
+            .rects
+            .iter_mut()
+            .min_by_key(|r| r.map_or(usize::MAX, growth))
+            .unwrap();
+        let merged = union(slot.take().unwrap(), rect);
+        self.add(merged);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rects.iter().all(Option::is_none)
+    }
+
+    /// Empty the queue and return the areas.
+    pub(crate) fn take(&mut self) -> impl Iterator<Item = NativeRect> {
+        IntoIterator::into_iter(core::mem::take(&mut self.rects)).flatten()
+    }
+}
+
+fn overlaps(a: NativeRect, b: NativeRect) -> bool {
+    a.0 < b.2 && b.0 < a.2 && a.1 < b.3 && b.1 < a.3
+}
+
+fn union(a: NativeRect, b: NativeRect) -> NativeRect {
+    (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))
+}
+
+fn area(r: NativeRect) -> usize {
+    (r.2 - r.0) * (r.3 - r.1)
+}
+
+/// Set or clear bits `x0..x1` of a MSB-first packed row.
+fn fill_bits(row: &mut [u8], x0: usize, x1: usize, value: bool) {
+    if x0 >= x1 {
+        return;
+    }
+    let bits = if value { 0xff } else { 0x00 };
+    let first = x0 / 8;
+    let last = (x1 - 1) / 8;
+    let head = 0xff >> (x0 % 8);
+    let tail = 0xff << (7 - (x1 - 1) % 8);
+
+    if first == last {
+        apply_bits(&mut row[first], head & tail, bits);
+    } else {
+        apply_bits(&mut row[first], head, bits);
+        row[first + 1..last].fill(bits);
+        apply_bits(&mut row[last], tail, bits);
+    }
+}
+
+/// Read 8 bits of a MSB-first packed row starting at `bit`, bits outside the row read as 0.
+fn read_bits(row: &[u8], bit: isize) -> u8 {
+    let byte_at = |i: isize| {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| row.get(i))
+            .copied()
+            .unwrap_or(0)
+    };
+    let index = bit.div_euclid(8);
+    let shift = bit.rem_euclid(8) as u32;
+    if shift == 0 {
+        byte_at(index)
+    } else {
+        (byte_at(index) << shift) | (byte_at(index + 1) >> (8 - shift))
+    }
+}
+
+/// Replace the masked bits of `byte` with `bits`.
+#[inline]
+fn apply_bits(byte: &mut u8, mask: u8, bits: u8) {
+    *byte = (*byte & !mask) | (bits & mask);
+}
+
+#[cfg(feature = "nightly")]
 #[derive(Clone)]
 pub struct GrayFrameBuffer<SIZE: DisplaySize, C: GrayColor + GrayColorInBits>
 where
@@ -295,26 +1195,45 @@ where
     buf: [u8; SIZE::N * C::BITS_PER_PIXEL],
     rotation: DisplayRotation,
     mirroring: Mirroring,
+    inverted: bool,
 }
 
-impl<SIZE: DisplaySize, C: GrayColor + GrayColorInBits> GrayFrameBuffer<SIZE, C>
+#[cfg(feature = "nightly")]
+impl<SIZE: DisplaySize, C: GrayColor + GrayColorInBits> Default for GrayFrameBuffer<SIZE, C>
 where
     [(); SIZE::N]:,
     [(); SIZE::N * C::BITS_PER_PIXEL]:,
 {
-    pub fn new() -> Self {
-        let mut buf: [u8; SIZE::N * C::BITS_PER_PIXEL] = unsafe { mem::zeroed() };
-        buf.fill(0xff);
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
+#[cfg(feature = "nightly")]
+impl<SIZE: DisplaySize, C: GrayColor + GrayColorInBits> GrayFrameBuffer<SIZE, C>
+where
+    [(); SIZE::N]:,
+    [(); SIZE::N * C::BITS_PER_PIXEL]:,
+{
+    /// White, `const` to allow placing the framebuffer in a `static`.
+    pub const fn new() -> Self {
         Self {
-            buf,
+            buf: [0xff; SIZE::N * C::BITS_PER_PIXEL],
             rotation: DisplayRotation::Rotate0,
             mirroring: Mirroring::None,
+            inverted: false,
         }
     }
 
+    /// Create a white framebuffer storing `C::MAX_VALUE - luma`, for controllers treating 0 as white.
+    pub fn new_inverted() -> Self {
+        let mut this = Self::new();
+        this.set_inverted(true);
+        this
+    }
+
     pub fn fill(&mut self, color: BinaryColor) {
-        if color.is_on() {
+        if color.is_on() ^ self.inverted {
             self.buf.fill(0xff);
         } else {
             self.buf.fill(0x00);
@@ -335,6 +1254,15 @@ where
         self.mirroring = mirroring;
     }
 
+    /// Invert the stored luma values, the current content is inverted as well.
+    pub fn set_inverted(&mut self, inverted: bool) {
+        if self.inverted == inverted {
+            return;
+        }
+        self.inverted = inverted;
+        self.buf.iter_mut().for_each(|b| *b = !*b);
+    }
+
     pub fn as_bytes(&self) -> &[u8] {
         &self.buf
     }
@@ -360,47 +1288,104 @@ where
         C::from_u8(luma)
     }
 
-    pub fn set_pixel(&mut self, x: usize, y: usize, pixel: C) {
-        let (width, height) = match self.rotation {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (SIZE::WIDTH, SIZE::HEIGHT),
-            _ => (SIZE::HEIGHT, SIZE::WIDTH),
-        };
+    /// Extract the `columns` of native rows starting at `y` into `out` as 1bpp data, pixels below
+    /// `level` are black.
+    ///
+    /// Rows in `out` span the bytes covering `columns`, bits outside of `columns` are white.
+    pub(crate) fn layer_rows(&self, y: usize, columns: Range<usize>, level: u8, out: &mut [u8]) {
+        self.extract_rows(y, columns, out, |luma| luma >= level);
+    }
 
-        if x >= width || y >= height {
-            defmt::warn!("overflow set {},{}  {}", x, y, pixel.luma());
-            return;
-        }
+    /// Extract bit `plane` of the luma of native rows starting at `y` into `out` as 1bpp data.
+    pub(crate) fn plane_rows(&self, y: usize, plane: u8, out: &mut [u8]) {
+        self.extract_rows(y, 0..SIZE::WIDTH, out, |luma| luma & (1 << plane) != 0);
+    }
 
-        let (mut x, mut y) = match self.rotation {
-            DisplayRotation::Rotate0 => (x, y),
-            DisplayRotation::Rotate90 => (SIZE::WIDTH - y - 1, x),
-            DisplayRotation::Rotate180 => (SIZE::WIDTH - x - 1, SIZE::HEIGHT - y - 1),
-            DisplayRotation::Rotate270 => (y, SIZE::HEIGHT - x - 1),
-        };
+    /// Native area of `area`, as `(x0, y0, x1, y1)` with exclusive ends, clipped to the display.
+    pub(crate) fn native_rect(&self, area: &Rectangle) -> Option<(usize, usize, usize, usize)> {
+        Transform::new(SIZE::WIDTH, SIZE::HEIGHT, self.rotation, self.mirroring)
+            .transform_rect(area)
+    }
 
-        match self.mirroring {
-            Mirroring::Horizontal => {
-                x = SIZE::WIDTH - x - 1;
-            }
-            Mirroring::Vertical => {
-                y = SIZE::HEIGHT - y - 1;
+    /// Map native rows starting at `y` to 1bpp data, `bit` must be set for white.
+    fn extract_rows(
+        &self,
+        y: usize,
+        columns: Range<usize>,
+        out: &mut [u8],
+        bit: impl Fn(u8) -> bool,
+    ) {
+        let bpp = C::BITS_PER_PIXEL;
+        let (first, last) = (columns.start / 8, (columns.end - 1) / 8);
+        let row_len = (SIZE::WIDTH * bpp).div_ceil(8);
+        let mask = u64::MAX >> (64 - bpp);
+        let white = u64::MAX >> (64 - 8 * bpp);
+
+        for (row, dst) in out.chunks_exact_mut(last - first + 1).enumerate() {
+            let src = &self.buf[(y + row) * row_len..][..row_len];
+            for (i, byte) in (first..=last).zip(dst.iter_mut()) {
+                // 8 pixels always take exactly `bpp` bytes
+                let window = (0..bpp).fold(0u64, |w, k| {
+                    w << 8 | src.get(i * bpp + k).copied().unwrap_or(0) as u64
+                });
+                if window == white {
+                    *byte = 0xff;
+                    continue;
+                }
+                let mut bits = 0xff;
+                for k in columns.start.saturating_sub(i * 8)..(columns.end - i * 8).min(8) {
+                    let field = (window >> (8 * bpp - (k + 1) * bpp)) & mask;
+                    // luma is stored LSB first
+                    let luma = (field as u8).reverse_bits() >> (8 - bpp);
+                    if !bit(luma) {
+                        bits &= !(0x80 >> k);
+                    }
+                }
+                *byte = bits;
             }
-            Mirroring::Origin => {
-                x = SIZE::WIDTH - x - 1;
-                y = SIZE::HEIGHT - y - 1;
+        }
+    }
+
+    /// Write the buffer as a binary PGM (P5) image, with `C::MAX_VALUE` as white.
+    #[cfg(feature = "std")]
+    pub fn write_pgm<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        write!(
+            w,
+            "P5\n{} {}\n{}\n",
+            SIZE::WIDTH,
+            SIZE::HEIGHT,
+            C::MAX_VALUE
+        )?;
+        let mask = if self.inverted { C::MAX_VALUE } else { 0 };
+        for y in 0..SIZE::HEIGHT {
+            for x in 0..SIZE::WIDTH {
+                w.write_all(&[self.get_pixel_in_raw_pos(x, y).luma() ^ mask])?;
             }
-            _ => (),
         }
+        Ok(())
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, pixel: C) {
+        let transform = Transform::new(SIZE::WIDTH, SIZE::HEIGHT, self.rotation, self.mirroring);
+        let Some((x, y)) = transform.transform(x, y) else {
+            warn!("overflow set {},{}  {}", x, y, pixel.luma());
+            return;
+        };
 
         let width_in_bits = SIZE::WIDTH * C::BITS_PER_PIXEL;
         let width_in_byte = width_in_bits / 8 + (width_in_bits % 8 != 0) as usize;
+        let luma = if self.inverted {
+            C::MAX_VALUE - pixel.luma()
+        } else {
+            pixel.luma()
+        };
 
         for i in 0..C::BITS_PER_PIXEL {
             let bit_offset = x * C::BITS_PER_PIXEL + i;
             let byte_offset = width_in_byte * y + bit_offset / 8;
             let bit_offset = 7 - bit_offset % 8;
 
-            if pixel.luma() & (1 << i) != 0 {
+            if luma & (1 << i) != 0 {
                 self.buf.as_mut_slice()[byte_offset] |= 1 << bit_offset;
             } else {
                 self.buf.as_mut_slice()[byte_offset] &= !(1 << bit_offset);
@@ -409,15 +1394,300 @@ where
     }
 
     pub fn bounding_box(&self) -> Rectangle {
-        match self.rotation {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => Rectangle::new(
-                Point::zero(),
-                Size::new(SIZE::WIDTH as _, SIZE::HEIGHT as _),
-            ),
-            _ => Rectangle::new(
-                Point::zero(),
-                Size::new(SIZE::HEIGHT as _, SIZE::WIDTH as _),
-            ),
+        Transform::new(SIZE::WIDTH, SIZE::HEIGHT, self.rotation, self.mirroring).bounding_box()
+    }
+
+    /// Color of the pixel at logical `p`, white outside of the display.
+    fn pixel(&self, p: Point) -> C {
+        let (Ok(x), Ok(y)) = (usize::try_from(p.x), usize::try_from(p.y)) else {
+            return C::WHITE;
+        };
+        let transform = Transform::new(SIZE::WIDTH, SIZE::HEIGHT, self.rotation, self.mirroring);
+        let Some((x, y)) = transform.transform(x, y) else {
+            return C::WHITE;
+        };
+        let pixel = self.get_pixel_in_raw_pos(x, y);
+        if self.inverted {
+            C::from_u8(C::MAX_VALUE - pixel.luma())
+        } else {
+            pixel
+        }
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<SIZE: DisplaySize, C: GrayColor + GrayColorInBits> OriginDimensions
+    for GrayFrameBuffer<SIZE, C>
+where
+    [(); SIZE::N]:,
+    [(); SIZE::N * C::BITS_PER_PIXEL]:,
+{
+    fn size(&self) -> Size {
+        GrayFrameBuffer::bounding_box(self).size
+    }
+}
+
+/// Draw the buffer with `Image`, see the `FrameBuffer` implementation.
+#[cfg(feature = "nightly")]
+impl<SIZE: DisplaySize, C: GrayColor + GrayColorInBits> ImageDrawable for GrayFrameBuffer<SIZE, C>
+where
+    [(); SIZE::N]:,
+    [(); SIZE::N * C::BITS_PER_PIXEL]:,
+{
+    type Color = C;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let area = GrayFrameBuffer::bounding_box(self);
+        target.fill_contiguous(&area, area.points().map(|p| self.pixel(p)))
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let area = area.intersection(&GrayFrameBuffer::bounding_box(self));
+        target.fill_contiguous(
+            &Rectangle::new(Point::zero(), area.size),
+            area.points().map(|p| self.pixel(p)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const W: usize = 16;
+    const H: usize = 8;
+
+    const ROTATIONS: [DisplayRotation; 4] = [
+        DisplayRotation::Rotate0,
+        DisplayRotation::Rotate90,
+        DisplayRotation::Rotate180,
+        DisplayRotation::Rotate270,
+    ];
+    const MIRRORINGS: [Mirroring; 4] = [
+        Mirroring::None,
+        Mirroring::Horizontal,
+        Mirroring::Vertical,
+        Mirroring::Origin,
+    ];
+
+    fn transforms() -> impl Iterator<Item = Transform> {
+        ROTATIONS.iter().copied().flat_map(|rotation| {
+            MIRRORINGS
+                .iter()
+                .copied()
+                .map(move |mirroring| Transform::new(W, H, rotation, mirroring))
+        })
+    }
+
+    #[test]
+    fn corners() {
+        // native position of the logical origin, by rotation and then mirroring
+        let origins = [
+            [(0, 0), (W - 1, 0), (0, H - 1), (W - 1, H - 1)],
+            [(W - 1, 0), (0, 0), (W - 1, H - 1), (0, H - 1)],
+            [(W - 1, H - 1), (0, H - 1), (W - 1, 0), (0, 0)],
+            [(0, H - 1), (W - 1, H - 1), (0, 0), (W - 1, 0)],
+        ];
+        for (r, rotation) in ROTATIONS.iter().copied().enumerate() {
+            for (m, mirroring) in MIRRORINGS.iter().copied().enumerate() {
+                let t = Transform::new(W, H, rotation, mirroring);
+                let size = t.size();
+                let (w, h) = (size.width as usize, size.height as usize);
+                let (x, y) = origins[r][m];
+                let opposite = (W - 1 - x, H - 1 - y);
+
+                assert_eq!(
+                    t.transform(0, 0),
+                    Some((x, y)),
+                    "{:?} {:?}",
+                    rotation,
+                    mirroring
+                );
+                assert_eq!(t.transform(w - 1, h - 1), Some(opposite));
+                assert_eq!(t.transform(w, 0), None);
+                assert_eq!(t.transform(0, h), None);
+            }
+        }
+    }
+
+    #[test]
+    fn rotated_size() {
+        for t in transforms() {
+            let expected = match t.rotation {
+                DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => Size::new(W as _, H as _),
+                _ => Size::new(H as _, W as _),
+            };
+            assert_eq!(t.size(), expected);
+        }
+    }
+
+    #[test]
+    fn bijection() {
+        for t in transforms() {
+            let size = t.size();
+            let mut seen = [[false; W]; H];
+            for y in 0..size.height as usize {
+                for x in 0..size.width as usize {
+                    let (nx, ny) = t.transform(x, y).unwrap();
+                    assert!(nx < W && ny < H);
+                    assert!(!seen[ny][nx], "{:?}: ({}, {}) mapped twice", t, nx, ny);
+                    seen[ny][nx] = true;
+                }
+            }
+            assert!(seen.iter().flatten().all(|&s| s));
+        }
+    }
+
+    #[test]
+    fn rect_matches_pixels() {
+        let areas = [
+            Rectangle::new(Point::new(0, 0), Size::new(1, 1)),
+            Rectangle::new(Point::new(1, 2), Size::new(3, 4)),
+            Rectangle::new(Point::new(5, 0), Size::new(2, 7)),
+            Rectangle::new(Point::new(0, 0), Size::new(16, 16)),
+            // partly and fully outside of the display
+            Rectangle::new(Point::new(-3, -2), Size::new(6, 5)),
+            Rectangle::new(Point::new(6, 3), Size::new(20, 20)),
+            Rectangle::new(Point::new(30, 30), Size::new(2, 2)),
+            Rectangle::new(Point::new(2, 2), Size::zero()),
+        ];
+        for t in transforms() {
+            for area in areas.iter() {
+                let clipped = area.intersection(&t.bounding_box());
+                let mut bounds: Option<(usize, usize, usize, usize)> = None;
+                for p in clipped.points() {
+                    let (x, y) = t.transform(p.x as usize, p.y as usize).unwrap();
+                    bounds = Some(match bounds {
+                        None => (x, y, x + 1, y + 1),
+                        Some((x0, y0, x1, y1)) => {
+                            (x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1))
+                        }
+                    });
+                }
+                assert_eq!(t.transform_rect(area), bounds, "{:?} {:?}", t, area);
+                if let Some((x0, y0, x1, y1)) = bounds {
+                    // the native area holds exactly the pixels of the logical one
+                    assert_eq!(
+                        (x1 - x0) * (y1 - y0),
+                        clipped.size.width as usize * clipped.size.height as usize
+                    );
+                }
+            }
+        }
+    }
+
+    fn queued(rects: &[NativeRect]) -> DirtyRects {
+        let mut dirty = DirtyRects::default();
+        for &rect in rects {
+            dirty.add(rect);
+        }
+        dirty
+    }
+
+    #[test]
+    fn dirty_disjoint() {
+        let mut dirty = queued(&[(3, 0, 9, 1), (16, 8, 24, 12)]);
+        assert!(!dirty.is_empty());
+        // X is widened to whole bytes
+        assert!(dirty.take().eq([(0, 0, 16, 1), (16, 8, 24, 12)]));
+        assert!(dirty.is_empty());
+    }
+
+    #[test]
+    fn dirty_overlapping() {
+        let mut dirty = queued(&[(0, 0, 16, 4), (8, 2, 24, 8)]);
+        assert!(dirty.take().eq([(0, 0, 24, 8)]));
+
+        // the union of the first overlap reaches an area the new one did not touch
+        let mut dirty = queued(&[(0, 0, 8, 4), (16, 3, 24, 5), (4, 1, 20, 2)]);
+        assert!(dirty.take().eq([(0, 0, 24, 5)]));
+
+        // touching areas are kept apart, after byte alignment
+        let mut dirty = queued(&[(0, 0, 8, 2), (8, 0, 16, 2), (9, 1, 12, 3)]);
+        assert!(dirty.take().eq([(0, 0, 8, 2), (8, 0, 16, 3)]));
+    }
+
+    #[test]
+    fn dirty_full() {
+        let diagonal = |i: usize| (i * 16, i * 16, i * 16 + 8, i * 16 + 8);
+        let mut dirty = DirtyRects::default();
+        for i in 0..MAX_DIRTY_RECTS + 3 {
+            dirty.add(diagonal(i));
+        }
+        let mut rects = [None; MAX_DIRTY_RECTS + 1];
+        for (slot, rect) in rects.iter_mut().zip(dirty.take()) {
+            *slot = Some(rect);
+        }
+        let rects = &rects[..rects.iter().flatten().count()];
+        assert_eq!(rects.len(), MAX_DIRTY_RECTS);
+        // the areas added last are closest to each other and merged
+        assert!(rects.contains(&Some((112, 112, 168, 168))));
+        for i in 0..MAX_DIRTY_RECTS + 3 {
+            let r = diagonal(i);
+            assert!(
+                rects.iter().flatten().any(|&d| union(d, r) == d),
+                "{:?} lost",
+                r
+            );
+        }
+        for (i, a) in rects.iter().flatten().enumerate() {
+            for b in rects.iter().flatten().skip(i + 1) {
+                assert!(!overlaps(*a, *b), "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    fn bit(row: &[u8], x: isize) -> bool {
+        usize::try_from(x)
+            .ok()
+            .and_then(|x| row.get(x / 8).map(|b| b & (0x80 >> (x % 8)) != 0))
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn fill_bits_alignment() {
+        assert_eq!(
+            {
+                let mut row = [0u8; 3];
+                fill_bits(&mut row, 3, 13, true);
+                row
+            },
+            [0x1f, 0xf8, 0x00]
+        );
+        for value in [false, true] {
+            for x0 in 0..=24 {
+                for x1 in 0..=24 {
+                    let mut row = [0x5a; 3];
+                    fill_bits(&mut row, x0, x1, value);
+                    for x in 0..24 {
+                        let expected = if (x0..x1).contains(&x) {
+                            value
+                        } else {
+                            bit(&[0x5a; 3], x as isize)
+                        };
+                        assert_eq!(bit(&row, x as isize), expected, "{} {} {}", x0, x1, x);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn read_bits_alignment() {
+        let row = [0b1010_0000, 0xff, 0x3c];
+        assert_eq!(read_bits(&row, 0), 0xa0);
+        assert_eq!(read_bits(&row, 4), 0x0f);
+        assert_eq!(read_bits(&row, -4), 0x0a);
+        assert_eq!(read_bits(&row, 20), 0xc0);
+        for start in -10..30 {
+            let expected = (0..8).fold(0u8, |b, i| b << 1 | bit(&row, start + i) as u8);
+            assert_eq!(read_bits(&row, start), expected, "{}", start);
         }
     }
 }
@@ -0,0 +1,99 @@
+//! Drawing BMP images into framebuffers, via `tinybmp`.
+//!
+//! Only indexed 1bpp and 4bpp images are supported, colors are mapped by the luma of the color table.
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use tinybmp::{Bpp, ParseError, RawBmp, RowOrder};
+
+use super::FrameBuffer;
+#[cfg(feature = "nightly")]
+use {
+    super::{DisplaySize, GrayFrameBuffer},
+    crate::color::GrayColorInBits,
+};
+
+/// Luma of each color table entry, 0 to 255.
+fn color_table_luma(bmp: &RawBmp<'_>) -> [u8; 16] {
+    let mut luma = [0; 16];
+    if let Some(table) = bmp.color_table() {
+        for (i, l) in luma.iter_mut().enumerate() {
+            if let Some(c) = table.get(i as u32) {
+                *l = ((c.r() as u16 * 77 + c.g() as u16 * 150 + c.b() as u16 * 29) >> 8) as u8;
+            }
+        }
+    } else {
+        // no table, treat index as intensity
+        luma[1] = 0xff;
+    }
+    luma
+}
+
+fn parse(data: &[u8]) -> Result<RawBmp<'_>, ParseError> {
+    let bmp = RawBmp::from_slice(data)?;
+    match bmp.header().bpp {
+        Bpp::Bits1 | Bpp::Bits4 => Ok(bmp),
+        bpp => Err(ParseError::UnsupportedBpp(bpp.bits())),
+    }
+}
+
+impl<SIZE, B: AsRef<[u8]> + AsMut<[u8]>> FrameBuffer<SIZE, B> {
+    /// Draw a 1bpp or 4bpp BMP image with its top left corner at `position`.
+    ///
+    /// Light colors are drawn as `BinaryColor::On`. Position is in rotated coordinates.
+    /// Uncompressed 1bpp images are copied row by row with `blit`.
+    pub fn draw_bmp(&mut self, data: &[u8], position: Point) -> Result<(), ParseError> {
+        let bmp = parse(data)?;
+        let header = bmp.header();
+        let luma = color_table_luma(&bmp);
+
+        if header.bpp == Bpp::Bits1 && header.compression_method == tinybmp::CompressionMethod::Rgb
+        {
+            // rows are padded to 4 bytes
+            let stride = (header.image_size.width as usize).div_ceil(32) * 4;
+            let height = header.image_size.height as usize;
+            let row_size = Size::new(header.image_size.width, 1);
+            let invert = luma[0] > luma[1];
+
+            for (i, src) in bmp.image_data().chunks_exact(stride).enumerate() {
+                let y = match header.row_order {
+                    RowOrder::TopDown => i,
+                    _ => height - 1 - i,
+                };
+                let dest = position + Point::new(0, y as i32);
+                self.blit_inner(src, stride, dest, row_size, invert);
+            }
+            return Ok(());
+        }
+
+        let pixels = bmp.pixels().map(|p| {
+            let on = luma[p.color as usize & 0x0f] >= 0x80;
+            Pixel(position + p.position, BinaryColor::from(on))
+        });
+        let _ = self.draw_iter(pixels);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<SIZE: DisplaySize, C: GrayColor + GrayColorInBits> GrayFrameBuffer<SIZE, C>
+where
+    [(); SIZE::N]:,
+    [(); SIZE::N * C::BITS_PER_PIXEL]:,
+{
+    /// Draw a 1bpp or 4bpp BMP image with its top left corner at `position`.
+    ///
+    /// Color table entries are mapped to gray levels by luma. Position is in rotated coordinates.
+    pub fn draw_bmp(&mut self, data: &[u8], position: Point) -> Result<(), ParseError> {
+        let bmp = parse(data)?;
+        let luma = color_table_luma(&bmp);
+        let levels = luma.map(|l| C::from_u8(((l as u16 * C::MAX_VALUE as u16 + 127) / 255) as u8));
+
+        for p in bmp.pixels() {
+            let Point { x, y } = position + p.position;
+            if x >= 0 && y >= 0 {
+                self.set_pixel(x as usize, y as usize, levels[p.color as usize & 0x0f]);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+//! Drawing colors of any kind into 1bpp planes, as encoded by a `ColorEncoder`.
+
+use core::marker::PhantomData;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+
+use super::FrameBuffer;
+use crate::color::ColorEncoder;
+use crate::interface::DisplayError;
+
+/// `DrawTarget` writing each color as the bits `E` gives it in the `N` planes.
+///
+/// Every plane must have the same size and rotation.
+pub struct EncodedTarget<'a, SIZE, B, C, E, const N: usize> {
+    planes: [&'a mut FrameBuffer<SIZE, B>; N],
+    _encoder: PhantomData<(C, E)>,
+}
+
+impl<'a, SIZE, B, C, E, const N: usize> EncodedTarget<'a, SIZE, B, C, E, N>
+where
+    B: AsRef<[u8]> + AsMut<[u8]>,
+    C: PixelColor,
+    E: ColorEncoder<C, N>,
+{
+    pub fn new(planes: [&'a mut FrameBuffer<SIZE, B>; N]) -> Self {
+        Self {
+            planes,
+            _encoder: PhantomData,
+        }
+    }
+}
+
+impl<SIZE, B, C, E, const N: usize> Dimensions for EncodedTarget<'_, SIZE, B, C, E, N>
+where
+    B: AsRef<[u8]> + AsMut<[u8]>,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.planes
+            .first()
+            .map_or(Rectangle::zero(), |plane| plane.bounding_box())
+    }
+}
+
+impl<SIZE, B, C, E, const N: usize> DrawTarget for EncodedTarget<'_, SIZE, B, C, E, N>
+where
+    B: AsRef<[u8]> + AsMut<[u8]>,
+    C: PixelColor,
+    E: ColorEncoder<C, N>,
+{
+    type Color = C;
+    type Error = DisplayError;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            for (plane, bit) in self.planes.iter_mut().zip(E::encode(color)) {
+                plane.draw_iter([Pixel::<BinaryColor>(point, bit)])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        for (plane, bit) in self.planes.iter_mut().zip(E::encode(color)) {
+            plane.fill_solid(area, bit)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+//! Drawing with swapped colors into part of a display, e.g. white text on a black header.
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+
+/// `DrawTarget` drawing `BinaryColor::On` as `Off` and the other way around into `T`.
+///
+/// Unlike `FrameBuffer::set_inverted`, only what is drawn through the wrapper is affected.
+/// Fills are passed on as fills, so the fast paths of `T` are kept.
+pub struct InvertedTarget<'a, T> {
+    target: &'a mut T,
+}
+
+impl<'a, T: DrawTarget<Color = BinaryColor>> InvertedTarget<'a, T> {
+    pub fn new(target: &'a mut T) -> Self {
+        Self { target }
+    }
+
+    /// The wrapped target, drawn with the original colors.
+    pub fn inner(&mut self) -> &mut T {
+        self.target
+    }
+}
+
+impl<T: DrawTarget<Color = BinaryColor>> Dimensions for InvertedTarget<'_, T> {
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+impl<T: DrawTarget<Color = BinaryColor>> DrawTarget for InvertedTarget<'_, T> {
+    type Color = BinaryColor;
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(point, color.invert())),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.target
+            .fill_contiguous(area, colors.into_iter().map(BinaryColor::invert))
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.fill_solid(area, color.invert())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.clear(color.invert())
+    }
+}
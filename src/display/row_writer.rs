@@ -0,0 +1,122 @@
+//! Writing decoded images into framebuffers row by row.
+//!
+//! Decoders producing one row at a time, e.g. of BMP or PNG images, write it without going through
+//! `DrawTarget`: the position of the row in the buffer is computed once, and the pixels are then
+//! stepped through in native orientation.
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+
+use super::{DisplayRotation, FrameBuffer, Mirroring};
+
+/// Writer of an image into a `FrameBuffer`, one row after the other, see `FrameBuffer::row_writer`.
+///
+/// Parts outside the display are clipped, rows past the bottom of the image are dropped.
+pub struct RowWriter<'a, SIZE, B: AsRef<[u8]> + AsMut<[u8]>> {
+    fb: &'a mut FrameBuffer<SIZE, B>,
+    dest: Point,
+    size: Size,
+    row: u32,
+}
+
+impl<SIZE, B: AsRef<[u8]> + AsMut<[u8]>> FrameBuffer<SIZE, B> {
+    /// Write an image of `size` with its top left corner at `dest`, in rotated coordinates, row by row.
+    pub fn row_writer(&mut self, dest: Point, size: Size) -> RowWriter<'_, SIZE, B> {
+        RowWriter {
+            fb: self,
+            dest,
+            size,
+            row: 0,
+        }
+    }
+}
+
+impl<SIZE, B: AsRef<[u8]> + AsMut<[u8]>> RowWriter<'_, SIZE, B> {
+    /// Image row written next.
+    pub fn row(&self) -> u32 {
+        self.row
+    }
+
+    /// Rows left to write.
+    pub fn remaining(&self) -> u32 {
+        self.size.height - self.row
+    }
+
+    /// Leave the next `n` rows as they are, e.g. transparent ones.
+    pub fn skip_rows(&mut self, n: u32) {
+        self.row = self.row.saturating_add(n).min(self.size.height);
+    }
+
+    /// Write the next row, packed 1bpp MSB-first. Set bits are drawn as `BinaryColor::On`.
+    ///
+    /// In native orientation the row is copied byte by byte, like with `FrameBuffer::blit`.
+    pub fn write_row(&mut self, row: &[u8]) {
+        let native = matches!(
+            self.fb.orientation(),
+            (DisplayRotation::Rotate0, Mirroring::None)
+        );
+        if native {
+            if self.row < self.size.height {
+                let dest = self.dest + Point::new(0, self.row as i32);
+                let size = Size::new(self.size.width, 1);
+                self.fb.blit_inner(row, row.len(), dest, size, false);
+                self.row += 1;
+            }
+            return;
+        }
+        let bits = (0..self.size.width as usize)
+            .map(|x| row.get(x / 8).is_some_and(|b| b & (0x80 >> (x % 8)) != 0));
+        self.write_bits(bits);
+    }
+
+    /// Write the next row from colors, e.g. of a gray row after thresholding.
+    ///
+    /// Missing colors at the end of the row leave the pixels as they are.
+    pub fn write_colors<I: IntoIterator<Item = BinaryColor>>(&mut self, colors: I) {
+        self.write_bits(colors.into_iter().map(BinaryColor::is_on));
+    }
+
+    /// Write the pixels of the next row in image order.
+    fn write_bits<I: Iterator<Item = bool>>(&mut self, bits: I) {
+        if self.row >= self.size.height {
+            return;
+        }
+        let y = self.dest.y + self.row as i32;
+        self.row += 1;
+
+        let fb = &mut *self.fb;
+        let area = Rectangle::new(Point::new(self.dest.x, y), Size::new(self.size.width, 1))
+            .intersection(&fb.bounding_box());
+        let Some(last) = area.bottom_right() else {
+            return;
+        };
+        let (x0, x1, y) = (area.top_left.x as usize, last.x as usize, y as usize);
+        let transform = fb.transform();
+        let (Some(start), Some(end)) = (transform.transform(x0, y), transform.transform(x1, y))
+        else {
+            return;
+        };
+        // the row is a line in native orientation, walked one pixel at a time
+        let step = |a: usize, b: usize| (b as isize - a as isize).signum();
+        let (step_x, step_y) = (step(start.0, end.0), step(start.1, end.1));
+        let (mut x, mut y) = (start.0 as isize, start.1 as isize);
+
+        let width_in_byte = fb.width.div_ceil(8);
+        let (first_row, rows, inverted) = (fb.first_row, fb.rows(), fb.inverted);
+        let buf = fb.buf.as_mut();
+        let skip = (x0 as i32 - self.dest.x) as usize;
+        for pixel in bits.skip(skip).take(x1 - x0 + 1) {
+            // pixels outside of the current strip are dropped
+            if let Some(row) = (y as usize).checked_sub(first_row).filter(|&r| r < rows) {
+                let byte = &mut buf[row * width_in_byte + x as usize / 8];
+                let mask = 0x80 >> (x % 8);
+                if pixel ^ inverted {
+                    *byte |= mask;
+                } else {
+                    *byte &= !mask;
+                }
+            }
+            x += step_x;
+            y += step_y;
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Framebuffer in an external 23K256 SPI SRAM, as found on Adafruit EPD breakouts.
+//!
+//! The SRAM shares the SPI bus with the EPD, each as its own `SpiDevice`, e.g. from `embedded-hal-bus`.
+//! Every SRAM access is a complete transaction with its own chip select, so the bus is free
+//! for the EPD in between.
+
+use core::convert::TryInto;
+use core::marker::PhantomData;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+use embedded_hal::spi::{Error as _, Operation, SpiDevice};
+
+use super::{fill_bits, DisplayRotation, DisplaySize, Mirroring, Transform};
+use crate::interface::DisplayError;
+
+const READ: u8 = 0x03;
+const WRITE: u8 = 0x02;
+const WRITE_STATUS: u8 = 0x01;
+/// Sequential mode, reads and writes continue over page boundaries
+const SEQUENTIAL_MODE: u8 = 0x40;
+
+/// Capacity of the 23K256 in bytes.
+pub const SRAM_SIZE: usize = 32 * 1024;
+
+/// Largest number of bytes read or written at once.
+const CHUNK: usize = 256;
+
+/// Framebuffer stored in an external SPI SRAM instead of MCU memory.
+///
+/// Pixels are drawn with read-modify-write cycles over SPI, send the frame with
+/// `StreamingEpd::display_frame_from_sram`. Panels up to 2048 pixels wide are supported.
+pub struct SpiSramFrameBuffer<SIZE: DisplaySize, SPI> {
+    spi: SPI,
+    address: u16,
+    rotation: DisplayRotation,
+    mirroring: Mirroring,
+    _size: PhantomData<SIZE>,
+}
+
+impl<SIZE: DisplaySize, SPI: SpiDevice> SpiSramFrameBuffer<SIZE, SPI> {
+    /// Place the framebuffer in the SRAM at `address`, the content is kept as is.
+    pub fn new(spi: SPI, address: u16) -> Result<Self, DisplayError> {
+        if address as usize + SIZE::N > SRAM_SIZE {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        let mut this = Self {
+            spi,
+            address,
+            rotation: DisplayRotation::Rotate0,
+            mirroring: Mirroring::None,
+            _size: PhantomData,
+        };
+        this.spi
+            .write(&[WRITE_STATUS, SEQUENTIAL_MODE])
+            .map_err(|e| DisplayError::Spi(e.kind()))?;
+        Ok(this)
+    }
+
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+
+    pub fn set_rotation(&mut self, rotation: i32) {
+        self.rotation = match rotation {
+            0 => DisplayRotation::Rotate0,
+            90 => DisplayRotation::Rotate90,
+            180 => DisplayRotation::Rotate180,
+            270 => DisplayRotation::Rotate270,
+            _ => DisplayRotation::Rotate0,
+        };
+    }
+
+    pub fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.mirroring = mirroring;
+    }
+
+    /// Read raw 1bpp data starting at byte `offset` of the frame.
+    pub fn read(&mut self, offset: usize, buf: &mut [u8]) -> Result<(), DisplayError> {
+        let [hi, lo] = (self.address + offset as u16).to_be_bytes();
+        self.spi
+            .transaction(&mut [Operation::Write(&[READ, hi, lo]), Operation::Read(buf)])
+            .map_err(|e| DisplayError::Spi(e.kind()))
+    }
+
+    /// Write raw 1bpp data starting at byte `offset` of the frame.
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> Result<(), DisplayError> {
+        let [hi, lo] = (self.address + offset as u16).to_be_bytes();
+        self.spi
+            .transaction(&mut [Operation::Write(&[WRITE, hi, lo]), Operation::Write(data)])
+            .map_err(|e| DisplayError::Spi(e.kind()))
+    }
+
+    fn transform(&self) -> Transform {
+        Transform::new(SIZE::WIDTH, SIZE::HEIGHT, self.rotation, self.mirroring)
+    }
+
+    fn read_byte(&mut self, offset: usize) -> Result<u8, DisplayError> {
+        let mut byte = [0];
+        self.read(offset, &mut byte)?;
+        Ok(byte[0])
+    }
+}
+
+impl<SIZE: DisplaySize, SPI: SpiDevice> Dimensions for SpiSramFrameBuffer<SIZE, SPI> {
+    fn bounding_box(&self) -> Rectangle {
+        self.transform().bounding_box()
+    }
+}
+
+impl<SIZE: DisplaySize, SPI: SpiDevice> DrawTarget for SpiSramFrameBuffer<SIZE, SPI> {
+    type Color = BinaryColor;
+    type Error = DisplayError;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let width_in_byte = SIZE::WIDTH.div_ceil(8);
+        let transform = self.transform();
+        // consecutive pixels in the same byte are written back at once
+        let mut cached: Option<(usize, u8)> = None;
+
+        for Pixel(coord, color) in pixels.into_iter() {
+            let Ok((x, y)) = TryInto::<(u32, u32)>::try_into(coord) else {
+                continue;
+            };
+            let Some((x, y)) = transform.transform(x as usize, y as usize) else {
+                continue;
+            };
+
+            let offset = y * width_in_byte + x / 8;
+            let byte = match cached {
+                Some((cached_offset, byte)) if cached_offset == offset => byte,
+                _ => {
+                    if let Some((cached_offset, byte)) = cached {
+                        self.write(cached_offset, &[byte])?;
+                    }
+                    self.read_byte(offset)?
+                }
+            };
+            let bit = 0x80 >> (x % 8);
+            let byte = if color.is_on() {
+                byte | bit
+            } else {
+                byte & !bit
+            };
+            cached = Some((offset, byte));
+        }
+
+        if let Some((offset, byte)) = cached {
+            self.write(offset, &[byte])?;
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let Some((x0, y0, x1, y1)) = self.transform().transform_rect(area) else {
+            return Ok(());
+        };
+        let width_in_byte = SIZE::WIDTH.div_ceil(8);
+        let (first, last) = (x0 / 8, (x1 - 1) / 8);
+        let mut row = [0u8; CHUNK];
+        let row = &mut row[..last - first + 1];
+
+        for y in y0..y1 {
+            let offset = y * width_in_byte + first;
+            self.read(offset, row)?;
+            fill_bits(row, x0 - first * 8, x1 - first * 8, color.is_on());
+            self.write(offset, row)?;
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let chunk = [if color.is_on() { 0xff } else { 0x00 }; CHUNK];
+        let len = SIZE::WIDTH.div_ceil(8) * SIZE::HEIGHT;
+
+        for offset in (0..len).step_by(CHUNK) {
+            self.write(offset, &chunk[..CHUNK.min(len - offset)])?;
+        }
+        Ok(())
+    }
+}
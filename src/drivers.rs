@@ -1,33 +1,261 @@
-use crate::interface::DisplayInterface;
-use embedded_graphics::prelude::GrayColor;
+use crate::interface::{DisplayError, DisplayInterface};
+use embedded_graphics::{pixelcolor::Gray2, prelude::GrayColor};
 use embedded_hal::delay::DelayNs;
 
+pub use self::border::BorderColor;
+pub use self::dynamic::DynDriver;
+#[cfg(feature = "il3895")]
 pub use self::il3895::*;
+#[cfg(feature = "pd")]
 pub use self::pd::*;
+#[cfg(feature = "ssd1608")]
 pub use self::ssd1608::*;
+#[cfg(feature = "ssd1619a")]
 pub use self::ssd1619a::*;
+#[cfg(feature = "ssd1675b")]
 pub use self::ssd1675b::*;
+#[cfg(feature = "ssd1680")]
 pub use self::ssd1680::*;
+pub use self::status::{probe, ControllerId, OtpInfo, PanelState, Status};
+#[cfg(feature = "uc8176")]
 pub use self::uc8176::*;
+#[cfg(feature = "uc8179")]
 pub use self::uc8179::*;
+pub use self::voltage::DrivingVoltages;
 
+mod border;
+pub mod command;
+mod dynamic;
+#[cfg(feature = "il3895")]
 mod il3895;
+#[cfg(feature = "pd")]
 mod pd;
+#[cfg(feature = "ssd1608")]
 mod ssd1608;
+#[cfg(feature = "ssd1619a")]
 mod ssd1619a;
+#[cfg(feature = "ssd1675b")]
 mod ssd1675b;
+#[cfg(feature = "ssd1680")]
 mod ssd1680;
+mod status;
+#[cfg(feature = "uc8176")]
 mod uc8176;
+#[cfg(feature = "uc8179")]
 mod uc8179;
+pub mod uc_command;
+mod voltage;
 
+#[cfg(feature = "ssd1608")]
 pub type IL3820 = SSD1608;
 
+/// Low power mode entered by `Driver::sleep`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SleepMode {
+    /// Analog circuits powered off, the controller stays responsive
+    Normal,
+    /// Deep sleep retaining the RAM content, the same as `Normal` on UC controllers
+    DeepSleep1,
+    /// Deep sleep with the lowest current, the RAM content is lost
+    DeepSleep2,
+}
+
+impl SleepMode {
+    /// Whether the last frame is still in the controller RAM after waking up.
+    pub const fn retains_ram(self) -> bool {
+        !matches!(self, SleepMode::DeepSleep2)
+    }
+}
+
+/// Display RAM plane written by `MultiColorDriver::update_channel_frame`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Plane {
+    /// Black/white RAM, 0x24 on SSD and 0x10 on UC controllers
+    BlackWhite,
+    /// Red or yellow RAM, 0x26 on SSD and 0x13 on UC controllers
+    Chromatic,
+}
+
+/// Timing of each layer of the gray scale waveform, see `GrayScaleDriver::setup_gray_scale_waveform_timed`.
+///
+/// A layer drives the pixels below its level for `frames_per_level` frames, `repeats` times.
+/// The right values depend on the panel batch and the temperature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GrayScaleTiming {
+    /// Length of the driving phase in frames (TP), at least 1 or the waveform fails with `LutError::Empty`
+    pub frames_per_level: u8,
+    /// Runs of the driving phase, at least 1 (RP)
+    pub repeats: u8,
+}
+
+impl GrayScaleTiming {
+    pub const fn new(frames_per_level: u8, repeats: u8) -> Self {
+        Self {
+            frames_per_level,
+            repeats,
+        }
+    }
+}
+
+/// Deep sleep mode parameter of command 0x10 on SSD controllers.
+#[cfg_attr(
+    not(any(feature = "ssd1619a", feature = "ssd1675b", feature = "ssd1680")),
+    allow(dead_code)
+)]
+pub(crate) fn ssd_deep_sleep(mode: SleepMode) -> u8 {
+    match mode {
+        SleepMode::Normal => 0x00,
+        SleepMode::DeepSleep1 => 0x01,
+        SleepMode::DeepSleep2 => 0x03,
+    }
+}
+
+/// Write rows into a RAM window of SSD controllers with the Y counter moving up, the first row at the bottom.
+///
+/// The window is left set, the data entry mode is restored to X and Y increment.
+#[cfg_attr(
+    not(any(
+        feature = "ssd1608",
+        feature = "ssd1619a",
+        feature = "ssd1675b",
+        feature = "ssd1680"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn ssd_update_window_flipped<'a, DI: DisplayInterface, I>(
+    di: &mut DI,
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+    buffer: I,
+) -> Result<(), DisplayError>
+where
+    I: IntoIterator<Item = &'a u8>,
+{
+    use self::command::{Command, DataEntryMode};
+
+    let (x1, y1) = (x + width - 1, y + height - 1);
+    di.send_command_data(
+        Command::DataEntryMode as u8,
+        &[DataEntryMode::x_inc_y_dec().bits()],
+    )?;
+    di.send_command_data(Command::RamXRange as u8, &[(x >> 3) as u8, (x1 >> 3) as u8])?;
+    // the Y range starts at the bottom when decrementing
+    di.send_command_data(
+        Command::RamYRange as u8,
+        &[y1 as u8, (y1 >> 8) as u8, y as u8, (y >> 8) as u8],
+    )?;
+    di.send_command_data(Command::RamXCounter as u8, &[(x >> 3) as u8])?;
+    di.send_command_data(Command::RamYCounter as u8, &[y1 as u8, (y1 >> 8) as u8])?;
+
+    di.send_command(Command::WriteRam as u8)?;
+    di.send_data_from_iter(buffer)?;
+
+    di.send_command_data(
+        Command::DataEntryMode as u8,
+        &[DataEntryMode::x_inc_y_inc().bits()],
+    )?;
+    Ok(())
+}
+
+/// Most bytes read back at once by `ssd_read_ram`.
+const SSD_READ_CHUNK: usize = 64;
+
+/// Read `buffer` back from the B/W RAM of SSD controllers, at native `x`, a multiple of 8, and row `y`.
+#[cfg_attr(
+    not(any(feature = "ssd1619a", feature = "ssd1675b", feature = "ssd1680")),
+    allow(dead_code)
+)]
+pub(crate) fn ssd_read_ram<DI: DisplayInterface>(
+    di: &mut DI,
+    x: u16,
+    y: u16,
+    buffer: &mut [u8],
+) -> Result<(), DisplayError> {
+    use self::command::Command;
+
+    if buffer.len() > SSD_READ_CHUNK {
+        return Err(DisplayError::OutOfRange);
+    }
+    di.send_command_data(Command::ReadRamOption as u8, &[0x00])?;
+    di.send_command_data(Command::RamXCounter as u8, &[(x >> 3) as u8])?;
+    di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
+    di.send_command(Command::ReadRam as u8)?;
+
+    // one transaction, the address counter may not survive CS going high
+    let mut chunk = [0u8; 1 + SSD_READ_CHUNK];
+    let chunk = &mut chunk[..=buffer.len()];
+    di.read_data(chunk)?;
+    buffer.copy_from_slice(&chunk[1..]);
+    Ok(())
+}
+
+/// Read the internal temperature sensor of SSD controllers, in °C.
+///
+/// The sensor is only sampled by the load temperature sequence of 0x22/0x20, which loads the waveform
+/// from OTP as well, replacing one written with `update_waveform`.
+#[cfg_attr(
+    not(any(feature = "ssd1619a", feature = "ssd1675b", feature = "ssd1680")),
+    allow(dead_code)
+)]
+pub(crate) fn ssd_read_temperature<D: Driver, DI: DisplayInterface>(
+    di: &mut DI,
+) -> Result<i8, D::Error> {
+    use self::command::{Command, UpdateSequence};
+
+    di.send_command_data(Command::TemperatureSensorSelection as u8, &[0x80])?; // internal temperature sensor
+    di.send_command_data(
+        Command::DisplayUpdateControl2 as u8,
+        &[UpdateSequence::new()
+            .enable_clock()
+            .load_temperature()
+            .load_lut()
+            .disable_clock()
+            .bits()],
+    )?;
+    di.send_command(Command::MasterActivation as u8)?;
+    D::busy_wait(di)?;
+
+    // 12 bit, the first byte is the integer part
+    let mut temp = [0; 2];
+    di.send_command(Command::ReadTemperature as u8)?;
+    di.read_data(&mut temp)?;
+    Ok(temp[0] as i8)
+}
+
 pub trait Driver {
-    type Error;
+    type Error: From<DisplayError>;
 
     // Almost all EPD use bit 0 as black, but some use bit 1 as black
     const BLACK_BIT: bool = false;
 
+    /// Highest SPI clock for writing, in Hz, for the HAL setup. Reading is usually slower.
+    ///
+    /// Defaults to a conservative 4MHz for controllers without a documented limit.
+    const MAX_SPI_HZ: u32 = 4_000_000;
+
+    /// The controller writes rows bottom-up through its data entry mode, see `update_frame_window_flipped`.
+    const DATA_ENTRY_FLIP: bool = false;
+
+    /// Fast partial refreshes are available through `FastUpdateDriver`.
+    const SUPPORTS_PARTIAL: bool = false;
+
+    /// Gray levels are available through `GrayScaleDriver`.
+    const SUPPORTS_GRAYSCALE: bool = false;
+
+    /// Frame RAM planes, 2 for controllers of black/white/red panels implementing `MultiColorDriver`.
+    const PLANES: usize = 1;
+
+    /// Longest full refresh in ms, on the slowest panels of the controller, e.g. to schedule updates.
+    ///
+    /// Black/white/red panels take the longest, as the red particles move slowly.
+    const MAX_REFRESH_MS: u32 = 30_000;
+
     /// Wake UP and init
     fn wake_up<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
@@ -41,34 +269,183 @@ pub trait Driver {
     where
         I: IntoIterator<Item = &'a u8>;
 
+    /// Write whole rows of the frame starting at native row `y`, used to stream a frame in strips.
+    ///
+    /// Strips must be written in order, starting from row 0.
+    fn update_frame_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>;
+
+    /// Write rows into a RAM window of `width` x `height` pixels at native `x`, `y`.
+    ///
+    /// `x` and `width` are multiples of 8, the window is left set, `set_shape` restores the full frame.
+    fn update_frame_window<'a, DI: DisplayInterface, I>(
+        _di: &mut DI,
+        _x: u16,
+        _y: u16,
+        _width: u16,
+        _height: u16,
+        _buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        Err(DisplayError::Unsupported.into())
+    }
+
+    /// Write rows into a RAM window like `update_frame_window`, with the first row of `buffer` at the bottom.
+    ///
+    /// Flips the frame vertically in the controller, supported if `DATA_ENTRY_FLIP` is set.
+    fn update_frame_window_flipped<'a, DI: DisplayInterface, I>(
+        _di: &mut DI,
+        _x: u16,
+        _y: u16,
+        _width: u16,
+        _height: u16,
+        _buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        Err(DisplayError::Unsupported.into())
+    }
+
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error>;
 
+    /// Start the refresh of `turn_on_display` without waiting for it to complete, see `busy_wait`.
+    ///
+    /// Drivers that cannot split the refresh wait for it here.
+    fn start_refresh<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        Self::turn_on_display(di)
+    }
+
+    /// Read `buffer` back from the B/W RAM at native `x`, a multiple of 8, and row `y`, up to 64 bytes.
+    ///
+    /// Requires an interface that can read from the controller.
+    fn read_frame_row<DI: DisplayInterface>(
+        _di: &mut DI,
+        _x: u16,
+        _y: u16,
+        _buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
+
+    /// Read the controller status, e.g. to report the display health.
+    ///
+    /// Controllers with a status register fall back to the BUSY pin if the interface cannot read.
+    fn read_status<DI: DisplayInterface>(_di: &mut DI) -> Result<Status, Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
+
+    /// Check the panel for cracks or a broken connection, e.g. in a production test fixture.
+    ///
+    /// Requires an interface that can read from the controller.
+    fn detect_panel_break<DI: DisplayInterface>(_di: &mut DI) -> Result<PanelState, Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
+
+    /// Read the VCOM and waveform version programmed in OTP by the panel vendor.
+    ///
+    /// Requires an interface that can read from the controller.
+    fn read_otp_info<DI: DisplayInterface>(_di: &mut DI) -> Result<OtpInfo, Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
+
+    /// Write the VCOM register, replacing the value set by `wake_up` or loaded from OTP.
+    fn set_vcom<DI: DisplayInterface>(_di: &mut DI, _vcom: u8) -> Result<(), Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
+
+    /// Enter a low power mode, `wake_up` must be called before further use.
     fn sleep<DI: DisplayInterface, DELAY: DelayNs>(
         _di: &mut DI,
         _delay: &mut DELAY,
+        _mode: SleepMode,
     ) -> Result<(), Self::Error> {
         Ok(())
     }
 
+    /// Read the internal temperature sensor, in °C.
+    ///
+    /// Requires an interface that can read from the controller. On SSD controllers the sensor is sampled
+    /// by the load temperature sequence (0x22/0x20), which reloads the waveform from OTP: a LUT written
+    /// with `WaveformDriver::update_waveform` is lost and must be written again.
+    fn read_temperature<DI: DisplayInterface>(_di: &mut DI) -> Result<i8, Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
+
+    /// Set the ambient temperature in °C, on controllers relying on an external value for waveform timing.
+    fn set_temperature<DI: DisplayInterface>(
+        _di: &mut DI,
+        _celsius: i8,
+    ) -> Result<(), Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
+
+    /// Set the gate and source driving voltages, replacing the ones set by `wake_up`.
+    fn set_driving_voltages<DI: DisplayInterface>(
+        _di: &mut DI,
+        _voltages: &DrivingVoltages,
+    ) -> Result<(), Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
+
+    /// Select the border waveform, replacing the one set by `wake_up`.
+    fn set_border<DI: DisplayInterface>(
+        _di: &mut DI,
+        _color: BorderColor,
+    ) -> Result<(), Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
+
     // allow driver to override default busy wait
     fn busy_wait<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        while di.is_busy_on() {}
+        while di.is_busy_on() {
+            di.busy_idle();
+        }
         Ok(())
     }
 }
 
 pub trait MultiColorDriver: Driver {
-    fn init_multi_color<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+    /// Bit value of colored pixels in the chromatic plane.
+    const CHROMATIC_BIT: bool = true;
+
+    /// Bit value of white pixels in the black/white plane, colored pixels are white there too.
+    const WHITE_BIT: bool = !Self::BLACK_BIT;
+
+    fn init_multi_color<DI: DisplayInterface>(_di: &mut DI) -> Result<(), Self::Error> {
         Ok(())
     }
 
     fn update_channel_frame<'a, DI: DisplayInterface, I>(
         di: &mut DI,
-        channel: u8,
+        plane: Plane,
         buffer: I,
     ) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = &'a u8>;
+
+    /// Write rows of a RAM plane into a window, see `Driver::update_frame_window`.
+    fn update_channel_window<'a, DI: DisplayInterface, I>(
+        _di: &mut DI,
+        _plane: Plane,
+        _x: u16,
+        _y: u16,
+        _width: u16,
+        _height: u16,
+        _buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        Err(DisplayError::Unsupported.into())
+    }
 }
 
 pub trait WaveformDriver: Driver {
@@ -80,6 +457,18 @@ pub trait WaveformDriver: Driver {
         di: &mut DI,
         lut: &'static [u8],
     ) -> Result<(), Self::Error>;
+
+    /// `update_waveform` with the phases of `lut` scaled to `percent`, see `lut::Lut::scale_timing`.
+    ///
+    /// Compensates for slower particles at low temperatures without a table per temperature range,
+    /// e.g. 150 below 5 °C. `percent` 100 writes `lut` unchanged.
+    fn scale_timing<DI: DisplayInterface>(
+        _di: &mut DI,
+        _lut: &[u8],
+        _percent: u16,
+    ) -> Result<(), Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
 }
 
 pub trait FastUpdateDriver: WaveformDriver {
@@ -87,13 +476,73 @@ pub trait FastUpdateDriver: WaveformDriver {
     fn restore_normal_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error>;
 }
 
+/// Fast black/white refresh of tri-color panels, pixels set in the red RAM are not driven and stay red.
+///
+/// The waveform is used by `WaveformDriver::turn_on_display`, `Driver::turn_on_display` loads the OTP one again.
+pub trait FastBlackWhiteDriver: MultiColorDriver + WaveformDriver {
+    fn setup_fast_black_white_waveform<DI: DisplayInterface>(
+        di: &mut DI,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Explicit control of the booster and analog circuits, on controllers with separate power on and off commands.
+///
+/// `Driver::turn_on_display` powers on as needed, powering off after a refresh saves current between rare updates.
+pub trait PowerControl: Driver {
+    fn power_on<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error>;
+    fn power_off<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error>;
+}
+
 pub trait GrayScaleDriver<Color: GrayColor>: WaveformDriver {
-    fn init_as_gray_scale<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+    fn init_as_gray_scale<DI: DisplayInterface>(_di: &mut DI) -> Result<(), Self::Error> {
         Ok(())
     }
     // const LUT_FULL_UPDATE: &'static [u8];
     // const LUT_FRAME_UPDATE: &'static [u8];
     fn setup_gray_scale_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error>;
 
+    /// `setup_gray_scale_waveform` with the timing of the layers set by `timing`.
+    ///
+    /// Fails with `DisplayError::InvalidFormatError` for a timing the waveform cannot hold.
+    fn setup_gray_scale_waveform_timed<DI: DisplayInterface>(
+        _di: &mut DI,
+        _timing: GrayScaleTiming,
+    ) -> Result<(), Self::Error> {
+        Err(DisplayError::Unsupported.into())
+    }
+
     fn restore_normal_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error>;
 }
+
+/// Single refresh 4 level gray, the two bits of a pixel's luma select its waveform.
+///
+/// Bit 0 goes to the B/W RAM and bit 1 to the red RAM, so LUT row `n` drives the pixels of luma `n`.
+pub trait FourGrayDriver: GrayScaleDriver<Gray2> {
+    fn setup_four_gray_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error>;
+
+    /// Write whole rows of a RAM plane starting at native row `y`.
+    fn update_plane_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        plane: Plane,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>;
+
+    /// Write rows of a RAM plane into a window, see `Driver::update_frame_window`.
+    fn update_plane_window<'a, DI: DisplayInterface, I>(
+        _di: &mut DI,
+        _plane: Plane,
+        _x: u16,
+        _y: u16,
+        _width: u16,
+        _height: u16,
+        _buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        Err(DisplayError::Unsupported.into())
+    }
+}
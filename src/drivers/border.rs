@@ -0,0 +1,40 @@
+//! Border waveform selection.
+
+/// Color of the border around the active area of the panel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BorderColor {
+    White,
+    Black,
+    /// Border left floating, it keeps its current color
+    HiZ,
+}
+
+/// Border waveform control, command 0x3C of SSD controllers.
+#[cfg_attr(
+    not(any(
+        feature = "il3895",
+        feature = "ssd1608",
+        feature = "ssd1619a",
+        feature = "ssd1675b",
+        feature = "ssd1680"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn ssd_border(color: BorderColor) -> u8 {
+    match color {
+        BorderColor::White => 0b00_00_01_01, // GS transition, follow LUT1
+        BorderColor::Black => 0b00_00_01_00, // GS transition, follow LUT0
+        BorderColor::HiZ => 0b11_00_00_00,
+    }
+}
+
+/// VBD bits of the VCOM and data interval setting of UC controllers, for KW/KWR data polarity (DDX = 01).
+#[cfg_attr(not(any(feature = "uc8176", feature = "uc8179")), allow(dead_code))]
+pub(crate) fn uc_border(color: BorderColor) -> u8 {
+    match color {
+        BorderColor::White => 0b10,
+        BorderColor::Black => 0b01,
+        BorderColor::HiZ => 0b11,
+    }
+}
@@ -0,0 +1,201 @@
+//! Commands of the SSD16xx family, also used by IL3895.
+//!
+//! Codes not listed by every controller are noted with the ones that have them.
+//! The builders at the end compose the payloads of the init sequences.
+
+/// Command codes, sent as `Command::WriteRam as u8`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Command {
+    /// Gate count and scanning order
+    DriverOutputControl = 0x01,
+    /// VGH/VGL
+    GateDrivingVoltage = 0x03,
+    /// VSH1, VSH2 and VSL
+    SourceDrivingVoltage = 0x04,
+    /// Booster soft start phases
+    BoosterSoftStart = 0x0c,
+    DeepSleepMode = 0x10,
+    /// Address increment direction of the RAM counters
+    DataEntryMode = 0x11,
+    SwReset = 0x12,
+    /// Internal or external temperature sensor
+    TemperatureSensorSelection = 0x18,
+    /// Temperature used for the waveform when no sensor is read
+    WriteTemperature = 0x1a,
+    /// Temperature read by the last `DisplayUpdateControl2` sequence loading it
+    ReadTemperature = 0x1b,
+    /// Run the sequence selected by `DisplayUpdateControl2`, BUSY is high until it completes
+    MasterActivation = 0x20,
+    /// RAM content options, e.g. bypassing the red RAM
+    DisplayUpdateControl1 = 0x21,
+    /// Sequence run by `MasterActivation`
+    DisplayUpdateControl2 = 0x22,
+    /// Panel break detection on SSD1619A, the result is in `StatusBitRead`
+    PanelBreakDetection = 0x23,
+    /// B/W RAM
+    WriteRam = 0x24,
+    /// Red RAM, also the previous frame of fast updates
+    WriteRedRam = 0x26,
+    /// Read the RAM selected by `ReadRamOption`, the first byte is a dummy
+    ReadRam = 0x27,
+    /// ACVCOM timing, on SSD1619A and SSD1675B
+    AcvcomSetting = 0x2b,
+    WriteVcom = 0x2c,
+    /// VCOM and waveform version programmed in OTP, on SSD1619A, SSD1675B and SSD1680
+    ReadOtpDisplayOption = 0x2d,
+    /// HV ready and VCI detection flags, chip ID in the low bits
+    StatusBitRead = 0x2f,
+    WriteLut = 0x32,
+    DummyLinePeriod = 0x3a,
+    GateLineWidth = 0x3b,
+    BorderWaveform = 0x3c,
+    /// RAM read by `ReadRam`, 0 for B/W and 1 for red
+    ReadRamOption = 0x41,
+    /// RAM X start and end, in bytes
+    RamXRange = 0x44,
+    /// RAM Y start and end, in gates
+    RamYRange = 0x45,
+    /// RAM X address counter, in bytes
+    RamXCounter = 0x4e,
+    /// RAM Y address counter, in gates
+    RamYCounter = 0x4f,
+    /// Undocumented analog setting from the vendor init code, on SSD1619A and SSD1675B
+    AnalogBlockControl = 0x74,
+    /// Undocumented digital setting from the vendor init code, on SSD1619A and SSD1675B
+    DigitalBlockControl = 0x7e,
+    /// No operation, terminates a RAM write
+    Nop = 0x7f,
+    /// No operation of SSD1608 and IL3895, terminates a RAM write
+    LegacyNop = 0xff,
+}
+
+/// Driver output control (0x01) payload of controllers with up to 512 gates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DriverOutputControl {
+    gates: u16,
+    flags: u8,
+}
+
+impl DriverOutputControl {
+    /// Drive `gates` gate lines, scanning from G0 in order.
+    pub const fn new(gates: u16) -> Self {
+        Self { gates, flags: 0 }
+    }
+
+    /// Scan order of the gates: starting from G1 instead of G0 (GD), interlaced (SM),
+    /// and backwards from the last gate (TB).
+    pub const fn scan_order(self, from_g1: bool, interlaced: bool, backwards: bool) -> Self {
+        Self {
+            flags: (from_g1 as u8) << 2 | (interlaced as u8) << 1 | backwards as u8,
+            ..self
+        }
+    }
+
+    pub const fn bytes(self) -> [u8; 3] {
+        let mux = self.gates - 1;
+        [mux as u8, (mux >> 8) as u8, self.flags]
+    }
+}
+
+/// Data entry mode (0x11), direction of the RAM address counters after each byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DataEntryMode(u8);
+
+impl DataEntryMode {
+    pub const fn x_inc_y_inc() -> Self {
+        Self(0b011)
+    }
+
+    pub const fn x_dec_y_inc() -> Self {
+        Self(0b010)
+    }
+
+    pub const fn x_inc_y_dec() -> Self {
+        Self(0b001)
+    }
+
+    pub const fn x_dec_y_dec() -> Self {
+        Self(0b000)
+    }
+
+    /// Update the Y counter after each byte, and X at the end of a column (AM).
+    pub const fn y_first(self) -> Self {
+        Self(self.0 | 0b100)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+/// Display update control 2 (0x22), the steps run by `Command::MasterActivation`, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UpdateSequence(u8);
+
+impl UpdateSequence {
+    /// Run nothing.
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub const fn enable_clock(self) -> Self {
+        Self(self.0 | 0x80)
+    }
+
+    pub const fn enable_analog(self) -> Self {
+        Self(self.0 | 0x40)
+    }
+
+    /// Read the temperature sensor selected by `TemperatureSensorSelection`.
+    pub const fn load_temperature(self) -> Self {
+        Self(self.0 | 0x20)
+    }
+
+    /// Load the OTP waveform for the current temperature, replacing one written by `WriteLut`.
+    pub const fn load_lut(self) -> Self {
+        Self(self.0 | 0x10)
+    }
+
+    /// Select display mode 2 of controllers with two OTP waveforms, usually a fast one.
+    pub const fn mode2(self) -> Self {
+        Self(self.0 | 0x08)
+    }
+
+    /// Drive the panel with the waveform, the actual refresh.
+    pub const fn display(self) -> Self {
+        Self(self.0 | 0x04)
+    }
+
+    pub const fn disable_analog(self) -> Self {
+        Self(self.0 | 0x02)
+    }
+
+    pub const fn disable_clock(self) -> Self {
+        Self(self.0 | 0x01)
+    }
+
+    /// Enable the clock, then the analog circuits.
+    pub const fn power_on(self) -> Self {
+        self.enable_clock().enable_analog()
+    }
+
+    /// Disable the analog circuits, then the clock.
+    pub const fn power_off(self) -> Self {
+        self.disable_analog().disable_clock()
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for UpdateSequence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,244 @@
+//! Object safe driver, to select the controller at runtime.
+
+use embedded_hal::delay::DelayNs;
+
+use super::{BorderColor, Driver, DrivingVoltages, OtpInfo, PanelState, SleepMode, Status};
+use crate::interface::{DisplayError, DisplayInterface};
+
+/// Object safe counterpart of `Driver`, implemented by every driver over any `DisplayInterface`.
+///
+/// Firmware supporting several panels picks one at boot, e.g.
+/// `let driver: &dyn DynDriver<DI> = if id == 0 { &SSD1680 } else { &UC8176 };`,
+/// instead of monomorphizing the code for each of them.
+pub trait DynDriver<DI: DisplayInterface> {
+    /// Bit value of black pixels in the frame data, see `Driver::BLACK_BIT`.
+    fn black_bit(&self) -> bool;
+
+    /// Highest SPI clock, see `Driver::MAX_SPI_HZ`.
+    fn max_spi_hz(&self) -> u32;
+
+    /// Rows can be flipped by the controller, see `Driver::DATA_ENTRY_FLIP`.
+    fn data_entry_flip(&self) -> bool;
+
+    /// Fast partial refreshes are available, see `Driver::SUPPORTS_PARTIAL`.
+    fn supports_partial(&self) -> bool;
+
+    /// Gray levels are available, see `Driver::SUPPORTS_GRAYSCALE`.
+    fn supports_grayscale(&self) -> bool;
+
+    /// Frame RAM planes, see `Driver::PLANES`.
+    fn planes(&self) -> usize;
+
+    /// Longest full refresh in ms, see `Driver::MAX_REFRESH_MS`.
+    fn max_refresh_ms(&self) -> u32;
+
+    fn wake_up(&self, di: &mut DI, delay: &mut dyn DelayNs) -> Result<(), DisplayError>;
+
+    fn set_shape(&self, di: &mut DI, x: u16, y: u16) -> Result<(), DisplayError>;
+
+    fn update_frame(&self, di: &mut DI, buffer: &[u8]) -> Result<(), DisplayError>;
+
+    fn update_frame_rows(&self, di: &mut DI, y: u16, buffer: &[u8]) -> Result<(), DisplayError>;
+
+    fn update_frame_window(
+        &self,
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: &[u8],
+    ) -> Result<(), DisplayError>;
+
+    fn update_frame_window_flipped(
+        &self,
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: &[u8],
+    ) -> Result<(), DisplayError>;
+
+    fn turn_on_display(&self, di: &mut DI) -> Result<(), DisplayError>;
+
+    fn start_refresh(&self, di: &mut DI) -> Result<(), DisplayError>;
+
+    fn read_frame_row(
+        &self,
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        buffer: &mut [u8],
+    ) -> Result<(), DisplayError>;
+
+    fn read_status(&self, di: &mut DI) -> Result<Status, DisplayError>;
+
+    fn detect_panel_break(&self, di: &mut DI) -> Result<PanelState, DisplayError>;
+
+    fn read_otp_info(&self, di: &mut DI) -> Result<OtpInfo, DisplayError>;
+
+    fn set_vcom(&self, di: &mut DI, vcom: u8) -> Result<(), DisplayError>;
+
+    fn sleep(
+        &self,
+        di: &mut DI,
+        delay: &mut dyn DelayNs,
+        mode: SleepMode,
+    ) -> Result<(), DisplayError>;
+
+    fn read_temperature(&self, di: &mut DI) -> Result<i8, DisplayError>;
+
+    fn set_temperature(&self, di: &mut DI, celsius: i8) -> Result<(), DisplayError>;
+
+    fn set_driving_voltages(
+        &self,
+        di: &mut DI,
+        voltages: &DrivingVoltages,
+    ) -> Result<(), DisplayError>;
+
+    fn set_border(&self, di: &mut DI, color: BorderColor) -> Result<(), DisplayError>;
+
+    fn busy_wait(&self, di: &mut DI) -> Result<(), DisplayError>;
+}
+
+impl<D, DI> DynDriver<DI> for D
+where
+    D: Driver<Error = DisplayError>,
+    DI: DisplayInterface,
+{
+    fn black_bit(&self) -> bool {
+        D::BLACK_BIT
+    }
+
+    fn max_spi_hz(&self) -> u32 {
+        D::MAX_SPI_HZ
+    }
+
+    fn data_entry_flip(&self) -> bool {
+        D::DATA_ENTRY_FLIP
+    }
+
+    fn supports_partial(&self) -> bool {
+        D::SUPPORTS_PARTIAL
+    }
+
+    fn supports_grayscale(&self) -> bool {
+        D::SUPPORTS_GRAYSCALE
+    }
+
+    fn planes(&self) -> usize {
+        D::PLANES
+    }
+
+    fn max_refresh_ms(&self) -> u32 {
+        D::MAX_REFRESH_MS
+    }
+
+    fn wake_up(&self, di: &mut DI, mut delay: &mut dyn DelayNs) -> Result<(), DisplayError> {
+        D::wake_up(di, &mut delay)
+    }
+
+    fn set_shape(&self, di: &mut DI, x: u16, y: u16) -> Result<(), DisplayError> {
+        D::set_shape(di, x, y)
+    }
+
+    fn update_frame(&self, di: &mut DI, buffer: &[u8]) -> Result<(), DisplayError> {
+        D::update_frame(di, buffer)
+    }
+
+    fn update_frame_rows(&self, di: &mut DI, y: u16, buffer: &[u8]) -> Result<(), DisplayError> {
+        D::update_frame_rows(di, y, buffer)
+    }
+
+    fn update_frame_window(
+        &self,
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: &[u8],
+    ) -> Result<(), DisplayError> {
+        D::update_frame_window(di, x, y, width, height, buffer)
+    }
+
+    fn update_frame_window_flipped(
+        &self,
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: &[u8],
+    ) -> Result<(), DisplayError> {
+        D::update_frame_window_flipped(di, x, y, width, height, buffer)
+    }
+
+    fn turn_on_display(&self, di: &mut DI) -> Result<(), DisplayError> {
+        D::turn_on_display(di)
+    }
+
+    fn start_refresh(&self, di: &mut DI) -> Result<(), DisplayError> {
+        D::start_refresh(di)
+    }
+
+    fn read_frame_row(
+        &self,
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        buffer: &mut [u8],
+    ) -> Result<(), DisplayError> {
+        D::read_frame_row(di, x, y, buffer)
+    }
+
+    fn read_status(&self, di: &mut DI) -> Result<Status, DisplayError> {
+        D::read_status(di)
+    }
+
+    fn detect_panel_break(&self, di: &mut DI) -> Result<PanelState, DisplayError> {
+        D::detect_panel_break(di)
+    }
+
+    fn read_otp_info(&self, di: &mut DI) -> Result<OtpInfo, DisplayError> {
+        D::read_otp_info(di)
+    }
+
+    fn set_vcom(&self, di: &mut DI, vcom: u8) -> Result<(), DisplayError> {
+        D::set_vcom(di, vcom)
+    }
+
+    fn sleep(
+        &self,
+        di: &mut DI,
+        mut delay: &mut dyn DelayNs,
+        mode: SleepMode,
+    ) -> Result<(), DisplayError> {
+        D::sleep(di, &mut delay, mode)
+    }
+
+    fn read_temperature(&self, di: &mut DI) -> Result<i8, DisplayError> {
+        D::read_temperature(di)
+    }
+
+    fn set_temperature(&self, di: &mut DI, celsius: i8) -> Result<(), DisplayError> {
+        D::set_temperature(di, celsius)
+    }
+
+    fn set_driving_voltages(
+        &self,
+        di: &mut DI,
+        voltages: &DrivingVoltages,
+    ) -> Result<(), DisplayError> {
+        D::set_driving_voltages(di, voltages)
+    }
+
+    fn set_border(&self, di: &mut DI, color: BorderColor) -> Result<(), DisplayError> {
+        D::set_border(di, color)
+    }
+
+    fn busy_wait(&self, di: &mut DI) -> Result<(), DisplayError> {
+        D::busy_wait(di)
+    }
+}
@@ -1,10 +1,11 @@
 //! IL3895 driver.
 
 use crate::interface::{DisplayError, DisplayInterface};
-use embedded_graphics::pixelcolor::Gray4;
+use crate::lut::{self, Lut, Lut30};
 use embedded_hal::delay::DelayNs;
 
-use super::{Driver, FastUpdateDriver, GrayScaleDriver, MultiColorDriver, WaveformDriver};
+use super::command::{Command, UpdateSequence};
+use super::{border, BorderColor, Driver, FastUpdateDriver, SleepMode, WaveformDriver};
 
 /// 150 source outputs, 250 gate outputs, B/W
 /// 30 bytes LUT, format is different from SSD1608.
@@ -16,6 +17,9 @@ pub struct IL3895;
 impl Driver for IL3895 {
     type Error = DisplayError;
 
+    const SUPPORTS_PARTIAL: bool = true;
+    const MAX_REFRESH_MS: u32 = 4_000;
+
     fn wake_up<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         delay: &mut DELAY,
@@ -23,13 +27,13 @@ impl Driver for IL3895 {
         di.reset(delay, 200_000, 200_000);
         Self::busy_wait(di)?;
 
-        di.send_command_data(0x2C, &[0xA8])?;
+        di.send_command_data(Command::WriteVcom as u8, &[0xA8])?;
 
-        di.send_command_data(0x3a, &[0x1a])?; // set dummy line period
-        di.send_command_data(0x3b, &[0x08])?; // set gate line width
-        di.send_command_data(0x3c, &[0x63])?; // border waveform control
+        di.send_command_data(Command::DummyLinePeriod as u8, &[0x1a])?;
+        di.send_command_data(Command::GateLineWidth as u8, &[0x08])?;
+        di.send_command_data(Command::BorderWaveform as u8, &[0x63])?;
 
-        di.send_command_data(0x11, &[0b011])?; // data entry mode: default
+        di.send_command_data(Command::DataEntryMode as u8, &[0b011])?; // data entry mode: default
 
         // LUT is required
         #[rustfmt::skip]
@@ -49,19 +53,22 @@ impl Driver for IL3895 {
             // R3A_A, dummy line
             0x00,
         ];
-        di.send_command_data(0x32, &LUT_FULL_UPDATE)?;
+        di.send_command_data(Command::WriteLut as u8, &LUT_FULL_UPDATE)?;
 
         Ok(())
     }
 
     fn set_shape<DI: DisplayInterface>(di: &mut DI, x: u16, y: u16) -> Result<(), Self::Error> {
         // Driver Output control
-        di.send_command_data(0x01, &[((y - 1) & 0xff) as u8, 0])?;
+        di.send_command_data(
+            Command::DriverOutputControl as u8,
+            &[((y - 1) & 0xff) as u8, 0],
+        )?;
 
         // set ram x start/end
-        di.send_command_data(0x44, &[0, ((x - 1) >> 3) as u8])?;
+        di.send_command_data(Command::RamXRange as u8, &[0, ((x - 1) >> 3) as u8])?;
         // set ram y start/end
-        di.send_command_data(0x45, &[0, ((y - 1) & 0xff) as u8])?;
+        di.send_command_data(Command::RamYRange as u8, &[0, ((y - 1) & 0xff) as u8])?;
 
         Ok(())
     }
@@ -71,31 +78,98 @@ impl Driver for IL3895 {
         I: IntoIterator<Item = &'a u8>,
     {
         // set cursor
-        di.send_command_data(0x4E, &[0])?;
-        di.send_command_data(0x4f, &[0])?;
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0])?;
 
         // write ram
-        di.send_command(0x24)?;
+        di.send_command(Command::WriteRam as u8)?;
         di.send_data_from_iter(buffer)?;
 
-        di.send_command(0xff)?;
+        di.send_command(Command::LegacyNop as u8)?;
+        Ok(())
+    }
+
+    fn update_frame_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        // set cursor
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
+
+        // write ram
+        di.send_command(Command::WriteRam as u8)?;
+        di.send_data_from_iter(buffer)?;
+
+        di.send_command(Command::LegacyNop as u8)?;
+        Ok(())
+    }
+
+    fn update_frame_window<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        let (x1, y1) = (x + width - 1, y + height - 1);
+        di.send_command_data(Command::RamXRange as u8, &[(x >> 3) as u8, (x1 >> 3) as u8])?;
+        di.send_command_data(Command::RamYRange as u8, &[y as u8, y1 as u8])?;
+        di.send_command_data(Command::RamXCounter as u8, &[(x >> 3) as u8])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
+
+        di.send_command(Command::WriteRam as u8)?;
+        di.send_data_from_iter(buffer)?;
+
+        di.send_command(Command::LegacyNop as u8)?;
         Ok(())
     }
 
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        di.send_command_data(0x22, &[0xc4])?;
-        di.send_command(0x20)?;
-        di.send_command(0xff)?;
+        Self::start_refresh(di)?;
+        Self::busy_wait(di)
+    }
 
-        Self::busy_wait(di)?;
+    fn start_refresh<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command_data(
+            Command::DisplayUpdateControl2 as u8,
+            &[UpdateSequence::new().power_on().display().bits()],
+        )?;
+        di.send_command(Command::MasterActivation as u8)?;
+        di.send_command(Command::LegacyNop as u8)?;
+        Ok(())
+    }
+
+    fn set_border<DI: DisplayInterface>(
+        di: &mut DI,
+        color: BorderColor,
+    ) -> Result<(), Self::Error> {
+        di.send_command_data(Command::BorderWaveform as u8, &[border::ssd_border(color)])?;
+        Ok(())
+    }
+
+    fn set_vcom<DI: DisplayInterface>(di: &mut DI, vcom: u8) -> Result<(), Self::Error> {
+        di.send_command_data(Command::WriteVcom as u8, &[vcom])?;
         Ok(())
     }
 
     fn sleep<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         _delay: &mut DELAY,
+        mode: SleepMode,
     ) -> Result<(), Self::Error> {
-        di.send_command_data(0x10, &[0x01])?;
+        // only one deep sleep mode, RAM is retained
+        if mode != SleepMode::Normal {
+            di.send_command_data(Command::DeepSleepMode as u8, &[0x01])?;
+        }
         Ok(())
     }
 }
@@ -105,9 +179,19 @@ impl WaveformDriver for IL3895 {
         di: &mut DI,
         lut: &'static [u8],
     ) -> Result<(), Self::Error> {
-        di.send_command_data(0x32, lut)?;
+        lut::debug_validate::<Lut30>(lut)?;
+        di.send_command_data(Command::WriteLut as u8, lut)?;
         Ok(())
     }
+
+    fn scale_timing<DI: DisplayInterface>(
+        di: &mut DI,
+        lut: &[u8],
+        percent: u16,
+    ) -> Result<(), Self::Error> {
+        let lut = lut::scaled::<Lut30>(lut, percent)?;
+        di.send_command_data(Command::WriteLut as u8, lut.as_bytes())
+    }
 }
 
 impl FastUpdateDriver for IL3895 {
@@ -130,7 +214,7 @@ impl FastUpdateDriver for IL3895 {
             // R3A_A, dummy line
             0x00,
         ];
-        di.send_command_data(0x32, &LUT_FULL_UPDATE)?;
+        di.send_command_data(Command::WriteLut as u8, &LUT_FULL_UPDATE)?;
 
         Ok(())
     }
@@ -154,7 +238,7 @@ impl FastUpdateDriver for IL3895 {
             // R3A_A, dummy line
             0x00,
         ];
-        di.send_command_data(0x32, &LUT_FULL_UPDATE)?;
+        di.send_command_data(Command::WriteLut as u8, &LUT_FULL_UPDATE)?;
 
         Ok(())
     }
@@ -1,10 +1,12 @@
 use core::iter;
 
 use crate::interface::{DisplayError, DisplayInterface};
-use embedded_graphics::pixelcolor::Gray4;
 use embedded_hal::delay::DelayNs;
 
-use super::{Driver, FastUpdateDriver, GrayScaleDriver, MultiColorDriver, WaveformDriver};
+use super::uc_command::{resolution, Command, PanelSetting, DEEP_SLEEP_CHECK};
+use super::{
+    Driver, FastUpdateDriver, MultiColorDriver, Plane, PowerControl, SleepMode, WaveformDriver,
+};
 
 /// By guessing, it's like the IL0373, but with different resulution.
 /// Up to 160 source x 296 gate resolution
@@ -13,12 +15,20 @@ use super::{Driver, FastUpdateDriver, GrayScaleDriver, MultiColorDriver, Wavefor
 // https://github.com/rei-vilo/PDLS_EXT3_Basic/blob/main/src/Screen_EPD_EXT3.cpp
 pub struct PervasiveDisplays;
 
+/// Input temperature in °C set by `wake_up`, use `set_temperature` for the actual one.
+const DEFAULT_TEMPERATURE: i8 = 25;
+
 impl Driver for PervasiveDisplays {
     type Error = DisplayError;
 
+    const SUPPORTS_PARTIAL: bool = true;
+    const PLANES: usize = 2;
+
     fn busy_wait<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
         // negative logic
-        while !di.is_busy_on() {}
+        while !di.is_busy_on() {
+            di.busy_idle();
+        }
         Ok(())
     }
 
@@ -38,12 +48,14 @@ impl Driver for PervasiveDisplays {
         // 0b0000_0100: scan right
         // 0b0000_0000: scan right
         // ob0000_0001: Disable power
-        di.send_command_data(0x00, &[0xbf])?; // soft-reset
+        let psr = PanelSetting::new()
+            .resolution(0b10)
+            .lut_from_register(true)
+            .black_white(true);
+        di.send_command_data(Command::PanelSetting as u8, &[psr.bits()])?;
 
         delay.delay_us(5_000_u32);
-        di.send_command_data(0xe5, &[0x19]).unwrap(); // Input Temperature 0°C = 0x00, 22°C = 0x16, 25°C = 0x19
-
-        di.send_command_data(0xe0, &[0x02]).unwrap(); // Active Temperature
+        Self::set_temperature(di, DEFAULT_TEMPERATURE)?;
 
         #[rustfmt::skip]
         const LUT_VCOM: [u8; 44] = [
@@ -103,23 +115,23 @@ impl Driver for PervasiveDisplays {
         ];
 
         // LUTC
-        di.send_command_data(0x20, &LUT_VCOM)?;
+        di.send_command_data(Command::LutVcom as u8, &LUT_VCOM)?;
         // LUTWW
-        di.send_command_data(0x21, &LUT_WW)?;
+        di.send_command_data(Command::LutWw as u8, &LUT_WW)?;
         // LUTR
-        di.send_command_data(0x22, &LUT_BW)?; // LUT_R
-                                              // LUTW
-        di.send_command_data(0x23, &LUT_WB)?; // LUT_W
-                                              // LUTB
-        di.send_command_data(0x24, &LUT_BB)?; // LUT_B
+        di.send_command_data(Command::LutBw as u8, &LUT_BW)?; // LUT_R
+                                                              // LUTW
+        di.send_command_data(Command::LutWb as u8, &LUT_WB)?; // LUT_W
+                                                              // LUTB
+        di.send_command_data(Command::LutBb as u8, &LUT_BB)?; // LUT_B
 
-        di.send_command_data(0x25, &LUT_WW)?;
+        di.send_command_data(Command::LutBorder as u8, &LUT_WW)?;
 
         Ok(())
     }
 
     fn set_shape<DI: DisplayInterface>(di: &mut DI, x: u16, y: u16) -> Result<(), Self::Error> {
-        di.send_command_data(0x61, &[(x >> 8) as u8, x as u8, (y >> 8) as u8, y as u8])?;
+        di.send_command_data(Command::ResolutionSetting as u8, &resolution(x, y))?;
         Ok(())
     }
 
@@ -127,55 +139,95 @@ impl Driver for PervasiveDisplays {
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        di.send_command(0x10)?;
+        di.send_command(Command::DataStartTransmission1 as u8)?;
         let n = di.send_data_from_iter(buffer)?;
 
         // empty red channel
-        di.send_command(0x13)?;
-        di.send_data_from_iter(iter::repeat(&0).take(n))?;
+        di.send_command(Command::DataStartTransmission2 as u8)?;
+        di.send_data_from_iter(iter::repeat_n(&0, n))?;
+        Ok(())
+    }
+
+    fn update_frame_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        // RAM address can't be set, later strips continue the same data transmission
+        if y == 0 {
+            di.send_command(Command::DataStartTransmission1 as u8)?;
+        }
+        // red channel is left as is
+        di.send_data_from_iter(buffer)?;
         Ok(())
     }
 
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        di.send_command_data(0x04, &[0x00])?; // Power on
-        Self::busy_wait(di)?;
+        Self::start_refresh(di)?;
+        Self::busy_wait(di)
+    }
 
-        di.send_command_data(0x12, &[0x00])?; // display refresh
+    fn start_refresh<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command_data(Command::PowerOn as u8, &[0x00])?;
         Self::busy_wait(di)?;
 
+        di.send_command_data(Command::DisplayRefresh as u8, &[0x00])?;
+        Ok(())
+    }
+
+    /// Waveform timing of PD panels changes significantly with temperature, there is no internal sensor.
+    fn set_temperature<DI: DisplayInterface>(di: &mut DI, celsius: i8) -> Result<(), Self::Error> {
+        di.send_command_data(Command::ForceTemperature as u8, &[celsius as u8])?; // Input Temperature 0°C = 0x00, 22°C = 0x16, 25°C = 0x19
+        di.send_command_data(Command::CascadeSetting as u8, &[0x02])?; // Active Temperature
         Ok(())
     }
 
     fn sleep<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         delay: &mut DELAY,
+        mode: SleepMode,
     ) -> Result<(), Self::Error> {
-        di.send_command_data(0x02, &[0x00])?; // power off
+        di.send_command_data(Command::PowerOff as u8, &[0x00])?;
         delay.delay_us(5_000_u32);
         Self::busy_wait(di)?;
 
+        if mode == SleepMode::DeepSleep2 {
+            di.send_command_data(Command::DeepSleep as u8, &[DEEP_SLEEP_CHECK])?;
+        }
         Ok(())
     }
 }
 
+impl PowerControl for PervasiveDisplays {
+    fn power_on<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command_data(Command::PowerOn as u8, &[0x00])?;
+        Self::busy_wait(di)
+    }
+
+    fn power_off<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command_data(Command::PowerOff as u8, &[0x00])?;
+        Self::busy_wait(di)
+    }
+}
+
 impl MultiColorDriver for PervasiveDisplays {
     fn update_channel_frame<'a, DI: DisplayInterface, I>(
         di: &mut DI,
-        channel: u8,
+        plane: Plane,
         buffer: I,
     ) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        if channel == 0 {
-            di.send_command(0x10)?;
-            di.send_data_from_iter(buffer)?;
-        } else if channel == 1 {
-            di.send_command(0x13)?;
-            di.send_data_from_iter(buffer)?;
-        } else {
-            //
-        }
+        let command = match plane {
+            Plane::BlackWhite => Command::DataStartTransmission1,
+            Plane::Chromatic => Command::DataStartTransmission2,
+        };
+        di.send_command(command as u8)?;
+        di.send_data_from_iter(buffer)?;
         Ok(())
     }
 }
@@ -250,17 +302,17 @@ impl FastUpdateDriver for PervasiveDisplays {
         ];
 
         // LUTC
-        di.send_command_data(0x20, &LUT_VCOM)?;
+        di.send_command_data(Command::LutVcom as u8, &LUT_VCOM)?;
         // LUTWW
-        di.send_command_data(0x21, &LUT_WW)?;
+        di.send_command_data(Command::LutWw as u8, &LUT_WW)?;
         // LUTR
-        di.send_command_data(0x22, &LUT_BW)?; // LUT_R
-                                              // LUTW
-        di.send_command_data(0x23, &LUT_WB)?; // LUT_W
-                                              // LUTB
-        di.send_command_data(0x24, &LUT_BB)?; // LUT_B
+        di.send_command_data(Command::LutBw as u8, &LUT_BW)?; // LUT_R
+                                                              // LUTW
+        di.send_command_data(Command::LutWb as u8, &LUT_WB)?; // LUT_W
+                                                              // LUTB
+        di.send_command_data(Command::LutBb as u8, &LUT_BB)?; // LUT_B
 
-        di.send_command_data(0x25, &LUT_WW)?;
+        di.send_command_data(Command::LutBorder as u8, &LUT_WW)?;
         Ok(())
     }
 
@@ -324,17 +376,17 @@ impl FastUpdateDriver for PervasiveDisplays {
         ];
 
         // LUTC
-        di.send_command_data(0x20, &LUT_VCOM)?;
+        di.send_command_data(Command::LutVcom as u8, &LUT_VCOM)?;
         // LUTWW
-        di.send_command_data(0x21, &LUT_WW)?;
+        di.send_command_data(Command::LutWw as u8, &LUT_WW)?;
         // LUTR
-        di.send_command_data(0x22, &LUT_BW)?; // LUT_R
-                                              // LUTW
-        di.send_command_data(0x23, &LUT_WB)?; // LUT_W
-                                              // LUTB
-        di.send_command_data(0x24, &LUT_BB)?; // LUT_B
+        di.send_command_data(Command::LutBw as u8, &LUT_BW)?; // LUT_R
+                                                              // LUTW
+        di.send_command_data(Command::LutWb as u8, &LUT_WB)?; // LUT_W
+                                                              // LUTB
+        di.send_command_data(Command::LutBb as u8, &LUT_BB)?; // LUT_B
 
-        di.send_command_data(0x25, &LUT_WW)?;
+        di.send_command_data(Command::LutBorder as u8, &LUT_WW)?;
         Ok(())
     }
 }
@@ -4,9 +4,13 @@ use embedded_hal::delay::DelayNs;
 use crate::{
     color::Gray3,
     interface::{self, DisplayInterface},
+    lut::{self, Lut, Lut30},
 };
 
-use super::{Driver, GrayScaleDriver, WaveformDriver};
+use super::command::{Command, DataEntryMode, DriverOutputControl, UpdateSequence};
+use super::{
+    border, BorderColor, Driver, GrayScaleDriver, GrayScaleTiming, SleepMode, WaveformDriver,
+};
 
 /// B/W 240 x 320
 /// 30 bytes LUT
@@ -15,6 +19,10 @@ pub struct SSD1608;
 impl Driver for SSD1608 {
     type Error = interface::DisplayError;
 
+    const DATA_ENTRY_FLIP: bool = true;
+    const SUPPORTS_GRAYSCALE: bool = true;
+    const MAX_REFRESH_MS: u32 = 4_000;
+
     fn wake_up<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         delay: &mut DELAY,
@@ -22,24 +30,24 @@ impl Driver for SSD1608 {
         di.reset(delay, 200_000, 200_000);
         Self::busy_wait(di)?;
 
-        defmt::debug!("wake up");
+        debug!("wake up");
 
         // TODO: deep sleep?
         // di.send_command_data(0x10, &[0x00])?;
 
-        di.send_command(0x12)?; //swreset
+        di.send_command(Command::SwReset as u8)?;
         Self::busy_wait(di)?;
 
         // Booster Enable with Phase 1, Phase 2 and Phase 3 for soft start current setting.
-        di.send_command_data(0x0c, &[0xd7, 0xd6, 0x9d])?;
+        di.send_command_data(Command::BoosterSoftStart as u8, &[0xd7, 0xd6, 0x9d])?;
 
         // write VCOM reg
-        di.send_command_data(0x2c, &[0x7c])?; //a8
+        di.send_command_data(Command::WriteVcom as u8, &[0x7c])?; //a8
 
         // Set dummy line period
-        di.send_command_data(0x3a, &[0x1a])?;
+        di.send_command_data(Command::DummyLinePeriod as u8, &[0x1a])?;
         // Set Gate line width
-        di.send_command_data(0x3b, &[0x08])?;
+        di.send_command_data(Command::GateLineWidth as u8, &[0x08])?;
 
         // optional voltage control
         //di.send_command_data(0x04, &[0b0000])?;
@@ -49,14 +57,18 @@ impl Driver for SSD1608 {
         // 01 VSH => very black
         // 10 VSL => white
         // 11 HiZ => no change
-        di.send_command_data(0x3c, &[0b1_1_10_00_00])?; // border waveform control
+        di.send_command_data(Command::BorderWaveform as u8, &[0b11_10_00_00])?;
 
         // Data Entry mode,
         // Y increment, X increment
         // address counter is updated in the X direction. [POR]
-        di.send_command_data(0x11, &[0x03])?;
+        di.send_command_data(
+            Command::DataEntryMode as u8,
+            &[DataEntryMode::x_inc_y_inc().bits()],
+        )?;
 
         // https://github.com/TeXitoi/il3820/blob/master/src/lib.rs
+        #[allow(dead_code)]
         #[rustfmt::skip]
         const EPD_2_IN13_LUT_FULL_UPDATE: [u8; 30] = [
             0x50, 0xAA, 0x55, 0xAA, 0x11,
@@ -91,19 +103,25 @@ impl Driver for SSD1608 {
             0x00, 0x00
         ];
 
-        di.send_command_data(0x32, &EPD_2_IN13_LUT_PARTIAL_UPDATE)?;
+        di.send_command_data(Command::WriteLut as u8, &EPD_2_IN13_LUT_PARTIAL_UPDATE)?;
 
         Ok(())
     }
 
     fn set_shape<DI: DisplayInterface>(di: &mut DI, x: u16, y: u16) -> Result<(), Self::Error> {
         // Driver Output control
-        di.send_command_data(0x01, &[((y - 1) & 0xff) as u8, ((y - 1) >> 8) as u8, 0])?;
+        di.send_command_data(
+            Command::DriverOutputControl as u8,
+            &DriverOutputControl::new(y).bytes(),
+        )?;
 
         // set ram x start/end
-        di.send_command_data(0x44, &[0, ((x - 1) >> 3) as u8])?;
+        di.send_command_data(Command::RamXRange as u8, &[0, ((x - 1) >> 3) as u8])?;
         // set ram y start/end
-        di.send_command_data(0x45, &[0, 0, ((y - 1) & 0xff) as u8, ((y - 1) >> 8) as u8])?;
+        di.send_command_data(
+            Command::RamYRange as u8,
+            &[0, 0, ((y - 1) & 0xff) as u8, ((y - 1) >> 8) as u8],
+        )?;
         Ok(())
     }
 
@@ -112,30 +130,117 @@ impl Driver for SSD1608 {
         I: IntoIterator<Item = &'a u8>,
     {
         // set cursor
-        di.send_command_data(0x4E, &[0])?;
-        di.send_command_data(0x4f, &[0, 0])?;
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0, 0])?;
+
+        // write ram
+        di.send_command(Command::WriteRam as u8)?;
+        di.send_data_from_iter(buffer)?;
+
+        di.send_command(Command::LegacyNop as u8)?;
+        Ok(())
+    }
+
+    fn update_frame_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        // set cursor
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
 
         // write ram
-        di.send_command(0x24)?;
+        di.send_command(Command::WriteRam as u8)?;
+        di.send_data_from_iter(buffer)?;
+
+        di.send_command(Command::LegacyNop as u8)?;
+        Ok(())
+    }
+
+    fn update_frame_window<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        let (x1, y1) = (x + width - 1, y + height - 1);
+        di.send_command_data(Command::RamXRange as u8, &[(x >> 3) as u8, (x1 >> 3) as u8])?;
+        di.send_command_data(
+            Command::RamYRange as u8,
+            &[y as u8, (y >> 8) as u8, y1 as u8, (y1 >> 8) as u8],
+        )?;
+        di.send_command_data(Command::RamXCounter as u8, &[(x >> 3) as u8])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
+
+        di.send_command(Command::WriteRam as u8)?;
         di.send_data_from_iter(buffer)?;
 
-        di.send_command(0xff)?;
+        di.send_command(Command::LegacyNop as u8)?;
+        Ok(())
+    }
+
+    fn update_frame_window_flipped<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        super::ssd_update_window_flipped(di, x, y, width, height, buffer)?;
+        di.send_command(Command::LegacyNop as u8)?;
         Ok(())
     }
 
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        di.send_command_data(0x22, &[0xc4])?; // Display Update Control 2
-        di.send_command(0x20)?;
-        di.send_command(0xff)?;
-        Self::busy_wait(di)?;
+        Self::start_refresh(di)?;
+        Self::busy_wait(di)
+    }
+
+    fn start_refresh<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command_data(
+            Command::DisplayUpdateControl2 as u8,
+            &[UpdateSequence::new().power_on().display().bits()],
+        )?;
+        di.send_command(Command::MasterActivation as u8)?;
+        di.send_command(Command::LegacyNop as u8)?;
+        Ok(())
+    }
+
+    fn set_border<DI: DisplayInterface>(
+        di: &mut DI,
+        color: BorderColor,
+    ) -> Result<(), Self::Error> {
+        di.send_command_data(Command::BorderWaveform as u8, &[border::ssd_border(color)])?;
+        Ok(())
+    }
+
+    fn set_vcom<DI: DisplayInterface>(di: &mut DI, vcom: u8) -> Result<(), Self::Error> {
+        di.send_command_data(Command::WriteVcom as u8, &[vcom])?;
         Ok(())
     }
 
     fn sleep<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         _delay: &mut DELAY,
+        mode: SleepMode,
     ) -> Result<(), Self::Error> {
-        di.send_command_data(0x10, &[0x01])?;
+        // only one deep sleep mode, RAM is retained
+        if mode != SleepMode::Normal {
+            di.send_command_data(Command::DeepSleepMode as u8, &[0x01])?;
+        }
         Ok(())
     }
 }
@@ -146,6 +251,8 @@ pub struct SSD1608Fast;
 impl Driver for SSD1608Fast {
     type Error = interface::DisplayError;
 
+    const MAX_REFRESH_MS: u32 = 4_000;
+
     fn wake_up<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         delay: &mut DELAY,
@@ -166,7 +273,7 @@ impl Driver for SSD1608Fast {
             0x00, 0x00
         ];
         SSD1608::wake_up(di, delay)?;
-        di.send_command_data(0x32, &LUT_FAST_UPDATE)?;
+        di.send_command_data(Command::WriteLut as u8, &LUT_FAST_UPDATE)?;
         Ok(())
     }
 
@@ -181,6 +288,17 @@ impl Driver for SSD1608Fast {
         SSD1608::update_frame(di, buffer)
     }
 
+    fn update_frame_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        SSD1608::update_frame_rows(di, y, buffer)
+    }
+
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
         <SSD1608 as Driver>::turn_on_display(di)
     }
@@ -191,30 +309,33 @@ impl WaveformDriver for SSD1608 {
         di: &mut DI,
         lut: &'static [u8],
     ) -> Result<(), Self::Error> {
-        di.send_command_data(0x32, lut)
+        lut::debug_validate::<Lut30>(lut)?;
+        di.send_command_data(Command::WriteLut as u8, lut)
+    }
+
+    fn scale_timing<DI: DisplayInterface>(
+        di: &mut DI,
+        lut: &[u8],
+        percent: u16,
+    ) -> Result<(), Self::Error> {
+        let lut = lut::scaled::<Lut30>(lut, percent)?;
+        di.send_command_data(Command::WriteLut as u8, lut.as_bytes())
     }
 }
 
 impl GrayScaleDriver<Gray2> for SSD1608 {
     fn setup_gray_scale_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        #[rustfmt::skip]
-        const LUT_INCREMENTAL_DIV_2: [u8; 30] = [
-            // VS
-            // incremental update
-            0b00_01_00_01,
-                  0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
-            // TP
-            0x03, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00,
-
-            0x00, 0x00
-        ];
+        <Self as GrayScaleDriver<Gray2>>::setup_gray_scale_waveform_timed(
+            di,
+            GrayScaleTiming::new(3, 1),
+        )
+    }
 
-        Self::update_waveform(di, &LUT_INCREMENTAL_DIV_2)?;
-        Ok(())
+    fn setup_gray_scale_waveform_timed<DI: DisplayInterface>(
+        di: &mut DI,
+        timing: GrayScaleTiming,
+    ) -> Result<(), Self::Error> {
+        di.send_command_data(Command::WriteLut as u8, gray_scale_lut(timing)?.as_bytes())
     }
 
     fn restore_normal_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
@@ -237,26 +358,20 @@ impl GrayScaleDriver<Gray2> for SSD1608 {
 
 impl GrayScaleDriver<Gray3> for SSD1608 {
     fn setup_gray_scale_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        #[rustfmt::skip]
-        const LUT_INCREMENTAL_DIV_16: [u8; 30] = [
-            // VS
-            // incremental update
-            0b00_01_00_01,
-                  0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
-            // TP
-            0x01, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00,
-
-            0x00, 0x00
-        ];
+        <Self as GrayScaleDriver<Gray3>>::setup_gray_scale_waveform_timed(
+            di,
+            GrayScaleTiming::new(1, 1),
+        )
+    }
 
-        di.send_command_data(0x04, &[0b0000])?; // lower VSH/VSL
+    fn setup_gray_scale_waveform_timed<DI: DisplayInterface>(
+        di: &mut DI,
+        timing: GrayScaleTiming,
+    ) -> Result<(), Self::Error> {
+        let lut = gray_scale_lut(timing)?;
+        di.send_command_data(Command::SourceDrivingVoltage as u8, &[0b0000])?; // lower VSH/VSL
 
-        Self::update_waveform(di, &LUT_INCREMENTAL_DIV_16)?;
-        Ok(())
+        di.send_command_data(Command::WriteLut as u8, lut.as_bytes())
     }
 
     fn restore_normal_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
@@ -279,31 +394,25 @@ impl GrayScaleDriver<Gray3> for SSD1608 {
 
 impl GrayScaleDriver<Gray4> for SSD1608 {
     fn setup_gray_scale_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        #[rustfmt::skip]
-        const LUT_INCREMENTAL_DIV_16: [u8; 30] = [
-            // VS
-            // incremental update
-            // 10 clean
-            0b00_01_00_01,
-                  0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
-            // TP
-            0x01, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00
-        ];
+        <Self as GrayScaleDriver<Gray4>>::setup_gray_scale_waveform_timed(
+            di,
+            GrayScaleTiming::new(1, 1),
+        )
+    }
 
+    fn setup_gray_scale_waveform_timed<DI: DisplayInterface>(
+        di: &mut DI,
+        timing: GrayScaleTiming,
+    ) -> Result<(), Self::Error> {
+        let lut = gray_scale_lut(timing)?;
         // write VCOM reg
-        di.send_command_data(0x2c, &[0xb8])?; // Good to distinguish between gray levels
+        di.send_command_data(Command::WriteVcom as u8, &[0xb8])?; // Good to distinguish between gray levels
 
         // di.send_command_data(0x03, &[0b0000_0000])?; // VGH/VGL
-        di.send_command_data(0x04, &[0b0000])?; // lower VSH/VSL
-        di.send_command_data(0x3b, &[0b0000])?; // lowest gate line width
+        di.send_command_data(Command::SourceDrivingVoltage as u8, &[0b0000])?; // lower VSH/VSL
+        di.send_command_data(Command::GateLineWidth as u8, &[0b0000])?; // lowest gate line width
 
-        Self::update_waveform(di, &LUT_INCREMENTAL_DIV_16)?;
-
-        Ok(())
+        di.send_command_data(Command::WriteLut as u8, lut.as_bytes())
     }
 
     fn restore_normal_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
@@ -323,3 +432,22 @@ impl GrayScaleDriver<Gray4> for SSD1608 {
         Ok(())
     }
 }
+
+/// Incremental waveform of the gray scale layers, driving black in the first `timing.repeats` of the
+/// 20 phases, for `timing.frames_per_level` frames each. Checked with `lut::validate`, unlike the constant tables.
+fn gray_scale_lut(timing: GrayScaleTiming) -> Result<Lut30, interface::DisplayError> {
+    let phases = timing.repeats as usize;
+    if timing.frames_per_level > 0x0f || !(1..=20).contains(&phases) {
+        return Err(interface::DisplayError::InvalidFormatError);
+    }
+    let mut lut = [0; 30];
+    for phase in 0..phases {
+        // VS, one byte per phase
+        lut[phase] = 0b00_01_00_01;
+        // TP, 4 bits per phase, the even ones in the low nibble
+        lut[20 + phase / 2] |= timing.frames_per_level << (4 * (phase % 2));
+    }
+    let lut = Lut30::new(lut);
+    lut::validate(&lut).map_err(interface::DisplayError::InvalidLut)?;
+    Ok(lut)
+}
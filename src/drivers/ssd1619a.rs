@@ -14,10 +14,16 @@ LUT4: VCOM
 use core::iter;
 
 use crate::interface::{self, DisplayInterface};
-use embedded_graphics::pixelcolor::Gray4;
+use crate::lut::{self, Lut, Lut70};
+use embedded_graphics::pixelcolor::{Gray2, Gray4};
 use embedded_hal::delay::DelayNs;
 
-use super::{Driver, FastUpdateDriver, GrayScaleDriver, MultiColorDriver, WaveformDriver};
+use super::command::{Command, DataEntryMode, DriverOutputControl, UpdateSequence};
+use super::{
+    border, status, voltage, BorderColor, Driver, DrivingVoltages, FastUpdateDriver,
+    FourGrayDriver, GrayScaleDriver, GrayScaleTiming, MultiColorDriver, OtpInfo, PanelState, Plane,
+    SleepMode, Status, WaveformDriver,
+};
 
 /// Red/Black/White. 400 source outputs, 300 gate outputs,
 /// or Red/Black. 400 source outputs, 300 gate outputs.
@@ -27,6 +33,13 @@ pub struct SSD1619A;
 impl Driver for SSD1619A {
     type Error = interface::DisplayError;
 
+    const MAX_SPI_HZ: u32 = 20_000_000;
+    const DATA_ENTRY_FLIP: bool = true;
+    const SUPPORTS_PARTIAL: bool = true;
+    const SUPPORTS_GRAYSCALE: bool = true;
+    const PLANES: usize = 2;
+    const MAX_REFRESH_MS: u32 = 20_000;
+
     fn wake_up<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         delay: &mut DELAY,
@@ -34,20 +47,26 @@ impl Driver for SSD1619A {
         di.reset(delay, 200_000, 200_000);
         Self::busy_wait(di)?;
 
-        di.send_command(0x12)?; //swreset
+        di.send_command(Command::SwReset as u8)?;
         Self::busy_wait(di)?;
 
         // Set analogue then digital block control
-        di.send_command_data(0x74, &[0x54])?;
-        di.send_command_data(0x7e, &[0x3b])?;
+        di.send_command_data(Command::AnalogBlockControl as u8, &[0x54])?;
+        di.send_command_data(Command::DigitalBlockControl as u8, &[0x3b])?;
 
-        di.send_command_data(0x2b, &[0x03, 0x63])?; // reduce glitch under ACVCOM
+        di.send_command_data(Command::AcvcomSetting as u8, &[0x03, 0x63])?; // reduce glitch under ACVCOM
 
-        di.send_command_data(0x0c, &[0x8b, 0x9c, 0x96, 0x0f])?; // soft start setting
+        di.send_command_data(Command::BoosterSoftStart as u8, &[0x8b, 0x9c, 0x96, 0x0f])?;
 
-        di.send_command_data(0x01, &[0x2b, 0x01, 0x00])?; // Driver Output Control - set mux as 300
+        di.send_command_data(
+            Command::DriverOutputControl as u8,
+            &DriverOutputControl::new(300).bytes(),
+        )?;
 
-        di.send_command_data(0x11, &[0b11])?; // data entry mode, X inc, Y inc
+        di.send_command_data(
+            Command::DataEntryMode as u8,
+            &[DataEntryMode::x_inc_y_inc().bits()],
+        )?;
 
         // 0x44, 0x45, ram x,y start,end
         // di.send_command_data(0x03, &[0x20])?; // Gate Driving Voltage Control
@@ -61,14 +80,23 @@ impl Driver for SSD1619A {
         // 0b10_00_00, VCOM, black
         // 0b11_00_00, HiZ
         // 0b01_00_00, VSS
-        di.send_command_data(0x3C, &[0b11_00_00_00])?; // border wavefrom, HiZ
+        di.send_command_data(Command::BorderWaveform as u8, &[0b11_00_00_00])?; // border wavefrom, HiZ
 
         // use internal temp sensor
-        di.send_command_data(0x18, &[0x80])?;
+        di.send_command_data(Command::TemperatureSensorSelection as u8, &[0x80])?;
         // load temperature and waveform setting.
-        di.send_command_data(0x22, &[0xb9])?; // B1 or B9
-                                              // master activation
-        di.send_command(0x20)?;
+        di.send_command_data(
+            Command::DisplayUpdateControl2 as u8,
+            &[UpdateSequence::new()
+                .enable_clock()
+                .load_temperature()
+                .load_lut()
+                .mode2()
+                .disable_clock()
+                .bits()],
+        )?;
+        // master activation
+        di.send_command(Command::MasterActivation as u8)?;
         Self::busy_wait(di)?;
 
         Ok(())
@@ -76,11 +104,11 @@ impl Driver for SSD1619A {
 
     fn set_shape<DI: DisplayInterface>(di: &mut DI, x: u16, y: u16) -> Result<(), Self::Error> {
         // Set RAM X - address Start / End position
-        di.send_command_data(0x44, &[0x00, ((x - 1) >> 3) as u8])?;
+        di.send_command_data(Command::RamXRange as u8, &[0x00, ((x - 1) >> 3) as u8])?;
 
         // Set RAM Y - address Start / End position
         di.send_command_data(
-            0x45,
+            Command::RamYRange as u8,
             &[0x00, 0x00, ((y - 1) & 0xff) as u8, ((y - 1) >> 8) as u8],
         )?;
         Ok(())
@@ -90,73 +118,233 @@ impl Driver for SSD1619A {
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        di.send_command_data(0x4e, &[0])?; // x start
-        di.send_command_data(0x4f, &[0, 0])?; // y start
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0, 0])?;
 
-        di.send_command(0x24)?;
+        di.send_command(Command::WriteRam as u8)?;
         let n = di.send_data_from_iter(buffer)?;
 
         // fill R frame with zeros(white)
-        di.send_command_data(0x4e, &[0])?; // x start
-        di.send_command_data(0x4f, &[0, 0])?; // y start
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0, 0])?;
 
-        di.send_command(0x26)?;
-        di.send_data_from_iter(iter::repeat(&0).take(n))?;
+        di.send_command(Command::WriteRedRam as u8)?;
+        di.send_data_from_iter(iter::repeat_n(&0, n))?;
 
         Ok(())
     }
 
+    fn update_frame_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
+
+        di.send_command(Command::WriteRam as u8)?;
+        let n = di.send_data_from_iter(buffer)?;
+
+        // fill the same rows of R frame with zeros(white)
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
+
+        di.send_command(Command::WriteRedRam as u8)?;
+        di.send_data_from_iter(iter::repeat_n(&0, n))?;
+
+        Ok(())
+    }
+
+    fn update_frame_window<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        Self::update_channel_window(di, Plane::BlackWhite, x, y, width, height, buffer)
+    }
+
+    fn update_frame_window_flipped<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        super::ssd_update_window_flipped(di, x, y, width, height, buffer)?;
+        Ok(())
+    }
+
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        // 0xf7: always use in system LUT
-        di.send_command_data(0x22, &[0xf7])?;
-        di.send_command(0x20)?; // master activation
+        Self::start_refresh(di)?;
+        Self::busy_wait(di)
+    }
+
+    fn start_refresh<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        // always use in system LUT
+        di.send_command_data(
+            Command::DisplayUpdateControl2 as u8,
+            &[UpdateSequence::new()
+                .power_on()
+                .load_temperature()
+                .load_lut()
+                .display()
+                .power_off()
+                .bits()],
+        )?;
+        di.send_command(Command::MasterActivation as u8)?;
+        Ok(())
+    }
+
+    fn read_frame_row<DI: DisplayInterface>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        super::ssd_read_ram(di, x, y, buffer)
+    }
+
+    fn set_border<DI: DisplayInterface>(
+        di: &mut DI,
+        color: BorderColor,
+    ) -> Result<(), Self::Error> {
+        di.send_command_data(Command::BorderWaveform as u8, &[border::ssd_border(color)])?;
+        Ok(())
+    }
+
+    fn read_status<DI: DisplayInterface>(di: &mut DI) -> Result<Status, Self::Error> {
+        status::ssd_read_status(di)
+    }
+
+    fn detect_panel_break<DI: DisplayInterface>(di: &mut DI) -> Result<PanelState, Self::Error> {
+        di.send_command(Command::PanelBreakDetection as u8)?;
         Self::busy_wait(di)?;
+
+        let mut flags = [0];
+        di.send_command(Command::StatusBitRead as u8)?;
+        di.read_data(&mut flags)?;
+        if flags[0] & status::SSD_PANEL_BREAK_FLAG != 0 {
+            Ok(PanelState::Broken)
+        } else {
+            Ok(PanelState::Intact)
+        }
+    }
+
+    fn read_otp_info<DI: DisplayInterface>(di: &mut DI) -> Result<OtpInfo, Self::Error> {
+        status::ssd_read_otp_info(di)
+    }
+
+    fn set_vcom<DI: DisplayInterface>(di: &mut DI, vcom: u8) -> Result<(), Self::Error> {
+        di.send_command_data(Command::WriteVcom as u8, &[vcom])?;
         Ok(())
     }
 
     fn sleep<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         _delay: &mut DELAY,
+        mode: SleepMode,
     ) -> Result<(), Self::Error> {
-        di.send_command_data(0x10, &[0x01])?; // or 0x02 for deep sleep mode 2
+        di.send_command_data(Command::DeepSleepMode as u8, &[super::ssd_deep_sleep(mode)])?;
 
         // will be busy forever
         Ok(())
     }
+
+    fn read_temperature<DI: DisplayInterface>(di: &mut DI) -> Result<i8, Self::Error> {
+        super::ssd_read_temperature::<Self, _>(di)
+    }
+
+    fn set_driving_voltages<DI: DisplayInterface>(
+        di: &mut DI,
+        voltages: &DrivingVoltages,
+    ) -> Result<(), Self::Error> {
+        let gate = voltage::ssd_gate(voltages)?;
+        let source = voltage::ssd_source(voltages)?;
+        di.send_command_data(Command::GateDrivingVoltage as u8, &[gate])?;
+        di.send_command_data(Command::SourceDrivingVoltage as u8, &source)?;
+        Ok(())
+    }
 }
 
 impl MultiColorDriver for SSD1619A {
     fn update_channel_frame<'a, DI: DisplayInterface, I>(
         di: &mut DI,
-        channel: u8,
+        plane: Plane,
         buffer: I,
     ) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = &'a u8>,
     {
         // s start and y start
-        di.send_command_data(0x4e, &[0])?;
-        di.send_command_data(0x4f, &[0, 0])?;
-
-        if channel == 0 {
-            di.send_command(0x24)?;
-            di.send_data_from_iter(buffer)?;
-        } else if channel == 1 {
-            di.send_command(0x26)?;
-            di.send_data_from_iter(buffer)?;
-        } else {
-            // error
-        }
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0, 0])?;
+
+        let command = match plane {
+            Plane::BlackWhite => Command::WriteRam,
+            Plane::Chromatic => Command::WriteRedRam,
+        };
+        di.send_command(command as u8)?;
+        di.send_data_from_iter(buffer)?;
 
         Ok(())
     }
+
+    fn update_channel_window<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        plane: Plane,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        let (x1, y1) = (x + width - 1, y + height - 1);
+        di.send_command_data(Command::RamXRange as u8, &[(x >> 3) as u8, (x1 >> 3) as u8])?;
+        di.send_command_data(
+            Command::RamYRange as u8,
+            &[y as u8, (y >> 8) as u8, y1 as u8, (y1 >> 8) as u8],
+        )?;
+        di.send_command_data(Command::RamXCounter as u8, &[(x >> 3) as u8])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
+
+        let command = match plane {
+            Plane::BlackWhite => Command::WriteRam,
+            Plane::Chromatic => Command::WriteRedRam,
+        };
+        di.send_command(command as u8)?;
+        di.send_data_from_iter(buffer)?;
+        Ok(())
+    }
 }
 
 impl WaveformDriver for SSD1619A {
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        // 0xf7: always use in system LUT
-        di.send_command_data(0x22, &[0xc5])?;
-        di.send_command(0x20)?;
+        // keep the LUT written by update_waveform
+        di.send_command_data(
+            Command::DisplayUpdateControl2 as u8,
+            &[UpdateSequence::new()
+                .power_on()
+                .display()
+                .disable_clock()
+                .bits()],
+        )?;
+        di.send_command(Command::MasterActivation as u8)?;
         Self::busy_wait(di)?;
         Ok(())
     }
@@ -164,60 +352,140 @@ impl WaveformDriver for SSD1619A {
         di: &mut DI,
         lut: &'static [u8],
     ) -> Result<(), Self::Error> {
-        di.send_command_data(0x32, lut)
+        lut::debug_validate::<Lut70>(lut)?;
+        di.send_command_data(Command::WriteLut as u8, lut)
+    }
+
+    fn scale_timing<DI: DisplayInterface>(
+        di: &mut DI,
+        lut: &[u8],
+        percent: u16,
+    ) -> Result<(), Self::Error> {
+        let lut = lut::scaled::<Lut70>(lut, percent)?;
+        di.send_command_data(Command::WriteLut as u8, lut.as_bytes())
+    }
+}
+
+impl GrayScaleDriver<Gray2> for SSD1619A {
+    fn setup_gray_scale_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        // 3 layers, each one a third of the 15 frames used for Gray4
+        <Self as GrayScaleDriver<Gray2>>::setup_gray_scale_waveform_timed(
+            di,
+            GrayScaleTiming::new(5, 1),
+        )
+    }
+
+    fn setup_gray_scale_waveform_timed<DI: DisplayInterface>(
+        di: &mut DI,
+        timing: GrayScaleTiming,
+    ) -> Result<(), Self::Error> {
+        di.send_command_data(Command::WriteLut as u8, gray_scale_lut(timing)?.as_bytes())
+    }
+
+    fn restore_normal_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        <Self as GrayScaleDriver<Gray4>>::restore_normal_waveform(di)
     }
 }
 
 impl GrayScaleDriver<Gray4> for SSD1619A {
     fn setup_gray_scale_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        <Self as GrayScaleDriver<Gray4>>::setup_gray_scale_waveform_timed(
+            di,
+            GrayScaleTiming::new(1, 1),
+        )
+    }
+
+    fn setup_gray_scale_waveform_timed<DI: DisplayInterface>(
+        di: &mut DI,
+        timing: GrayScaleTiming,
+    ) -> Result<(), Self::Error> {
+        di.send_command_data(Command::WriteLut as u8, gray_scale_lut(timing)?.as_bytes())
+    }
+
+    fn restore_normal_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
         #[rustfmt::skip]
-        const LUT_INCREMENTAL_DIV_16: [u8; 70] = [
+        const LUT_FAST_UPDATE: [u8; 70] = [
             // VS
-            // 00 – VSS
-            // 01 – VSH1
-            // 10 – VSL
-            // 11 – VSH2
-            0b01_00_00_00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // L0 => B
-            0b00_00_00_00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // L1 => W
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0b10_10_10_10, 0b01_01_01_01, 0b01_00_00_00, 0x00, 0x00, 0x00, 0x00, // L0 => B
+            0b10_10_10_10, 0b01_01_01_01, 0b10_00_00_00, 0x00, 0x00, 0x00, 0x00, // L1 => W
+            0b00_00_00_00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // L2 => B
+            0b00_00_00_00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // L3 => W
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // L4
             // TP0                  RP[0]
-            0x01, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
+            0x0f, 0x00, 0x00, 0x00, 0x00,
+            0x14, 0x00, 0x00, 0x00, 0x00,
+            0x1a, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00,
         ];
-        Self::update_waveform(di, &LUT_INCREMENTAL_DIV_16)?;
+
+        Self::update_waveform(di, &LUT_FAST_UPDATE)?;
+
         Ok(())
     }
+}
 
-    fn restore_normal_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+impl FourGrayDriver for SSD1619A {
+    fn setup_four_gray_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
         #[rustfmt::skip]
-        const LUT_FAST_UPDATE: [u8; 70] = [
+        const LUT_FOUR_GRAY: [u8; 70] = [
             // VS
-            0b10_10_10_10, 0b01_01_01_01, 0b01_00_00_00, 0x00, 0x00, 0x00, 0x00, // L0 => B
-            0b10_10_10_10, 0b01_01_01_01, 0b10_00_00_00, 0x00, 0x00, 0x00, 0x00, // L1 => W
-            0b00_00_00_00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // L2 => B
-            0b00_00_00_00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // L3 => W
+            // clean to white, black, white, then darken by level
+            0b10_00_00_00, 0b01_00_00_00, 0b10_00_00_00, 0b01_01_01_00, 0x00, 0x00, 0x00, // L0 => B
+            0b10_00_00_00, 0b01_00_00_00, 0b10_00_00_00, 0b01_01_00_00, 0x00, 0x00, 0x00, // L1 => dark gray
+            0b10_00_00_00, 0b01_00_00_00, 0b10_00_00_00, 0b01_00_00_00, 0x00, 0x00, 0x00, // L2 => light gray
+            0b10_00_00_00, 0b01_00_00_00, 0b10_00_00_00, 0b00_00_00_00, 0x00, 0x00, 0x00, // L3 => W
             0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // L4
             // TP0                  RP[0]
             0x0f, 0x00, 0x00, 0x00, 0x00,
             0x14, 0x00, 0x00, 0x00, 0x00,
             0x1a, 0x00, 0x00, 0x00, 0x00,
-            0x00, 0x00, 0x00, 0x00, 0x00,
+            0x05, 0x05, 0x0a, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00,
             0x00, 0x00, 0x00, 0x00, 0x00,
         ];
+        Self::update_waveform(di, &LUT_FOUR_GRAY)?;
+        Ok(())
+    }
 
-        Self::update_waveform(di, &LUT_FAST_UPDATE)?;
-
+    fn update_plane_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        plane: Plane,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
+
+        let command = match plane {
+            Plane::BlackWhite => Command::WriteRam,
+            Plane::Chromatic => Command::WriteRedRam,
+        };
+        di.send_command(command as u8)?;
+        di.send_data_from_iter(buffer)?;
         Ok(())
     }
+
+    fn update_plane_window<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        plane: Plane,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        Self::update_channel_window(di, plane, x, y, width, height, buffer)
+    }
 }
 
 impl FastUpdateDriver for SSD1619A {
@@ -246,14 +514,14 @@ impl FastUpdateDriver for SSD1619A {
         Self::update_waveform(di, &LUT_FAST)?;
 
         // gate level: VGH
-        di.send_command_data(0x03, &[0x19])?; // POR, ok
+        di.send_command_data(Command::GateDrivingVoltage as u8, &[0x19])?; // POR, ok
 
         // source level: VSH1, VSH2, VSL
-        di.send_command_data(0x04, &[0x4b, 0xa8, 0x32])?;
+        di.send_command_data(Command::SourceDrivingVoltage as u8, &[0x4b, 0xa8, 0x32])?;
         // dummy line
-        di.send_command_data(0x3a, &[0x1a])?;
+        di.send_command_data(Command::DummyLinePeriod as u8, &[0x1a])?;
         // gate line
-        di.send_command_data(0x3b, &[0x0b])?;
+        di.send_command_data(Command::GateLineWidth as u8, &[0x0b])?;
 
         // VCOM
         // di.send_command_data(0x2c, &[0x78])?;
@@ -284,3 +552,22 @@ impl FastUpdateDriver for SSD1619A {
         Ok(())
     }
 }
+
+/// Incremental waveform of the gray scale layers, driving the pixels of L0 black for
+/// `timing.frames_per_level` frames, `timing.repeats` times.
+///
+/// 0 frames per level would write a waveform doing nothing, `lut::validate` fails it with `LutError::Empty`.
+fn gray_scale_lut(timing: GrayScaleTiming) -> Result<Lut70, interface::DisplayError> {
+    if timing.repeats == 0 {
+        return Err(interface::DisplayError::InvalidFormatError);
+    }
+    let mut lut = [0; 70];
+    // VS of L0 => B, VSH1 in the first phase
+    lut[0] = 0b01_00_00_00;
+    // TP[0A] and RP[0] of the first group
+    lut[35] = timing.frames_per_level;
+    lut[39] = timing.repeats - 1;
+    let lut = Lut70::new(lut);
+    lut::validate(&lut).map_err(interface::DisplayError::InvalidLut)?;
+    Ok(lut)
+}
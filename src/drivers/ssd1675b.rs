@@ -3,8 +3,13 @@
 use core::iter;
 use embedded_hal::delay::DelayNs;
 
-use super::{Driver, FastUpdateDriver, MultiColorDriver, WaveformDriver};
+use super::command::{Command, DataEntryMode, DriverOutputControl, UpdateSequence};
+use super::{
+    border, status, voltage, BorderColor, Driver, DrivingVoltages, FastBlackWhiteDriver,
+    FastUpdateDriver, MultiColorDriver, OtpInfo, Plane, SleepMode, Status, WaveformDriver,
+};
 use crate::interface::{DisplayError, DisplayInterface};
+use crate::lut::{self, Lut, Lut105};
 
 /// 160 Source x 296 Gate Red/Black/White.
 /// 100 bytes LUT. almost the same as SSD1619A.
@@ -13,6 +18,12 @@ pub struct SSD1675B;
 impl Driver for SSD1675B {
     type Error = DisplayError;
 
+    const MAX_SPI_HZ: u32 = 20_000_000;
+    const DATA_ENTRY_FLIP: bool = true;
+    const SUPPORTS_PARTIAL: bool = true;
+    const PLANES: usize = 2;
+    const MAX_REFRESH_MS: u32 = 20_000;
+
     fn wake_up<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         delay: &mut DELAY,
@@ -20,46 +31,61 @@ impl Driver for SSD1675B {
         di.reset(delay, 200_000, 200_000);
         Self::busy_wait(di)?;
 
-        di.send_command(0x12)?; //swreset
+        di.send_command(Command::SwReset as u8)?;
         Self::busy_wait(di)?;
 
-        di.send_command_data(0x74, &[0x54])?;
-        di.send_command_data(0x7e, &[0x3b])?;
+        di.send_command_data(Command::AnalogBlockControl as u8, &[0x54])?;
+        di.send_command_data(Command::DigitalBlockControl as u8, &[0x3b])?;
 
-        di.send_command_data(0x2b, &[0x03, 0x63])?; // reduce glitch under ACVCOM
+        di.send_command_data(Command::AcvcomSetting as u8, &[0x03, 0x63])?; // reduce glitch under ACVCOM
 
-        di.send_command_data(0x0c, &[0x8b, 0x9c, 0x96, 0x0f])?; // soft start setting
+        di.send_command_data(Command::BoosterSoftStart as u8, &[0x8b, 0x9c, 0x96, 0x0f])?;
 
-        di.send_command_data(0x01, &[0x2b, 0x01, 0x00])?; // Driver Output Control - set mux as 300
+        di.send_command_data(
+            Command::DriverOutputControl as u8,
+            &DriverOutputControl::new(300).bytes(),
+        )?;
 
-        di.send_command_data(0x11, &[0b11])?; // data entry mode, X inc, Y inc
+        di.send_command_data(
+            Command::DataEntryMode as u8,
+            &[DataEntryMode::x_inc_y_inc().bits()],
+        )?;
 
-        di.send_command_data(0x3C, &[0x01])?; // border wavefrom, HIZ
+        di.send_command_data(Command::BorderWaveform as u8, &[0x01])?; // border wavefrom, HIZ
 
         // use internal temp sensor
-        di.send_command_data(0x18, &[0x80])?;
+        di.send_command_data(Command::TemperatureSensorSelection as u8, &[0x80])?;
         // load temperature and waveform setting.
-        di.send_command_data(0x22, &[0xb9])?; // B1 or B9
-                                              // master activation
-        di.send_command(0x20)?;
+        di.send_command_data(
+            Command::DisplayUpdateControl2 as u8,
+            &[UpdateSequence::new()
+                .enable_clock()
+                .load_temperature()
+                .load_lut()
+                .mode2()
+                .disable_clock()
+                .bits()],
+        )?;
+        // master activation
+        di.send_command(Command::MasterActivation as u8)?;
         Self::busy_wait(di)?;
 
         // fill R frame with zeros(white)
-        di.send_command_data(0x4e, &[0])?; // x start
-        di.send_command_data(0x4f, &[0, 0])?; // y start
-        di.send_command(0x26)?;
-        di.send_data_from_iter(iter::repeat(&0).take(160 * 296 / 8))?;
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0, 0])?;
+        di.send_command(Command::WriteRedRam as u8)?;
+        di.send_data_from_iter(iter::repeat_n(&0, 160 * 296 / 8))?;
 
         Ok(())
     }
 
     fn set_shape<DI: DisplayInterface>(di: &mut DI, x: u16, y: u16) -> Result<(), Self::Error> {
         // Set RAM X - address Start / End position
-        di.send_command_data(0x44, &[0x00, ((x - 1) >> 3) as u8])?;
+        di.send_command_data(Command::RamXRange as u8, &[0x00, ((x - 1) >> 3) as u8])?;
 
         // Set RAM Y - address Start / End position
         di.send_command_data(
-            0x45,
+            Command::RamYRange as u8,
             &[0x00, 0x00, ((y - 1) & 0xff) as u8, ((y - 1) >> 8) as u8],
         )?;
         Ok(())
@@ -69,20 +95,133 @@ impl Driver for SSD1675B {
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        di.send_command_data(0x4e, &[0])?; // x start
-        di.send_command_data(0x4f, &[0, 0])?; // y start
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0, 0])?;
+
+        di.send_command(Command::WriteRam as u8)?;
+        di.send_data_from_iter(buffer)?;
+
+        Ok(())
+    }
+
+    fn update_frame_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
 
-        di.send_command(0x24)?;
+        di.send_command(Command::WriteRam as u8)?;
         di.send_data_from_iter(buffer)?;
 
         Ok(())
     }
 
+    fn update_frame_window<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        Self::update_channel_window(di, Plane::BlackWhite, x, y, width, height, buffer)
+    }
+
+    fn update_frame_window_flipped<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        super::ssd_update_window_flipped(di, x, y, width, height, buffer)?;
+        Ok(())
+    }
+
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        // 0xf7: always use in system LUT
-        di.send_command_data(0x22, &[0xf7])?;
-        di.send_command(0x20)?; // master activation
-        Self::busy_wait(di)?;
+        Self::start_refresh(di)?;
+        Self::busy_wait(di)
+    }
+
+    fn start_refresh<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        // always use in system LUT
+        di.send_command_data(
+            Command::DisplayUpdateControl2 as u8,
+            &[UpdateSequence::new()
+                .power_on()
+                .load_temperature()
+                .load_lut()
+                .display()
+                .power_off()
+                .bits()],
+        )?;
+        di.send_command(Command::MasterActivation as u8)?;
+        Ok(())
+    }
+
+    fn read_frame_row<DI: DisplayInterface>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        super::ssd_read_ram(di, x, y, buffer)
+    }
+
+    fn read_status<DI: DisplayInterface>(di: &mut DI) -> Result<Status, Self::Error> {
+        status::ssd_read_status(di)
+    }
+
+    fn read_otp_info<DI: DisplayInterface>(di: &mut DI) -> Result<OtpInfo, Self::Error> {
+        status::ssd_read_otp_info(di)
+    }
+
+    fn set_vcom<DI: DisplayInterface>(di: &mut DI, vcom: u8) -> Result<(), Self::Error> {
+        di.send_command_data(Command::WriteVcom as u8, &[vcom])?;
+        Ok(())
+    }
+
+    fn sleep<DI: DisplayInterface, DELAY: DelayNs>(
+        di: &mut DI,
+        _delay: &mut DELAY,
+        mode: SleepMode,
+    ) -> Result<(), Self::Error> {
+        di.send_command_data(Command::DeepSleepMode as u8, &[super::ssd_deep_sleep(mode)])?;
+        Ok(())
+    }
+
+    fn set_border<DI: DisplayInterface>(
+        di: &mut DI,
+        color: BorderColor,
+    ) -> Result<(), Self::Error> {
+        di.send_command_data(Command::BorderWaveform as u8, &[border::ssd_border(color)])?;
+        Ok(())
+    }
+
+    fn read_temperature<DI: DisplayInterface>(di: &mut DI) -> Result<i8, Self::Error> {
+        super::ssd_read_temperature::<Self, _>(di)
+    }
+
+    fn set_driving_voltages<DI: DisplayInterface>(
+        di: &mut DI,
+        voltages: &DrivingVoltages,
+    ) -> Result<(), Self::Error> {
+        let gate = voltage::ssd_gate(voltages)?;
+        let source = voltage::ssd_source(voltages)?;
+        di.send_command_data(Command::GateDrivingVoltage as u8, &[gate])?;
+        di.send_command_data(Command::SourceDrivingVoltage as u8, &source)?;
         Ok(())
     }
 }
@@ -90,34 +229,68 @@ impl Driver for SSD1675B {
 impl MultiColorDriver for SSD1675B {
     fn update_channel_frame<'a, DI: DisplayInterface, I>(
         di: &mut DI,
-        channel: u8,
+        plane: Plane,
         buffer: I,
     ) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = &'a u8>,
     {
         // s start and y start
-        di.send_command_data(0x4e, &[0])?;
-        di.send_command_data(0x4f, &[0, 0])?;
-
-        if channel == 0 {
-            di.send_command(0x24)?;
-            di.send_data_from_iter(buffer)?;
-        } else if channel == 1 {
-            di.send_command(0x26)?;
-            di.send_data_from_iter(buffer)?;
-        } else {
-            // error
-        }
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0, 0])?;
+
+        let command = match plane {
+            Plane::BlackWhite => Command::WriteRam,
+            Plane::Chromatic => Command::WriteRedRam,
+        };
+        di.send_command(command as u8)?;
+        di.send_data_from_iter(buffer)?;
+
+        Ok(())
+    }
+
+    fn update_channel_window<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        plane: Plane,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        let (x1, y1) = (x + width - 1, y + height - 1);
+        di.send_command_data(Command::RamXRange as u8, &[(x >> 3) as u8, (x1 >> 3) as u8])?;
+        di.send_command_data(
+            Command::RamYRange as u8,
+            &[y as u8, (y >> 8) as u8, y1 as u8, (y1 >> 8) as u8],
+        )?;
+        di.send_command_data(Command::RamXCounter as u8, &[(x >> 3) as u8])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
 
+        let command = match plane {
+            Plane::BlackWhite => Command::WriteRam,
+            Plane::Chromatic => Command::WriteRedRam,
+        };
+        di.send_command(command as u8)?;
+        di.send_data_from_iter(buffer)?;
         Ok(())
     }
 }
 
 impl WaveformDriver for SSD1675B {
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        di.send_command_data(0x22, &[0xc5])?;
-        di.send_command(0x20)?;
+        di.send_command_data(
+            Command::DisplayUpdateControl2 as u8,
+            &[UpdateSequence::new()
+                .power_on()
+                .display()
+                .disable_clock()
+                .bits()],
+        )?;
+        di.send_command(Command::MasterActivation as u8)?;
         Self::busy_wait(di)?;
         Ok(())
     }
@@ -125,7 +298,17 @@ impl WaveformDriver for SSD1675B {
         di: &mut DI,
         lut: &'static [u8],
     ) -> Result<(), Self::Error> {
-        di.send_command_data(0x32, lut)
+        lut::debug_validate::<Lut105>(lut)?;
+        di.send_command_data(Command::WriteLut as u8, lut)
+    }
+
+    fn scale_timing<DI: DisplayInterface>(
+        di: &mut DI,
+        lut: &[u8],
+        percent: u16,
+    ) -> Result<(), Self::Error> {
+        let lut = lut::scaled::<Lut105>(lut, percent)?;
+        di.send_command_data(Command::WriteLut as u8, lut.as_bytes())
     }
 }
 
@@ -174,3 +357,28 @@ impl FastUpdateDriver for SSD1675B {
         Ok(())
     }
 }
+
+impl FastBlackWhiteDriver for SSD1675B {
+    /// The fast waveform with the red LUTs emptied.
+    fn setup_fast_black_white_waveform<DI: DisplayInterface>(
+        di: &mut DI,
+    ) -> Result<(), Self::Error> {
+        #[rustfmt::skip]
+        const LUT: [u8; 105] = [
+            // VS
+            0x2A, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //1
+            0x05, 0x2A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //2
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //3
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //4
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //5
+
+            0x00, 0x02, 0x03, 0x0A, 0x00, 0x02, 0x06, 0x0A, 0x05, 0x00, //6
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //7
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //8
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //9
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //10
+            0x22, 0x22, 0x22, 0x22, 0x22,
+        ];
+        Self::update_waveform(di, &LUT[..])
+    }
+}
@@ -8,8 +8,13 @@
 use core::iter;
 use embedded_hal::delay::DelayNs;
 
-use super::{Driver, FastUpdateDriver, MultiColorDriver, WaveformDriver};
+use super::command::{Command, DataEntryMode, DriverOutputControl, UpdateSequence};
+use super::{
+    border, status, voltage, BorderColor, Driver, DrivingVoltages, FastBlackWhiteDriver,
+    FastUpdateDriver, MultiColorDriver, OtpInfo, Plane, SleepMode, Status, WaveformDriver,
+};
 use crate::interface::{DisplayError, DisplayInterface};
+use crate::lut::{self, Lut, Lut153};
 
 /// 176 Source x 296 Gate Red/Black/White
 pub struct SSD1680;
@@ -17,6 +22,12 @@ pub struct SSD1680;
 impl Driver for SSD1680 {
     type Error = DisplayError;
 
+    const MAX_SPI_HZ: u32 = 20_000_000;
+    const DATA_ENTRY_FLIP: bool = true;
+    const SUPPORTS_PARTIAL: bool = true;
+    const PLANES: usize = 2;
+    const MAX_REFRESH_MS: u32 = 20_000;
+
     fn wake_up<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         delay: &mut DELAY,
@@ -24,31 +35,37 @@ impl Driver for SSD1680 {
         di.reset(delay, 10_000, 10_000); // HW Reset
         Self::busy_wait(di)?;
 
-        di.send_command(0x12)?; // swreset
+        di.send_command(Command::SwReset as u8)?;
         Self::busy_wait(di)?;
 
-        di.send_command_data(0x01, &[0x27, 0x01, 0x00])?; // Driver output control
+        di.send_command_data(
+            Command::DriverOutputControl as u8,
+            &DriverOutputControl::new(296).bytes(),
+        )?;
 
-        di.send_command_data(0x11, &[0b0_11])?; // data entry mode
+        di.send_command_data(
+            Command::DataEntryMode as u8,
+            &[DataEntryMode::x_inc_y_inc().bits()],
+        )?;
 
-        di.send_command_data(0x21, &[0x00, 0x80])?; // Display update control
+        di.send_command_data(Command::DisplayUpdateControl1 as u8, &[0x00, 0x80])?;
 
         // fill R frame with zeros(white)
-        di.send_command_data(0x4e, &[0])?; // x start
-        di.send_command_data(0x4f, &[0, 0])?; // y start
-        di.send_command(0x26)?;
-        di.send_data_from_iter(iter::repeat(&0).take(176 * 296 / 8))?;
-        di.send_command(0x7f)?; // NOP
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0, 0])?;
+        di.send_command(Command::WriteRedRam as u8)?;
+        di.send_data_from_iter(iter::repeat_n(&0, 176 * 296 / 8))?;
+        di.send_command(Command::Nop as u8)?;
 
         Ok(())
     }
 
     fn set_shape<DI: DisplayInterface>(di: &mut DI, x: u16, y: u16) -> Result<(), Self::Error> {
         // Set RAM X - address Start / End position
-        di.send_command_data(0x44, &[0x00, ((x - 1) >> 3) as u8])?;
+        di.send_command_data(Command::RamXRange as u8, &[0x00, ((x - 1) >> 3) as u8])?;
         // Set RAM Y - address Start / End position
         di.send_command_data(
-            0x45,
+            Command::RamYRange as u8,
             &[0x00, 0x00, ((y - 1) & 0xff) as u8, ((y - 1) >> 8) as u8],
         )?;
         Ok(())
@@ -58,64 +75,203 @@ impl Driver for SSD1680 {
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        di.send_command_data(0x4e, &[0])?; // x start
-        di.send_command_data(0x4f, &[0, 0])?; // y start
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0, 0])?;
 
-        di.send_command(0x24)?;
+        di.send_command(Command::WriteRam as u8)?;
         di.send_data_from_iter(buffer)?;
 
         Ok(())
     }
 
+    fn update_frame_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
+
+        di.send_command(Command::WriteRam as u8)?;
+        di.send_data_from_iter(buffer)?;
+
+        Ok(())
+    }
+
+    fn update_frame_window<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        Self::update_channel_window(di, Plane::BlackWhite, x, y, width, height, buffer)
+    }
+
+    fn update_frame_window_flipped<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        super::ssd_update_window_flipped(di, x, y, width, height, buffer)?;
+        Ok(())
+    }
+
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        Self::start_refresh(di)?;
+        Self::busy_wait(di)
+    }
+
+    fn start_refresh<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
         // default
-        di.send_command_data(0x22, &[0xf7])?;
+        di.send_command_data(
+            Command::DisplayUpdateControl2 as u8,
+            &[UpdateSequence::new()
+                .power_on()
+                .load_temperature()
+                .load_lut()
+                .display()
+                .power_off()
+                .bits()],
+        )?;
         // di.send_command_data(0x22, &[0xc7])?;
-        di.send_command(0x20)?;
-        Self::busy_wait(di)?;
+        di.send_command(Command::MasterActivation as u8)?;
+        Ok(())
+    }
 
+    fn read_frame_row<DI: DisplayInterface>(
+        di: &mut DI,
+        x: u16,
+        y: u16,
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        super::ssd_read_ram(di, x, y, buffer)
+    }
+
+    fn set_border<DI: DisplayInterface>(
+        di: &mut DI,
+        color: BorderColor,
+    ) -> Result<(), Self::Error> {
+        di.send_command_data(Command::BorderWaveform as u8, &[border::ssd_border(color)])?;
+        Ok(())
+    }
+
+    fn read_status<DI: DisplayInterface>(di: &mut DI) -> Result<Status, Self::Error> {
+        status::ssd_read_status(di)
+    }
+
+    fn read_otp_info<DI: DisplayInterface>(di: &mut DI) -> Result<OtpInfo, Self::Error> {
+        status::ssd_read_otp_info(di)
+    }
+
+    fn set_vcom<DI: DisplayInterface>(di: &mut DI, vcom: u8) -> Result<(), Self::Error> {
+        di.send_command_data(Command::WriteVcom as u8, &[vcom])?;
         Ok(())
     }
 
     fn sleep<DI: DisplayInterface, DELAY: DelayNs>(
         di: &mut DI,
         delay: &mut DELAY,
+        mode: SleepMode,
     ) -> Result<(), Self::Error> {
-        di.send_command_data(0x10, &[0x01])?;
+        di.send_command_data(Command::DeepSleepMode as u8, &[super::ssd_deep_sleep(mode)])?;
         delay.delay_us(100_000);
 
         Ok(())
     }
+
+    fn read_temperature<DI: DisplayInterface>(di: &mut DI) -> Result<i8, Self::Error> {
+        super::ssd_read_temperature::<Self, _>(di)
+    }
+
+    fn set_driving_voltages<DI: DisplayInterface>(
+        di: &mut DI,
+        voltages: &DrivingVoltages,
+    ) -> Result<(), Self::Error> {
+        let gate = voltage::ssd_gate(voltages)?;
+        let source = voltage::ssd_source(voltages)?;
+        di.send_command_data(Command::GateDrivingVoltage as u8, &[gate])?;
+        di.send_command_data(Command::SourceDrivingVoltage as u8, &source)?;
+        Ok(())
+    }
 }
 
 impl MultiColorDriver for SSD1680 {
     fn update_channel_frame<'a, DI: DisplayInterface, I>(
         di: &mut DI,
-        channel: u8,
+        plane: Plane,
         buffer: I,
     ) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        di.send_command_data(0x4e, &[0])?; // x start
-        di.send_command_data(0x4f, &[0, 0])?; // y start
+        di.send_command_data(Command::RamXCounter as u8, &[0])?;
+        di.send_command_data(Command::RamYCounter as u8, &[0, 0])?;
 
-        if channel == 0 {
-            di.send_command(0x24)?;
-            di.send_data_from_iter(buffer)?;
-        } else if channel == 1 {
-            di.send_command(0x26)?;
-            di.send_data_from_iter(buffer)?;
-        } else {
-        }
+        let command = match plane {
+            Plane::BlackWhite => Command::WriteRam,
+            Plane::Chromatic => Command::WriteRedRam,
+        };
+        di.send_command(command as u8)?;
+        di.send_data_from_iter(buffer)?;
+        Ok(())
+    }
+
+    fn update_channel_window<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        plane: Plane,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        let (x1, y1) = (x + width - 1, y + height - 1);
+        di.send_command_data(Command::RamXRange as u8, &[(x >> 3) as u8, (x1 >> 3) as u8])?;
+        di.send_command_data(
+            Command::RamYRange as u8,
+            &[y as u8, (y >> 8) as u8, y1 as u8, (y1 >> 8) as u8],
+        )?;
+        di.send_command_data(Command::RamXCounter as u8, &[(x >> 3) as u8])?;
+        di.send_command_data(Command::RamYCounter as u8, &[y as u8, (y >> 8) as u8])?;
+
+        let command = match plane {
+            Plane::BlackWhite => Command::WriteRam,
+            Plane::Chromatic => Command::WriteRedRam,
+        };
+        di.send_command(command as u8)?;
+        di.send_data_from_iter(buffer)?;
         Ok(())
     }
 }
 
 impl WaveformDriver for SSD1680 {
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        di.send_command_data(0x22, &[0xc7])?;
-        di.send_command(0x20)?;
+        di.send_command_data(
+            Command::DisplayUpdateControl2 as u8,
+            &[UpdateSequence::new()
+                .power_on()
+                .display()
+                .power_off()
+                .bits()],
+        )?;
+        di.send_command(Command::MasterActivation as u8)?;
         Self::busy_wait(di)?;
         Ok(())
     }
@@ -123,9 +279,19 @@ impl WaveformDriver for SSD1680 {
         di: &mut DI,
         lut: &'static [u8],
     ) -> Result<(), Self::Error> {
-        di.send_command_data(0x32, lut)?;
+        lut::debug_validate::<Lut153>(lut)?;
+        di.send_command_data(Command::WriteLut as u8, lut)?;
         Ok(())
     }
+
+    fn scale_timing<DI: DisplayInterface>(
+        di: &mut DI,
+        lut: &[u8],
+        percent: u16,
+    ) -> Result<(), Self::Error> {
+        let lut = lut::scaled::<Lut153>(lut, percent)?;
+        di.send_command_data(Command::WriteLut as u8, lut.as_bytes())
+    }
 }
 
 impl FastUpdateDriver for SSD1680 {
@@ -210,3 +376,43 @@ impl FastUpdateDriver for SSD1680 {
         Ok(())
     }
 }
+
+impl FastBlackWhiteDriver for SSD1680 {
+    fn setup_fast_black_white_waveform<DI: DisplayInterface>(
+        di: &mut DI,
+    ) -> Result<(), Self::Error> {
+        #[rustfmt::skip]
+        const LUT: [u8; 153] = [
+            // VS
+            // 00 - VSS
+            // 01 - VSH1
+            // 10 - VSL
+            // 11 - VSH2
+            0b01_00_00_00,
+                  0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // B
+            0b10_00_00_00,
+                  0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // W
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // R, not driven
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,  // R, not driven
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // TPnA, TPnB, SRnAB, TPnC, TPnD, SRnCD, RPn
+            0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 0
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 6
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // 11
+            // FR
+            0b0111_0000, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // XON
+            0x00, 0x00, 0x00,
+        ];
+        Self::update_waveform(di, &LUT)
+    }
+}
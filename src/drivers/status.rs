@@ -0,0 +1,177 @@
+//! Controller status and OTP data read back for diagnostics.
+
+use super::command::Command;
+use super::uc_command::Command as UcCommand;
+use crate::interface::{DisplayError, DisplayInterface};
+
+/// Controller state returned by `Driver::read_status`, `None` where the controller or the interface
+/// cannot report it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct Status {
+    /// A command or refresh is in progress
+    pub busy: bool,
+    /// Booster on, PON on UC controllers, HV ready as of the last detection on SSD controllers
+    pub powered: Option<bool>,
+    /// VCI below the detection level as of the last detection, SSD controllers
+    pub low_voltage: Option<bool>,
+    /// Chip ID bits of the status register, SSD controllers
+    pub chip_id: Option<u8>,
+    /// Broken panel found by the last `Epd::detect_panel_break`
+    pub panel_break: Option<bool>,
+    /// Status register, 0x2F on SSD and FLG (0x71) on UC controllers
+    pub raw: Option<u8>,
+}
+
+impl Status {
+    /// Status known from the BUSY pin only.
+    pub const fn from_busy(busy: bool) -> Self {
+        Self {
+            busy,
+            powered: None,
+            low_voltage: None,
+            chip_id: None,
+            panel_break: None,
+            raw: None,
+        }
+    }
+}
+
+/// Result of `Driver::detect_panel_break`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PanelState {
+    Intact,
+    /// Cracked glass or a broken connection to the panel
+    Broken,
+}
+
+/// Controller family and ID found by `probe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ControllerId {
+    /// SSD16xx or IL3895, with the chip ID bits of the status bits (0x2F)
+    Ssd { chip_id: u8 },
+    /// UC81xx, with the first bytes of the LUT and chip revision (REV, 0x70)
+    Uc { revision: [u8; 4] },
+}
+
+impl ControllerId {
+    /// Whether a UC driver, e.g. `UC8176` or `UC8179`, matches the controller.
+    pub const fn is_uc(&self) -> bool {
+        matches!(self, ControllerId::Uc { .. })
+    }
+}
+
+/// Identify the controller behind `di`, e.g. for products shipping panels of several vendors.
+///
+/// The family is told by the idle level of BUSY, low on SSD and high on UC controllers, the ID is then read
+/// from the controller. Must be called after a reset, with the controller idle and out of deep sleep.
+/// Returns `None` if the interface cannot read, or if nothing answers.
+///
+/// An idle SSD controller with chip ID 0 reads 0x00 from its status bits, as a floating line does. The
+/// display option OTP (0x2D) is then read as well, the controller counts as present if it holds anything
+/// but all zeros or all ones. A controller whose display option OTP was never programmed still reads as `None`.
+pub fn probe<DI: DisplayInterface>(di: &mut DI) -> Option<ControllerId> {
+    // a floating data line reads as all zeros or all ones
+    let answered =
+        |bytes: &[u8]| bytes.iter().any(|&b| b != bytes[0]) || !matches!(bytes[0], 0x00 | 0xff);
+    if di.is_busy_on() {
+        let mut revision = [0; 4];
+        di.send_command(UcCommand::Revision as u8).ok()?;
+        di.read_data(&mut revision).ok()?;
+        answered(&revision).then_some(ControllerId::Uc { revision })
+    } else {
+        let mut raw = [0];
+        di.send_command(Command::StatusBitRead as u8).ok()?;
+        di.read_data(&mut raw).ok()?;
+        let present = answered(&raw) || {
+            let mut otp = [0; 11];
+            di.send_command(Command::ReadOtpDisplayOption as u8).ok()?;
+            di.read_data(&mut otp).ok()?;
+            answered(&otp)
+        };
+        present.then_some(ControllerId::Ssd {
+            chip_id: raw[0] & 0b11,
+        })
+    }
+}
+
+/// Factory settings programmed in the controller OTP, see `Driver::read_otp_info`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OtpInfo {
+    /// VCOM register value, as written by `Driver::set_vcom`
+    pub vcom: u8,
+    /// Waveform version, vendor defined
+    pub waveform_version: [u8; 4],
+}
+
+/// Read the display option OTP (0x2D) of SSD controllers: VCOM selection, VCOM, display mode and waveform version.
+#[cfg_attr(
+    not(any(feature = "ssd1619a", feature = "ssd1675b", feature = "ssd1680")),
+    allow(dead_code)
+)]
+pub(crate) fn ssd_read_otp_info<DI: DisplayInterface>(
+    di: &mut DI,
+) -> Result<OtpInfo, DisplayError> {
+    let mut otp = [0; 11];
+    di.send_command(Command::ReadOtpDisplayOption as u8)?;
+    di.read_data(&mut otp)?;
+    Ok(OtpInfo {
+        vcom: otp[1],
+        waveform_version: [otp[7], otp[8], otp[9], otp[10]],
+    })
+}
+
+/// Panel break flag of the status bits (0x2F) after `Command::PanelBreakDetection`.
+#[cfg_attr(not(feature = "ssd1619a"), allow(dead_code))]
+pub(crate) const SSD_PANEL_BREAK_FLAG: u8 = 0x08;
+
+/// Read the status bits (0x2F) of SSD controllers, the BUSY pin only if the interface cannot read.
+///
+/// Commands are ignored while busy, the register is not read then.
+#[cfg_attr(
+    not(any(feature = "ssd1619a", feature = "ssd1675b", feature = "ssd1680")),
+    allow(dead_code)
+)]
+pub(crate) fn ssd_read_status<DI: DisplayInterface>(di: &mut DI) -> Result<Status, DisplayError> {
+    let busy = di.is_busy_on();
+    if busy {
+        return Ok(Status::from_busy(true));
+    }
+    di.send_command(Command::StatusBitRead as u8)?;
+    let mut raw = [0];
+    match di.read_data(&mut raw) {
+        Ok(()) => Ok(Status {
+            busy,
+            powered: Some(raw[0] & 0x20 == 0),
+            low_voltage: Some(raw[0] & 0x10 != 0),
+            chip_id: Some(raw[0] & 0b11),
+            panel_break: None,
+            raw: Some(raw[0]),
+        }),
+        Err(DisplayError::Unsupported) => Ok(Status::from_busy(busy)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Read the status flags (FLG) of UC controllers, the BUSY pin only if the interface cannot read.
+#[cfg_attr(not(any(feature = "uc8176", feature = "uc8179")), allow(dead_code))]
+pub(crate) fn uc_read_status<DI: DisplayInterface>(di: &mut DI) -> Result<Status, DisplayError> {
+    // BUSY is active low
+    let busy = !di.is_busy_on();
+    di.send_command(UcCommand::GetStatus as u8)?;
+    let mut raw = [0];
+    match di.read_data(&mut raw) {
+        Ok(()) => Ok(Status {
+            busy: raw[0] & 0x01 == 0,
+            powered: Some(raw[0] & 0x04 != 0),
+            raw: Some(raw[0]),
+            ..Status::from_busy(busy)
+        }),
+        Err(DisplayError::Unsupported) => Ok(Status::from_busy(busy)),
+        Err(e) => Err(e),
+    }
+}
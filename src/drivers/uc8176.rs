@@ -3,7 +3,14 @@
 use core::iter;
 use embedded_hal::delay::DelayNs;
 
-use super::{Driver, MultiColorDriver};
+use super::uc_command::{
+    power_setting_uc8176, resolution, vcom_data_interval_uc8176, Command, PanelSetting,
+    DEEP_SLEEP_CHECK,
+};
+use super::{
+    border, status, voltage, BorderColor, Driver, DrivingVoltages, FastUpdateDriver,
+    MultiColorDriver, Plane, PowerControl, SleepMode, Status, WaveformDriver,
+};
 use crate::interface::{DisplayError, DisplayInterface};
 
 /// 400 source x 300 gate, B/W/R
@@ -13,9 +20,16 @@ impl Driver for UC8176 {
     type Error = DisplayError;
     // const BLACK_BIT: bool = true;
 
+    const MAX_SPI_HZ: u32 = 10_000_000;
+    const SUPPORTS_PARTIAL: bool = true;
+    const PLANES: usize = 2;
+    const MAX_REFRESH_MS: u32 = 20_000;
+
     fn busy_wait<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
         // negative logic
-        while !di.is_busy_on() {}
+        while !di.is_busy_on() {
+            di.busy_idle();
+        }
         Ok(())
     }
 
@@ -26,29 +40,37 @@ impl Driver for UC8176 {
         di.reset(delay, 10_000, 10_000); // HW Reset
         Self::busy_wait(di)?;
 
-        di.send_command_data(0x01, &[0x03, 0x00, 0x2b, 0x2b, 0x13])?;
+        // VGH=16V, VGL=-16V, VDH=11V, VDL=-11V, VDHR=6.2V
+        di.send_command_data(
+            Command::PowerSetting as u8,
+            &power_setting_uc8176(0, 0x2b, 0x2b, 0x13),
+        )?;
 
-        di.send_command_data(0x06, &[0x17, 0x17, 0x17])?;
+        di.send_command_data(Command::BoosterSoftStart as u8, &[0x17, 0x17, 0x17])?;
 
-        di.send_command(0x04)?; // power on
+        di.send_command(Command::PowerOn as u8)?;
         Self::busy_wait(di)?;
 
         // di.send_command_data(0x00, &[0x3f])?; // panel setting
 
-        di.send_command_data(0x30, &[0x3c])?; // PLL control
+        di.send_command_data(Command::PllControl as u8, &[0x3c])?;
 
-        di.send_command_data(0x82, &[0x12])?; // VCM_DC setting
-        di.send_command_data(0x50, &[0x97])?; // VCOM AND DATA INTERVAL SETTING
+        di.send_command_data(Command::VcmDcSetting as u8, &[0x12])?;
+        // border white, KWR polarity
+        di.send_command_data(
+            Command::VcomDataInterval as u8,
+            &[vcom_data_interval_uc8176(0b10, 0b01, 0x07)],
+        )?;
 
         // fill r channel with zeros(white)
-        di.send_command(0x13)?;
-        di.send_data_from_iter(iter::repeat(&0x00).take(400 * 300 / 8))?;
+        di.send_command(Command::DataStartTransmission2 as u8)?;
+        di.send_data_from_iter(iter::repeat_n(&0x00, 400 * 300 / 8))?;
 
         Ok(())
     }
 
     fn set_shape<DI: DisplayInterface>(di: &mut DI, x: u16, y: u16) -> Result<(), Self::Error> {
-        di.send_command_data(0x61, &[(x >> 8) as u8, x as u8, (y >> 8) as u8, y as u8])?;
+        di.send_command_data(Command::ResolutionSetting as u8, &resolution(x, y))?;
         Ok(())
     }
 
@@ -56,42 +78,208 @@ impl Driver for UC8176 {
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        di.send_command(0x10)?;
+        di.send_command(Command::DataStartTransmission1 as u8)?;
+        di.send_data_from_iter(buffer)?;
+        Ok(())
+    }
+
+    fn update_frame_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        // RAM address can't be set, later strips continue the same data transmission
+        if y == 0 {
+            di.send_command(Command::DataStartTransmission1 as u8)?;
+        }
         di.send_data_from_iter(buffer)?;
         Ok(())
     }
 
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        di.send_command_data(0x04, &[0x00])?; // Power on
+        Self::start_refresh(di)?;
+        Self::busy_wait(di)
+    }
+
+    fn start_refresh<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command_data(Command::PowerOn as u8, &[0x00])?;
         Self::busy_wait(di)?;
 
-        di.send_command_data(0x12, &[0x00])?; // display refresh
+        di.send_command_data(Command::DisplayRefresh as u8, &[0x00])?;
+        Ok(())
+    }
 
+    fn read_status<DI: DisplayInterface>(di: &mut DI) -> Result<Status, Self::Error> {
+        status::uc_read_status(di)
+    }
+
+    fn sleep<DI: DisplayInterface, DELAY: DelayNs>(
+        di: &mut DI,
+        _delay: &mut DELAY,
+        mode: SleepMode,
+    ) -> Result<(), Self::Error> {
+        di.send_command(Command::PowerOff as u8)?;
         Self::busy_wait(di)?;
 
+        if mode == SleepMode::DeepSleep2 {
+            di.send_command_data(Command::DeepSleep as u8, &[DEEP_SLEEP_CHECK])?;
+        }
+        Ok(())
+    }
+
+    fn set_border<DI: DisplayInterface>(
+        di: &mut DI,
+        color: BorderColor,
+    ) -> Result<(), Self::Error> {
+        let cdi = vcom_data_interval_uc8176(border::uc_border(color), 0b01, 0x07);
+        di.send_command_data(Command::VcomDataInterval as u8, &[cdi])?;
+        Ok(())
+    }
+
+    fn set_driving_voltages<DI: DisplayInterface>(
+        di: &mut DI,
+        voltages: &DrivingVoltages,
+    ) -> Result<(), Self::Error> {
+        // VGH/VGL from 16V down to 13V
+        let vghl = match voltages.vgh {
+            13_000..=16_000 => ((16_000 - voltages.vgh) / 1_000) as u8,
+            _ => return Err(DisplayError::OutOfRange),
+        };
+        let vdh = voltage::uc_source(voltages.vsh, 11_000)?;
+        let vdl = voltage::uc_source(voltages.vsl, 11_000)?;
+        let vdhr = voltage::uc_source(voltages.vsh2, 11_000)?;
+        di.send_command_data(
+            Command::PowerSetting as u8,
+            &power_setting_uc8176(vghl, vdh, vdl, vdhr),
+        )?;
         Ok(())
     }
 }
 
+impl PowerControl for UC8176 {
+    fn power_on<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command(Command::PowerOn as u8)?;
+        Self::busy_wait(di)
+    }
+
+    fn power_off<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command(Command::PowerOff as u8)?;
+        Self::busy_wait(di)
+    }
+}
+
 impl MultiColorDriver for UC8176 {
     fn update_channel_frame<'a, DI: DisplayInterface, I>(
         di: &mut DI,
-        channel: u8,
+        plane: Plane,
         buffer: I,
     ) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        if channel == 0 {
-            di.send_command(0x10)?;
-            di.send_data_from_iter(buffer)?;
-        } else if channel == 1 {
-            di.send_command(0x13)?;
-            di.send_data_from_iter(buffer)?;
-        } else {
-            return Err(DisplayError::InvalidChannel);
+        let command = match plane {
+            Plane::BlackWhite => Command::DataStartTransmission1,
+            Plane::Chromatic => Command::DataStartTransmission2,
+        };
+        di.send_command(command as u8)?;
+        di.send_data_from_iter(buffer)?;
+
+        Ok(())
+    }
+}
+
+/// Size of LUTC, 7 groups of a level select byte, 4 phase lengths in frames and a repeat count, then 2 more bytes.
+const LUT_VCOM_SIZE: usize = 44;
+/// Size of the other register LUTs.
+const LUT_SIZE: usize = 42;
+
+impl WaveformDriver for UC8176 {
+    /// `lut` holds LUTC, LUTWW, LUTBW, LUTWB and LUTBB in a row.
+    fn update_waveform<DI: DisplayInterface>(
+        di: &mut DI,
+        lut: &'static [u8],
+    ) -> Result<(), Self::Error> {
+        if lut.len() != LUT_VCOM_SIZE + 4 * LUT_SIZE {
+            return Err(DisplayError::InvalidFormatError);
         }
+        let (vcom, luts) = lut.split_at(LUT_VCOM_SIZE);
+        di.send_command_data(Command::LutVcom as u8, vcom)?;
+        let commands = [
+            Command::LutWw,
+            Command::LutBw,
+            Command::LutWb,
+            Command::LutBb,
+        ];
+        for (command, table) in commands.iter().zip(luts.chunks(LUT_SIZE)) {
+            di.send_command_data(*command as u8, table)?;
+        }
+        Ok(())
+    }
+}
+
+/// The well known quick LUTs of 4.2" panels, in KW mode where DTM1 holds the old frame and DTM2 the new one.
+///
+/// They are assigned by the old data here, DTM1 is what `update_frame` writes.
+impl FastUpdateDriver for UC8176 {
+    fn setup_fast_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        // Level select: 00 - VCOM_DC, 01 - VDH, 10 - VDL, 11 - floating
+        #[rustfmt::skip]
+        const LUT: [u8; LUT_VCOM_SIZE + 4 * LUT_SIZE] = [
+            // LUTC
+            0x00, 0x0e, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+            // LUTWW, old white: to white
+            0xa0, 0x0e, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // LUTBW, old black: to black
+            0x50, 0x0e, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // LUTWB, old white: to white
+            0xa0, 0x0e, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // LUTBB, old black: to black
+            0x50, 0x0e, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let psr = PanelSetting::new()
+            .lut_from_register(true)
+            .black_white(true);
+        di.send_command_data(Command::PanelSetting as u8, &[psr.bits()])?;
+        Self::update_waveform(di, &LUT)
+    }
 
+    fn restore_normal_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        // KWR mode with the OTP LUT, the reset default
+        di.send_command_data(Command::PanelSetting as u8, &[PanelSetting::new().bits()])?;
         Ok(())
     }
 }
@@ -1,12 +1,24 @@
-//! UC8176 driver
+//! UC8179 driver
 //!
 //! Up to 20MHz
 
+use core::iter;
+
 use embedded_hal::delay::DelayNs;
 
-use super::{Driver, MultiColorDriver};
+use super::uc_command::{
+    power_setting_uc8179, resolution, vcom_data_interval_uc8179, Command, PanelSetting,
+    DEEP_SLEEP_CHECK,
+};
+use super::{
+    border, status, voltage, BorderColor, Driver, DrivingVoltages, FastUpdateDriver,
+    MultiColorDriver, Plane, PowerControl, SleepMode, Status, WaveformDriver,
+};
 use crate::interface::{DisplayError, DisplayInterface};
 
+const MAX_WIDTH: u16 = 800;
+const MAX_HEIGHT: u16 = 600;
+
 /// 800 x 600 x 2
 pub struct UC8179;
 
@@ -14,10 +26,16 @@ impl Driver for UC8179 {
     type Error = DisplayError;
     // const BLACK_BIT: bool = true;
 
+    const MAX_SPI_HZ: u32 = 20_000_000;
+    const SUPPORTS_PARTIAL: bool = true;
+    const PLANES: usize = 2;
+
     fn busy_wait<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        di.send_command(0x71)?; // read status
+        di.send_command(Command::GetStatus as u8)?;
 
-        while !di.is_busy_on() {}
+        while !di.is_busy_on() {
+            di.busy_idle();
+        }
         Ok(())
     }
 
@@ -28,41 +46,43 @@ impl Driver for UC8179 {
         di.reset(delay, 10_000, 10_000); // HW Reset
         Self::busy_wait(di)?;
 
-        // Power Setting
         // VGH=20V, VGL=-20V, VDH=15V, VDL=-15V
-        di.send_command_data(0x01, &[0x07, 0x07, 0x3f, 0x3f])?;
+        di.send_command_data(
+            Command::PowerSetting as u8,
+            &power_setting_uc8179(0x07, 0x3f, 0x3f),
+        )?;
 
-        //        di.send_command_data(0x06, &[0x17, 0x17, 0x17])?;
-
-        di.send_command(0x04)?; // power on
+        di.send_command(Command::PowerOn as u8)?;
         Self::busy_wait(di)?;
 
-        // Panel setting
-        // KW-3f   KWR-2F BWROTP 0f BWOTP 1f
-        di.send_command_data(0x00, &[0x0F])?;
-
-        di.send_command_data(0x15, &[0x00])?;
-
-        di.send_command_data(0x50, &[0x11, 0x07])?; // VCOM AND DATA INTERVAL SETTING
-
-        di.send_command_data(0x60, &[0x22])?; // TCON SETTING
+        // KWR mode, LUT from OTP
+        di.send_command_data(Command::PanelSetting as u8, &[PanelSetting::new().bits()])?;
 
-        // di.send_command_data(0x00, &[0x3f])?; // panel setting
+        di.send_command_data(Command::DualSpi as u8, &[0x00])?;
 
-        //        di.send_command_data(0x30, &[0x3c])?; // PLL control
+        di.send_command_data(
+            Command::VcomDataInterval as u8,
+            &vcom_data_interval_uc8179(false, 0b01, 0b01, 0x07),
+        )?;
 
-        //      di.send_command_data(0x82, &[0x12])?; // VCM_DC setting
-        //        di.send_command_data(0x50, &[0x97])?; // VCOM AND DATA INTERVAL SETTING
+        di.send_command_data(Command::TconSetting as u8, &[0x22])?;
 
-        // fill r channel with zeros(white)
-        // di.send_command(0x13)?;
-        //        di.send_data_from_iter(iter::repeat(&0x00).take(400 * 300 / 8))?;
+        // fill r channel of the whole RAM with zeros(white), set_shape narrows the resolution afterwards
+        di.send_command_data(
+            Command::ResolutionSetting as u8,
+            &resolution(MAX_WIDTH, MAX_HEIGHT),
+        )?;
+        di.send_command(Command::DataStartTransmission2 as u8)?;
+        di.send_data_from_iter(iter::repeat_n(
+            &0x00,
+            MAX_WIDTH as usize * MAX_HEIGHT as usize / 8,
+        ))?;
 
         Ok(())
     }
 
     fn set_shape<DI: DisplayInterface>(di: &mut DI, x: u16, y: u16) -> Result<(), Self::Error> {
-        di.send_command_data(0x61, &[(x >> 8) as u8, x as u8, (y >> 8) as u8, y as u8])?;
+        di.send_command_data(Command::ResolutionSetting as u8, &resolution(x, y))?;
         Ok(())
     }
 
@@ -70,42 +90,219 @@ impl Driver for UC8179 {
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        di.send_command(0x10)?;
+        di.send_command(Command::DataStartTransmission1 as u8)?;
+        di.send_data_from_iter(buffer)?;
+        Ok(())
+    }
+
+    fn update_frame_rows<'a, DI: DisplayInterface, I>(
+        di: &mut DI,
+        y: u16,
+        buffer: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = &'a u8>,
+    {
+        // RAM address can't be set, later strips continue the same data transmission
+        if y == 0 {
+            di.send_command(Command::DataStartTransmission1 as u8)?;
+        }
         di.send_data_from_iter(buffer)?;
         Ok(())
     }
 
     fn turn_on_display<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
-        di.send_command(0x04)?; // Power on
+        Self::start_refresh(di)?;
+        Self::busy_wait(di)
+    }
+
+    fn start_refresh<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command(Command::PowerOn as u8)?;
         Self::busy_wait(di)?;
 
-        //   di.send_command(0x12)?; // display refresh
+        di.send_command(Command::DisplayRefresh as u8)?;
+        Ok(())
+    }
+
+    fn read_status<DI: DisplayInterface>(di: &mut DI) -> Result<Status, Self::Error> {
+        status::uc_read_status(di)
+    }
 
+    fn sleep<DI: DisplayInterface, DELAY: DelayNs>(
+        di: &mut DI,
+        _delay: &mut DELAY,
+        mode: SleepMode,
+    ) -> Result<(), Self::Error> {
+        di.send_command(Command::PowerOff as u8)?;
         Self::busy_wait(di)?;
 
+        // only a hardware reset leaves deep sleep, wake_up starts with one
+        if mode == SleepMode::DeepSleep2 {
+            di.send_command_data(Command::DeepSleep as u8, &[DEEP_SLEEP_CHECK])?;
+        }
+        Ok(())
+    }
+
+    fn set_border<DI: DisplayInterface>(
+        di: &mut DI,
+        color: BorderColor,
+    ) -> Result<(), Self::Error> {
+        let cdi = match color {
+            BorderColor::HiZ => vcom_data_interval_uc8179(true, 0b00, 0b01, 0x07),
+            _ => vcom_data_interval_uc8179(false, border::uc_border(color), 0b01, 0x07),
+        };
+        di.send_command_data(Command::VcomDataInterval as u8, &cdi)?;
+        Ok(())
+    }
+
+    /// VDHR is not used, the panel is B/W
+    fn set_driving_voltages<DI: DisplayInterface>(
+        di: &mut DI,
+        voltages: &DrivingVoltages,
+    ) -> Result<(), Self::Error> {
+        // VGH/VGL 9V to 12V or 17V to 20V
+        let vghl = match voltages.vgh {
+            9_000..=12_000 => ((voltages.vgh - 9_000) / 1_000) as u8,
+            17_000..=20_000 => ((voltages.vgh - 17_000) / 1_000) as u8 + 4,
+            _ => return Err(DisplayError::OutOfRange),
+        };
+        let vdh = voltage::uc_source(voltages.vsh, 15_000)?;
+        let vdl = voltage::uc_source(voltages.vsl, 15_000)?;
+        di.send_command_data(
+            Command::PowerSetting as u8,
+            &power_setting_uc8179(vghl, vdh, vdl),
+        )?;
         Ok(())
     }
 }
 
+impl PowerControl for UC8179 {
+    fn power_on<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command(Command::PowerOn as u8)?;
+        Self::busy_wait(di)
+    }
+
+    fn power_off<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        di.send_command(Command::PowerOff as u8)?;
+        Self::busy_wait(di)
+    }
+}
+
 impl MultiColorDriver for UC8179 {
     fn update_channel_frame<'a, DI: DisplayInterface, I>(
         di: &mut DI,
-        channel: u8,
+        plane: Plane,
         buffer: I,
     ) -> Result<(), Self::Error>
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        if channel == 0 {
-            di.send_command(0x10)?;
-            di.send_data_from_iter(buffer)?;
-        } else if channel == 1 {
-            di.send_command(0x13)?;
-            di.send_data_from_iter(buffer)?;
-        } else {
-            return Err(DisplayError::InvalidChannel);
+        let command = match plane {
+            Plane::BlackWhite => Command::DataStartTransmission1,
+            Plane::Chromatic => Command::DataStartTransmission2,
+        };
+        di.send_command(command as u8)?;
+        di.send_data_from_iter(buffer)?;
+
+        Ok(())
+    }
+}
+
+/// Size of each register LUT, 7 groups of a level select byte, 4 phase lengths in frames and a repeat count.
+const LUT_SIZE: usize = 42;
+
+impl WaveformDriver for UC8179 {
+    /// `lut` holds LUTC, LUTWW, LUTKW, LUTWK, LUTKK and LUTBD in a row, or the first 5 of them.
+    fn update_waveform<DI: DisplayInterface>(
+        di: &mut DI,
+        lut: &'static [u8],
+    ) -> Result<(), Self::Error> {
+        if lut.len() != 5 * LUT_SIZE && lut.len() != 6 * LUT_SIZE {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        let commands = [
+            Command::LutVcom,
+            Command::LutWw,
+            Command::LutBw,
+            Command::LutWb,
+            Command::LutBb,
+            Command::LutBorder,
+        ];
+        for (command, table) in commands.iter().zip(lut.chunks(LUT_SIZE)) {
+            di.send_command_data(*command as u8, table)?;
         }
+        Ok(())
+    }
+}
+
+/// Register LUTs in KW mode, where DTM1 holds the old frame and DTM2 the new one.
+///
+/// The fast waveform only looks at DTM1, which `update_frame` writes, so a stale DTM2 does no harm.
+impl FastUpdateDriver for UC8179 {
+    fn setup_fast_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        // Level select: 00 - VCOM_DC, 01 - VDH, 10 - VDL, 11 - floating
+        // 20 frames at the default 50Hz frame rate
+        #[rustfmt::skip]
+        const LUT: [u8; 6 * LUT_SIZE] = [
+            // LUTC
+            0b00_00_00_00, 0x14, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // LUTWW, old white: to white
+            0b10_00_00_00, 0x14, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // LUTKW, old black: to black
+            0b01_00_00_00, 0x14, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // LUTWK, old white: to white
+            0b10_00_00_00, 0x14, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // LUTKK, old black: to black
+            0b01_00_00_00, 0x14, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // LUTBD, border left as is
+            0b00_00_00_00, 0x14, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let psr = PanelSetting::new()
+            .lut_from_register(true)
+            .black_white(true);
+        di.send_command_data(Command::PanelSetting as u8, &[psr.bits()])?;
+        Self::update_waveform(di, &LUT)
+    }
 
+    fn restore_normal_waveform<DI: DisplayInterface>(di: &mut DI) -> Result<(), Self::Error> {
+        // KWR mode with the OTP LUT, as set by wake_up
+        di.send_command_data(Command::PanelSetting as u8, &[PanelSetting::new().bits()])?;
         Ok(())
     }
 }
@@ -0,0 +1,192 @@
+//! Commands of the UC8176/UC8179 family, also used by the Pervasive Displays controllers.
+//!
+//! Payload layouts that differ between controllers are built by functions named after the controller.
+
+/// Command codes, sent as `Command::PowerOn as u8`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum Command {
+    /// PSR, see `PanelSetting`
+    PanelSetting = 0x00,
+    /// PWR, see `power_setting_uc8176` and `power_setting_uc8179`
+    PowerSetting = 0x01,
+    /// POF
+    PowerOff = 0x02,
+    /// PFS
+    PowerOffSequence = 0x03,
+    /// PON
+    PowerOn = 0x04,
+    /// PMES
+    PowerOnMeasure = 0x05,
+    /// BTST
+    BoosterSoftStart = 0x06,
+    /// DSLP, followed by the check code 0xA5
+    DeepSleep = 0x07,
+    /// DTM1, B/W data in KWR mode, old data in KW mode
+    DataStartTransmission1 = 0x10,
+    /// DSP
+    DataStop = 0x11,
+    /// DRF
+    DisplayRefresh = 0x12,
+    /// DTM2, red data in KWR mode, new data in KW mode
+    DataStartTransmission2 = 0x13,
+    /// Dual SPI mode of UC8179
+    DualSpi = 0x15,
+    /// AUTO, power on, refresh and power off in one command
+    AutoSequence = 0x17,
+    /// LUTC
+    LutVcom = 0x20,
+    /// LUTWW
+    LutWw = 0x21,
+    /// LUTBW (old black to new white), LUTR in KWR mode
+    LutBw = 0x22,
+    /// LUTWB (old white to new black), LUTW in KWR mode
+    LutWb = 0x23,
+    /// LUTBB, LUTB in KWR mode
+    LutBb = 0x24,
+    /// LUTBD, border
+    LutBorder = 0x25,
+    /// PLL
+    PllControl = 0x30,
+    /// TSC
+    TemperatureSensorCalibration = 0x40,
+    /// TSE
+    TemperatureSensorSelection = 0x41,
+    /// TSW
+    TemperatureSensorWrite = 0x42,
+    /// TSR
+    TemperatureSensorRead = 0x43,
+    /// CDI, see `vcom_data_interval_uc8176` and `vcom_data_interval_uc8179`
+    VcomDataInterval = 0x50,
+    /// LPD
+    LowPowerDetection = 0x51,
+    /// TCON
+    TconSetting = 0x60,
+    /// TRES, see `resolution`
+    ResolutionSetting = 0x61,
+    /// GSST
+    GateSourceStart = 0x65,
+    /// REV
+    Revision = 0x70,
+    /// FLG
+    GetStatus = 0x71,
+    /// AMV
+    AutoMeasureVcom = 0x80,
+    /// VV
+    VcomValue = 0x81,
+    /// VDCS
+    VcmDcSetting = 0x82,
+    /// PTL
+    PartialWindow = 0x90,
+    /// PTIN
+    PartialIn = 0x91,
+    /// PTOUT
+    PartialOut = 0x92,
+    /// PGM
+    ProgramMode = 0xa0,
+    /// APG
+    ActiveProgramming = 0xa1,
+    /// ROTP
+    ReadOtp = 0xa2,
+    /// CCSET, bit 1 (TSFIX) makes the controller use the temperature of `ForceTemperature`
+    CascadeSetting = 0xe0,
+    /// PWS
+    PowerSaving = 0xe3,
+    /// TSSET
+    ForceTemperature = 0xe5,
+}
+
+/// Check code following `Command::DeepSleep`.
+pub const DEEP_SLEEP_CHECK: u8 = 0xa5;
+
+/// Panel setting (PSR), the bits common to UC8176 and UC8179.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PanelSetting(u8);
+
+impl PanelSetting {
+    /// LUT from OTP, KWR mode, gates scanning up, sources shifting right, booster on and no soft reset.
+    pub const fn new() -> Self {
+        Self(0b0000_1111)
+    }
+
+    /// Resolution (RES) of UC8176 and the Pervasive Displays controllers, 0 to 3.
+    ///
+    /// UC8179 takes the resolution from `ResolutionSetting` only.
+    pub const fn resolution(self, res: u8) -> Self {
+        Self(self.0 & 0x3f | (res & 0b11) << 6)
+    }
+
+    /// Use the LUTs written by the LUT commands instead of the ones in OTP (REG).
+    pub const fn lut_from_register(self, on: bool) -> Self {
+        self.bit(5, on)
+    }
+
+    /// B/W mode using LUTWW to LUTBB instead of the KWR mode (KW/R).
+    pub const fn black_white(self, on: bool) -> Self {
+        self.bit(4, on)
+    }
+
+    /// Gate scan direction (UD).
+    pub const fn scan_up(self, on: bool) -> Self {
+        self.bit(3, on)
+    }
+
+    /// Source shift direction (SHL).
+    pub const fn shift_right(self, on: bool) -> Self {
+        self.bit(2, on)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    const fn bit(self, n: u8, on: bool) -> Self {
+        if on {
+            Self(self.0 | 1 << n)
+        } else {
+            Self(self.0 & !(1 << n))
+        }
+    }
+}
+
+impl Default for PanelSetting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Power setting (PWR) of UC8176 with internal DC/DC.
+///
+/// `vghl` selects VGH/VGL from ±16V (0) down to ±13V (3), the source voltages count 0.2V steps from 2.4V.
+pub const fn power_setting_uc8176(vghl: u8, vdh: u8, vdl: u8, vdhr: u8) -> [u8; 5] {
+    [0x03, vghl & 0b11, vdh, vdl, vdhr]
+}
+
+/// Power setting (PWR) of UC8179 with internal DC/DC.
+///
+/// `vghl` selects VGH/VGL, ±9V to ±12V for 0 to 3 and ±17V to ±20V for 4 to 7,
+/// the source voltages count 0.2V steps from 2.4V.
+pub const fn power_setting_uc8179(vghl: u8, vdh: u8, vdl: u8) -> [u8; 4] {
+    [0x07, vghl & 0b111, vdh, vdl]
+}
+
+/// VCOM and data interval setting (CDI) of UC8176: border waveform (VBD), data polarity (DDX)
+/// and VCOM to data interval in hsync (CDI).
+pub const fn vcom_data_interval_uc8176(vbd: u8, ddx: u8, interval: u8) -> u8 {
+    (vbd & 0b11) << 6 | (ddx & 0b11) << 4 | interval & 0x0f
+}
+
+/// VCOM and data interval setting (CDI) of UC8179, the border floats if `floating` (BDZ) is set.
+pub const fn vcom_data_interval_uc8179(floating: bool, vbd: u8, ddx: u8, interval: u8) -> [u8; 2] {
+    let bdz = if floating { 0x80 } else { 0x00 };
+    [bdz | (vbd & 0b11) << 4 | ddx & 0b11, interval & 0x0f]
+}
+
+/// Resolution setting (TRES) in pixels.
+pub const fn resolution(width: u16, height: u16) -> [u8; 4] {
+    let [w0, w1] = width.to_be_bytes();
+    let [h0, h1] = height.to_be_bytes();
+    [w0, w1, h0, h1]
+}
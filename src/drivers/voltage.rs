@@ -0,0 +1,72 @@
+//! Gate and source driving voltages.
+
+use crate::interface::DisplayError;
+
+/// Gate and source driving voltages in mV, negative voltages are given as their magnitude.
+///
+/// The values are rounded down to the step size of the controller, a value out of its range
+/// is rejected with `DisplayError::OutOfRange`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DrivingVoltages {
+    /// Gate high voltage VGH, VGL is set to -VGH
+    pub vgh: u16,
+    /// Source high voltage, VSH1 on SSD controllers, VDH on UC controllers
+    pub vsh: u16,
+    /// Second source high voltage used for red, VSH2 on SSD controllers, VDHR on UC controllers
+    pub vsh2: u16,
+    /// Source low voltage, VSL on SSD controllers, VDL on UC controllers
+    pub vsl: u16,
+}
+
+#[cfg_attr(
+    not(any(
+        feature = "ssd1619a",
+        feature = "ssd1675b",
+        feature = "ssd1680",
+        feature = "uc8176",
+        feature = "uc8179"
+    )),
+    allow(dead_code)
+)]
+fn step(mv: u16, min: u16, max: u16, step: u16) -> Result<u8, DisplayError> {
+    if !(min..=max).contains(&mv) {
+        return Err(DisplayError::OutOfRange);
+    }
+    Ok(((mv - min) / step) as u8)
+}
+
+/// Gate driving voltage, command 0x03 of SSD controllers: 12V to 20V.
+#[cfg_attr(
+    not(any(feature = "ssd1619a", feature = "ssd1675b", feature = "ssd1680")),
+    allow(dead_code)
+)]
+pub(crate) fn ssd_gate(v: &DrivingVoltages) -> Result<u8, DisplayError> {
+    Ok(0x07 + step(v.vgh, 12_000, 20_000, 500)?)
+}
+
+/// Source driving voltage, command 0x04 of SSD controllers: VSH 2.4V to 8.8V or 9V to 17V, VSL -5V to -17V.
+#[cfg_attr(
+    not(any(feature = "ssd1619a", feature = "ssd1675b", feature = "ssd1680")),
+    allow(dead_code)
+)]
+pub(crate) fn ssd_source(v: &DrivingVoltages) -> Result<[u8; 3], DisplayError> {
+    let vsh = |mv| {
+        if mv < 9_000 {
+            Ok(0x8e + step(mv, 2_400, 8_800, 100)?)
+        } else {
+            Ok(0x23 + step(mv, 9_000, 17_000, 200)?)
+        }
+    };
+    Ok([
+        vsh(v.vsh)?,
+        vsh(v.vsh2)?,
+        0x0a + 2 * step(v.vsl, 5_000, 17_000, 500)?,
+    ])
+}
+
+/// VDH, VDL or VDHR of the power setting of UC controllers: 2.4V up to `max`.
+#[cfg_attr(not(any(feature = "uc8176", feature = "uc8179")), allow(dead_code))]
+pub(crate) fn uc_source(mv: u16, max: u16) -> Result<u8, DisplayError> {
+    step(mv, 2_400, max, 200)
+}
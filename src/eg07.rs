@@ -0,0 +1,161 @@
+//! Drawing with embedded-graphics 0.7, for applications not yet moved to 0.8.
+//!
+//! The crate is built on embedded-graphics 0.8. `DrawTarget07` wraps any of its draw targets, e.g. an `Epd`
+//! or a `FrameBuffer`, as a `DrawTarget` of embedded-graphics-core 0.3, the core crate of embedded-graphics 0.7.
+
+use embedded_graphics::{
+    pixelcolor::{BinaryColor, Gray2, Gray4, Gray8},
+    prelude::*,
+    primitives::Rectangle,
+};
+use embedded_graphics_core_03 as eg07;
+
+use crate::color::{Gray3, TriColor};
+
+/// Color of embedded-graphics 0.8 with a counterpart in 0.7.
+pub trait Color07: PixelColor {
+    /// The same color in embedded-graphics 0.7
+    type Color: eg07::pixelcolor::PixelColor;
+
+    fn from_07(color: Self::Color) -> Self;
+}
+
+impl Color07 for BinaryColor {
+    type Color = eg07::pixelcolor::BinaryColor;
+
+    fn from_07(color: Self::Color) -> Self {
+        BinaryColor::from(color.is_on())
+    }
+}
+
+macro_rules! gray_color07 {
+    ($($gray:ident),*) => {
+        $(
+            impl Color07 for $gray {
+                type Color = eg07::pixelcolor::$gray;
+
+                fn from_07(color: Self::Color) -> Self {
+                    use eg07::pixelcolor::GrayColor as _;
+
+                    $gray::new(color.luma())
+                }
+            }
+        )*
+    };
+}
+
+gray_color07!(Gray2, Gray4, Gray8);
+
+/// The colors of the crate are used by both versions.
+impl eg07::pixelcolor::PixelColor for TriColor {
+    type Raw = ();
+}
+
+impl Color07 for TriColor {
+    type Color = TriColor;
+
+    fn from_07(color: Self::Color) -> Self {
+        color
+    }
+}
+
+impl eg07::pixelcolor::PixelColor for Gray3 {
+    type Raw = eg07::pixelcolor::raw::RawU4;
+}
+
+impl Color07 for Gray3 {
+    type Color = Gray3;
+
+    fn from_07(color: Self::Color) -> Self {
+        color
+    }
+}
+
+/// `DrawTarget` of embedded-graphics 0.7 drawing into `T`, a target of 0.8.
+///
+/// Points, areas and colors are converted on the way, fills are passed on as fills.
+pub struct DrawTarget07<'a, T> {
+    target: &'a mut T,
+}
+
+impl<'a, T: DrawTarget> DrawTarget07<'a, T>
+where
+    T::Color: Color07,
+{
+    pub fn new(target: &'a mut T) -> Self {
+        Self { target }
+    }
+
+    /// The wrapped target, drawn with embedded-graphics 0.8.
+    pub fn inner(&mut self) -> &mut T {
+        self.target
+    }
+}
+
+fn point(point: eg07::geometry::Point) -> Point {
+    Point::new(point.x, point.y)
+}
+
+fn area(area: &eg07::primitives::Rectangle) -> Rectangle {
+    Rectangle::new(
+        point(area.top_left),
+        Size::new(area.size.width, area.size.height),
+    )
+}
+
+impl<T: DrawTarget> eg07::geometry::Dimensions for DrawTarget07<'_, T>
+where
+    T::Color: Color07,
+{
+    fn bounding_box(&self) -> eg07::primitives::Rectangle {
+        let bb = self.target.bounding_box();
+        eg07::primitives::Rectangle::new(
+            eg07::geometry::Point::new(bb.top_left.x, bb.top_left.y),
+            eg07::geometry::Size::new(bb.size.width, bb.size.height),
+        )
+    }
+}
+
+impl<T: DrawTarget> eg07::draw_target::DrawTarget for DrawTarget07<'_, T>
+where
+    T::Color: Color07,
+{
+    type Color = <T::Color as Color07>::Color;
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = eg07::Pixel<Self::Color>>,
+    {
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|eg07::Pixel(p, color)| Pixel(point(p), T::Color::from_07(color))),
+        )
+    }
+
+    fn fill_contiguous<I>(
+        &mut self,
+        area07: &eg07::primitives::Rectangle,
+        colors: I,
+    ) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.target
+            .fill_contiguous(&area(area07), colors.into_iter().map(T::Color::from_07))
+    }
+
+    fn fill_solid(
+        &mut self,
+        area07: &eg07::primitives::Rectangle,
+        color: Self::Color,
+    ) -> Result<(), Self::Error> {
+        self.target
+            .fill_solid(&area(area07), T::Color::from_07(color))
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.clear(T::Color::from_07(color))
+    }
+}
@@ -0,0 +1,57 @@
+//! Logging macros, forwarded to `defmt` or `log` depending on the enabled feature.
+//!
+//! `defmt` takes precedence if both are enabled. Without either feature the macros compile to nothing.
+
+#![allow(unused_macros)]
+
+macro_rules! debug {
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(feature = "defmt")]
+            ::defmt::debug!($s $(, $x)*);
+            #[cfg(all(feature = "log", not(feature = "defmt")))]
+            ::log::debug!($s $(, $x)*);
+            #[cfg(not(any(feature = "defmt", feature = "log")))]
+            let _ = ($( & $x ),*);
+        }
+    };
+}
+
+macro_rules! info {
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(feature = "defmt")]
+            ::defmt::info!($s $(, $x)*);
+            #[cfg(all(feature = "log", not(feature = "defmt")))]
+            ::log::info!($s $(, $x)*);
+            #[cfg(not(any(feature = "defmt", feature = "log")))]
+            let _ = ($( & $x ),*);
+        }
+    };
+}
+
+macro_rules! warn {
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(feature = "defmt")]
+            ::defmt::warn!($s $(, $x)*);
+            #[cfg(all(feature = "log", not(feature = "defmt")))]
+            ::log::warn!($s $(, $x)*);
+            #[cfg(not(any(feature = "defmt", feature = "log")))]
+            let _ = ($( & $x ),*);
+        }
+    };
+}
+
+macro_rules! error {
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(feature = "defmt")]
+            ::defmt::error!($s $(, $x)*);
+            #[cfg(all(feature = "log", not(feature = "defmt")))]
+            ::log::error!($s $(, $x)*);
+            #[cfg(not(any(feature = "defmt", feature = "log")))]
+            let _ = ($( & $x ),*);
+        }
+    };
+}
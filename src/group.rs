@@ -0,0 +1,93 @@
+//! Several displays refreshed together, e.g. the panels of a signage device sharing one SPI bus.
+
+use embedded_hal::delay::DelayNs;
+
+use crate::drivers::{Driver, SleepMode};
+use crate::interface::DisplayInterface;
+use crate::Epd;
+
+/// How an `EpdGroup` refreshes its displays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GroupRefresh {
+    /// One display after the other, the peak current is the one of a single refresh
+    Staggered,
+    /// All displays at once, the group waits for the slowest one
+    Parallel,
+}
+
+/// Displays of the same type refreshed together.
+///
+/// Each display has its own interface, e.g. an `EpdInterface` over an `SpiDevice` of a shared bus,
+/// with its own CS and BUSY pins.
+pub struct EpdGroup<I: DisplayInterface, S, D: Driver, B, const N: usize> {
+    pub displays: [Epd<I, S, D, B>; N],
+    refresh: GroupRefresh,
+}
+
+impl<DI, S, D, B, const N: usize> EpdGroup<DI, S, D, B, N>
+where
+    DI: DisplayInterface,
+    D: Driver,
+    B: AsRef<[u8]> + AsMut<[u8]>,
+{
+    pub fn new(displays: [Epd<DI, S, D, B>; N], refresh: GroupRefresh) -> Self {
+        Self { displays, refresh }
+    }
+
+    /// Consume the group and return the displays.
+    pub fn release(self) -> [Epd<DI, S, D, B>; N] {
+        self.displays
+    }
+
+    pub fn set_refresh(&mut self, refresh: GroupRefresh) {
+        self.refresh = refresh;
+    }
+
+    pub fn init<DELAY: DelayNs>(&mut self, delay: &mut DELAY) -> Result<(), D::Error> {
+        for display in &mut self.displays {
+            display.init(delay)?;
+        }
+        Ok(())
+    }
+
+    /// Display the framebuffer of every display, in the order set by `GroupRefresh`.
+    ///
+    /// In parallel, the refreshes already started are waited for before an error is returned.
+    pub fn display_frames(&mut self) -> Result<(), D::Error> {
+        if self.refresh == GroupRefresh::Staggered {
+            for display in &mut self.displays {
+                display.display_frame()?;
+            }
+            return Ok(());
+        }
+
+        let mut result = Ok(());
+        let mut started = 0;
+        for display in &mut self.displays {
+            result = display.start_frame();
+            if result.is_err() {
+                break;
+            }
+            started += 1;
+        }
+        for display in &mut self.displays[..started] {
+            let waited = display.wait_refresh();
+            if result.is_ok() {
+                result = waited;
+            }
+        }
+        result
+    }
+
+    pub fn sleep<DELAY: DelayNs>(
+        &mut self,
+        delay: &mut DELAY,
+        mode: SleepMode,
+    ) -> Result<(), D::Error> {
+        for display in &mut self.displays {
+            display.sleep(delay, mode)?;
+        }
+        Ok(())
+    }
+}
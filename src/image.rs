@@ -0,0 +1,62 @@
+//! Image formats that can be drawn onto the displays.
+
+use embedded_graphics::{
+    image::ImageDrawable, pixelcolor::BinaryColor, prelude::*, primitives::Rectangle,
+};
+
+/// XBM image data, as found in the `_bits` array of a `.xbm` file.
+///
+/// Rows are padded to whole bytes and bits are LSB-first, opposite to the framebuffer.
+/// Set bits are drawn as `BinaryColor::On`, like `ImageRaw<BinaryColor>`.
+#[derive(Clone, Copy, Debug)]
+pub struct Xbm<'a> {
+    data: &'a [u8],
+    size: Size,
+}
+
+impl<'a> Xbm<'a> {
+    pub fn new(data: &'a [u8], width: u32, height: u32) -> Self {
+        Self {
+            data,
+            size: Size::new(width, height),
+        }
+    }
+
+    fn pixel(&self, p: Point) -> BinaryColor {
+        let stride = (self.size.width as usize).div_ceil(8);
+        let (x, y) = (p.x as usize, p.y as usize);
+        self.data
+            .get(y * stride + x / 8)
+            .map(|byte| BinaryColor::from(byte & (1 << (x % 8)) != 0))
+            .unwrap_or(BinaryColor::Off)
+    }
+}
+
+impl OriginDimensions for Xbm<'_> {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl ImageDrawable for Xbm<'_> {
+    type Color = BinaryColor;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let area = self.bounding_box();
+        target.fill_contiguous(&area, area.points().map(|p| self.pixel(p)))
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let area = area.intersection(&self.bounding_box());
+        target.fill_contiguous(
+            &Rectangle::new(Point::zero(), area.size),
+            area.points().map(|p| self.pixel(p)),
+        )
+    }
+}
@@ -2,15 +2,90 @@
 
 use embedded_hal::delay::DelayNs;
 use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::{self, Error as _};
 
-#[derive(Clone, Debug)]
+use crate::lut::LutError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DisplayError {
     InvalidFormatError,
+    /// Bus error without further details
     BusWriteError,
+    /// SPI bus error, with the kind reported by the HAL
+    Spi(spi::ErrorKind),
     DCError,
     CSError,
     BUSYError,
     InvalidChannel,
+    /// The display was refreshed before `init`, or while asleep
+    Uninitialized,
+    /// Drawing outside of the display, in strict mode
+    OutOfBounds,
+    /// The controller or the interface does not support the operation
+    Unsupported,
+    /// A parameter outside of the range supported by the controller
+    OutOfRange,
+    /// The controller RAM read back differs from the data sent
+    VerifyFailed,
+    /// A waveform rejected by `lut::validate`
+    InvalidLut(LutError),
+    /// A refresh came earlier than allowed by the `RateLimiter`
+    RateLimited,
+    /// The bus failed while sending `cmd` or its data, in `phase` of the update sequence
+    Command {
+        cmd: u8,
+        phase: Phase,
+        source: BusError,
+    },
+}
+
+impl DisplayError {
+    /// Attach the command being sent and the phase to a bus error, other errors are returned as they are.
+    ///
+    /// Used by `EpdInterface`, for other interfaces to report errors the same way.
+    pub fn in_command(self, cmd: u8, phase: Phase) -> Self {
+        let source = match self {
+            DisplayError::BusWriteError => BusError::Write,
+            DisplayError::Spi(kind) => BusError::Spi(kind),
+            DisplayError::DCError => BusError::Dc,
+            DisplayError::CSError => BusError::Cs,
+            other => return other,
+        };
+        DisplayError::Command { cmd, phase, source }
+    }
+}
+
+/// Failure on the bus, see `DisplayError::Command`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BusError {
+    /// Bus error without further details
+    Write,
+    /// SPI bus error, with the kind reported by the HAL
+    Spi(spi::ErrorKind),
+    /// The DC pin could not be set
+    Dc,
+    /// The CS pin could not be set
+    Cs,
+}
+
+/// Phase of the update sequence, set by the displays on their interface to report where it failed.
+///
+/// Commands outside of these, e.g. temperature or status reads, report the phase before them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Phase {
+    /// Reset and init sequence of `wake_up`
+    Init,
+    /// Writing a waveform, or selecting one
+    LutLoad,
+    /// Writing the frame into the controller RAM
+    FrameTransfer,
+    /// Starting the refresh and waiting for it
+    Refresh,
+    /// Entering sleep mode
+    Sleep,
 }
 
 /// Trait implemented by displays to provide implemenation of core functionality.
@@ -32,20 +107,93 @@ pub trait DisplayInterface {
     where
         I: IntoIterator<Item = &'a u8>;
 
+    /// Read data returned by the last command.
+    ///
+    /// Not all wirings can read from the controller, the default returns `DisplayError::Unsupported`.
+    fn read_data(&mut self, _buf: &mut [u8]) -> Result<(), DisplayError> {
+        Err(DisplayError::Unsupported)
+    }
+
     fn is_busy_on(&mut self) -> bool;
 
+    /// Phase of the commands sent next, for the errors of the interface, see `DisplayError::Command`.
+    fn set_phase(&mut self, _phase: Phase) {}
+
+    /// Bytes sent since the interface was created, commands included, `None` if not counted.
+    fn bytes_sent(&self) -> Option<u64> {
+        None
+    }
+
+    /// Called repeatedly while waiting for the controller to be ready.
+    fn busy_idle(&mut self) {
+        core::hint::spin_loop();
+    }
+
     /// Hard reset
     fn reset<D>(&mut self, delay: &mut D, initial_delay: u32, duration: u32)
     where
         D: DelayNs;
 }
 
+/// Strategy for waiting while the controller is busy, refreshes take up to several seconds.
+pub trait BusyWait {
+    /// Called repeatedly as long as the controller is busy.
+    fn idle(&mut self);
+}
+
+/// Busy loop, the default.
+pub struct Spin;
+
+impl BusyWait for Spin {
+    fn idle(&mut self) {
+        core::hint::spin_loop();
+    }
+}
+
+/// Poll the BUSY pin every `interval_us`, delaying in between.
+pub struct DelayPoll<D> {
+    delay: D,
+    interval_us: u32,
+}
+
+impl<D: DelayNs> DelayPoll<D> {
+    pub fn new(delay: D, interval_us: u32) -> Self {
+        Self { delay, interval_us }
+    }
+
+    pub fn release(self) -> D {
+        self.delay
+    }
+}
+
+impl<D: DelayNs> BusyWait for DelayPoll<D> {
+    fn idle(&mut self) {
+        self.delay.delay_us(self.interval_us);
+    }
+}
+
+/// A user hook, e.g. to wait for an interrupt with WFI or to feed a watchdog.
+impl<F: FnMut()> BusyWait for F {
+    fn idle(&mut self) {
+        self()
+    }
+}
+
+/// Largest transfer chunk of `EpdInterface`, also the size of its buffer on the stack.
+pub const MAX_CHUNK: usize = 256;
+
 /// E-Paper Display SPI display interface.
-pub struct EpdInterface<SPI, DC, RST, BUSY> {
+pub struct EpdInterface<SPI, DC, RST, BUSY, W = Spin> {
     spi: SPI,
     dc: DC,
     rst: RST,
     busy: BUSY,
+    busy_wait: W,
+    /// Most bytes per SPI transfer, `None` sends each slice in one transfer
+    chunk_size: Option<usize>,
+    command: u8,
+    phase: Phase,
+    bytes_sent: u64,
 }
 
 impl<SPI, DC, RST, BUSY> EpdInterface<SPI, DC, RST, BUSY>
@@ -56,7 +204,64 @@ where
     BUSY: InputPin,
 {
     pub fn new(spi: SPI, dc: DC, rst: RST, busy: BUSY) -> Self {
-        EpdInterface { spi, dc, rst, busy }
+        EpdInterface {
+            spi,
+            dc,
+            rst,
+            busy,
+            busy_wait: Spin,
+            chunk_size: None,
+            command: 0,
+            phase: Phase::Init,
+            bytes_sent: 0,
+        }
+    }
+}
+
+impl<SPI, DC, RST, BUSY, W> EpdInterface<SPI, DC, RST, BUSY, W>
+where
+    SPI: embedded_hal::spi::SpiDevice,
+    DC: OutputPin,
+    RST: OutputPin,
+    BUSY: InputPin,
+    W: BusyWait,
+{
+    /// Replace the busy loop used while waiting for the controller.
+    pub fn with_busy_wait<W2: BusyWait>(
+        self,
+        busy_wait: W2,
+    ) -> EpdInterface<SPI, DC, RST, BUSY, W2> {
+        EpdInterface {
+            spi: self.spi,
+            dc: self.dc,
+            rst: self.rst,
+            busy: self.busy,
+            busy_wait,
+            chunk_size: self.chunk_size,
+            command: self.command,
+            phase: self.phase,
+            bytes_sent: self.bytes_sent,
+        }
+    }
+
+    /// Set the most bytes written per SPI transfer, clamped to 1..=`MAX_CHUNK`.
+    ///
+    /// By default each slice of data is written in a single transfer, and data sent from an iterator is
+    /// gathered in chunks of `MAX_CHUNK` bytes. Smaller chunks bound the time the bus is held.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size.clamp(1, MAX_CHUNK));
+        self
+    }
+
+    /// The chunk size set by `with_chunk_size`, `None` if not set.
+    pub fn chunk_size(&self) -> Option<usize> {
+        self.chunk_size
+    }
+
+    /// Context of the errors of the last command sent.
+    fn in_command(&self) -> impl Fn(DisplayError) -> DisplayError {
+        let (cmd, phase) = (self.command, self.phase);
+        move |e| e.in_command(cmd, phase)
     }
 
     /// Consume the display interface and return
@@ -66,62 +271,120 @@ where
     }
 }
 
-impl<SPI, DC, RST, BUSY> DisplayInterface for EpdInterface<SPI, DC, RST, BUSY>
+impl<SPI, DC, RST, BUSY, W> DisplayInterface for EpdInterface<SPI, DC, RST, BUSY, W>
 where
     SPI: embedded_hal::spi::SpiDevice,
     DC: OutputPin,
     RST: OutputPin,
     BUSY: InputPin,
+    W: BusyWait,
 {
     /// Send a command to the controller.
     fn send_command(&mut self, command: u8) -> Result<(), DisplayError> {
+        self.command = command;
+        self.bytes_sent += 1;
+        let context = self.in_command();
         // 1 = data, 0 = command
-        self.dc.set_low().map_err(|_| DisplayError::DCError)?;
+        self.dc
+            .set_low()
+            .map_err(|_| DisplayError::DCError)
+            .map_err(&context)?;
 
         // Send words over SPI
-        let ret = self
-            .spi
+        self.spi
             .write(&[command])
-            .map_err(|_| DisplayError::BusWriteError);
-
-        ret
+            .map_err(|e| DisplayError::Spi(e.kind()))
+            .map_err(context)
     }
 
     /// Send data for a command.
     fn send_data(&mut self, data: &[u8]) -> Result<(), DisplayError> {
+        let context = self.in_command();
         // 1 = data, 0 = command
-        self.dc.set_high().map_err(|_| DisplayError::DCError)?;
+        self.dc
+            .set_high()
+            .map_err(|_| DisplayError::DCError)
+            .map_err(&context)?;
 
-        // Send words over SPI
-        let ret = self
-            .spi
-            .write(data)
-            .map_err(|_| DisplayError::BusWriteError);
-
-        ret
+        // Send words over SPI, in one transfer unless a chunk size was set
+        for chunk in data.chunks(self.chunk_size.unwrap_or(usize::MAX)) {
+            self.spi
+                .write(chunk)
+                .map_err(|e| DisplayError::Spi(e.kind()))
+                .map_err(&context)?;
+            self.bytes_sent += chunk.len() as u64;
+        }
+        Ok(())
     }
 
     fn send_data_from_iter<'a, I>(&mut self, iter: I) -> Result<usize, DisplayError>
     where
         I: IntoIterator<Item = &'a u8>,
     {
-        self.dc.set_high().map_err(|_| DisplayError::DCError)?;
+        let context = self.in_command();
+        self.dc
+            .set_high()
+            .map_err(|_| DisplayError::DCError)
+            .map_err(&context)?;
 
+        let chunk_size = self.chunk_size.unwrap_or(MAX_CHUNK);
+        let mut buf = [0u8; MAX_CHUNK];
+        let mut len = 0;
         let mut n = 0;
         for &d in iter {
+            buf[len] = d;
+            len += 1;
             n += 1;
+            if len == chunk_size {
+                self.spi
+                    .write(&buf[..len])
+                    .map_err(|e| DisplayError::Spi(e.kind()))
+                    .map_err(&context)?;
+                self.bytes_sent += len as u64;
+                len = 0;
+            }
+        }
+        if len > 0 {
             self.spi
-                .write(&[d])
-                .map_err(|_| DisplayError::BusWriteError)?;
+                .write(&buf[..len])
+                .map_err(|e| DisplayError::Spi(e.kind()))
+                .map_err(&context)?;
+            self.bytes_sent += len as u64;
         }
 
         Ok(n)
     }
 
+    /// Read data from the controller, requires its data line to be connected to MISO.
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<(), DisplayError> {
+        let context = self.in_command();
+        self.dc
+            .set_high()
+            .map_err(|_| DisplayError::DCError)
+            .map_err(&context)?;
+
+        self.spi
+            .read(buf)
+            .map_err(|e| DisplayError::Spi(e.kind()))
+            .map_err(context)
+    }
+
     fn is_busy_on(&mut self) -> bool {
         self.busy.is_high().unwrap_or(false)
     }
 
+    fn set_phase(&mut self, phase: Phase) {
+        self.phase = phase;
+    }
+
+    fn bytes_sent(&self) -> Option<u64> {
+        Some(self.bytes_sent)
+    }
+
+    fn busy_idle(&mut self) {
+        self.busy_wait.idle();
+    }
+
     fn reset<D>(&mut self, delay: &mut D, initial_delay: u32, duration: u32)
     where
         D: DelayNs,
@@ -1,148 +1,2396 @@
 #![no_std]
 #![allow(incomplete_features)]
-#![feature(generic_const_exprs)]
-#![feature(generic_arg_infer)]
+#![cfg_attr(feature = "nightly", feature(generic_const_exprs))]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[macro_use]
+mod fmt;
 
 pub mod color;
+#[cfg(feature = "eh02")]
+pub mod compat;
 pub mod display;
 pub mod drivers;
+#[cfg(feature = "eg-0_7")]
+pub mod eg07;
+pub mod group;
+pub mod image;
 pub mod interface;
+pub mod lut;
+pub mod policy;
+pub mod prelude;
+pub mod rle;
 
 use core::marker::PhantomData;
 
-use color::GrayColorInBits;
 pub use color::TriColor;
-use defmt::println;
-use display::{DisplaySize, FrameBuffer, GrayFrameBuffer};
-use drivers::{Driver, FastUpdateDriver, GrayScaleDriver, MultiColorDriver};
+use color::{ColorEncoder, TriColorEncoder};
+use display::{DirtyRects, DisplaySize, EncodedTarget, FrameBuffer, Mirroring, SpiSramFrameBuffer};
+use drivers::{
+    BorderColor, Driver, DrivingVoltages, DynDriver, FastBlackWhiteDriver, FastUpdateDriver,
+    MultiColorDriver, OtpInfo, PanelState, Plane, PowerControl, SleepMode, Status,
+};
 use embedded_graphics::{
     pixelcolor::BinaryColor,
-    prelude::{Dimensions, DrawTarget, GrayColor, PixelColor},
+    prelude::{Dimensions, DrawTarget, PixelColor, Point, PointsIter, Size},
     primitives::Rectangle,
     Pixel,
 };
-use interface::DisplayInterface;
+use embedded_hal::spi::SpiDevice;
+pub use group::{EpdGroup, GroupRefresh};
 pub use interface::EpdInterface;
+use interface::{DisplayError, DisplayInterface, Phase};
+use policy::{Clock, PixelShift, RateLimiter, RefreshKind, RefreshPolicy};
+#[cfg(feature = "nightly")]
+use {
+    color::GrayColorInBits,
+    display::{ArrayStorage, GrayFrameBuffer},
+    drivers::{FourGrayDriver, GrayScaleDriver, GrayScaleTiming},
+    embedded_graphics::{pixelcolor::Gray2, prelude::GrayColor},
+};
 
 use crate::drivers::WaveformDriver;
 
-pub struct Epd<I: DisplayInterface, S: DisplaySize, D: Driver>
-where
-    [(); S::N]:,
-{
+pub struct Epd<
+    I: DisplayInterface,
+    S,
+    D: Driver,
+    #[cfg(feature = "nightly")] B = ArrayStorage<S>,
+    #[cfg(not(feature = "nightly"))] B,
+    C = fn() -> u32,
+> {
     pub interface: I,
-    pub framebuf: FrameBuffer<S>,
+    pub framebuf: FrameBuffer<S, B>,
+    initialized: bool,
+    auto_sleep: Option<SleepMode>,
+    progress: Option<fn(RefreshStage)>,
+    /// The controller RAM holds the current framebuffer content
+    frame_in_ram: bool,
+    border: Option<BorderColor>,
+    /// Copy of the last frame sent, see `set_shadow`
+    shadow: Option<FrameBuffer<S, B>>,
+    /// The controller RAM holds the shadow content
+    shadow_in_ram: bool,
+    /// Read back a sample of the frame after sending it, see `set_verify`
+    verify: bool,
+    /// Put to sleep by `sleep`, as opposed to never initialized
+    asleep: bool,
+    /// Delay in µs used to wake up a sleeping display on refresh, see `set_wake_delay`
+    wake_delay: Option<fn(u32)>,
+    /// Result of the last `detect_panel_break`, reported by `status`
+    panel_state: Option<PanelState>,
+    /// Moves the frame on the panel, see `set_pixel_shift`
+    pixel_shift: Option<PixelShift>,
+    /// Areas queued by `invalidate`, sent by `present`
+    dirty: DirtyRects,
+    /// Guard against too frequent refreshes, see `set_rate_limiter`
+    rate_limiter: Option<RateLimiter>,
+    /// Counters reported by `stats`
+    stats: EpdStats,
+    /// Time source of the rate limiter and of the busy time of `stats`, see `with_clock`
+    clock: Option<C>,
+    /// Set the VCOM programmed in OTP on `init` and `wake_up`, see `set_otp_vcom`
+    otp_vcom: bool,
     _phantom: PhantomData<(S, D)>,
 }
 
+#[cfg(feature = "nightly")]
 impl<DI: DisplayInterface, S: DisplaySize, D: Driver> Epd<DI, S, D>
 where
     [(); S::N]:,
 {
     pub fn new(interface: DI) -> Self {
-        Self {
+        Self::from_framebuffer(
             interface,
-            framebuf: if D::BLACK_BIT == false {
+            if !D::BLACK_BIT {
                 FrameBuffer::new_ones()
             } else {
                 FrameBuffer::new()
             },
+        )
+    }
+}
+
+impl<DI: DisplayInterface, S: DisplaySize, D: Driver, B: AsRef<[u8]> + AsMut<[u8]>>
+    Epd<DI, S, D, B>
+{
+    /// Create the display with the framebuffer in user supplied storage of `S::N` bytes.
+    pub fn new_in(interface: DI, buf: B) -> Result<Self, DisplayError> {
+        let mut framebuf = FrameBuffer::new_in(buf)?;
+        framebuf.clear(BinaryColor::from(!D::BLACK_BIT)).ok();
+        Ok(Self::from_framebuffer(interface, framebuf))
+    }
+
+    /// Create the display with only a strip of whole rows in `buf`, to be drawn with `render_paged`.
+    ///
+    /// `display_frame` only writes the first strip to such a display.
+    pub fn new_paged(interface: DI, buf: B) -> Result<Self, DisplayError> {
+        Ok(Self::from_framebuffer(
+            interface,
+            FrameBuffer::new_strip(buf)?,
+        ))
+    }
+}
+
+impl<DI: DisplayInterface, S, D: Driver, B: AsRef<[u8]> + AsMut<[u8]>> Epd<DI, S, D, B> {
+    /// Create the display around an existing framebuffer, e.g. a runtime sized one.
+    pub fn with_framebuffer(interface: DI, framebuf: FrameBuffer<S, B>) -> Self {
+        Self::from_framebuffer(interface, framebuf)
+    }
+}
+
+impl<DI: DisplayInterface, S, D: Driver, B> Epd<DI, S, D, B> {
+    fn from_framebuffer(interface: DI, framebuf: FrameBuffer<S, B>) -> Self {
+        Self {
+            interface,
+            framebuf,
+            initialized: false,
+            auto_sleep: None,
+            progress: None,
+            frame_in_ram: false,
+            border: None,
+            shadow: None,
+            shadow_in_ram: false,
+            verify: false,
+            asleep: false,
+            wake_delay: None,
+            panel_state: None,
+            pixel_shift: None,
+            dirty: DirtyRects::default(),
+            rate_limiter: None,
+            stats: EpdStats::new(),
+            clock: None,
+            otp_vcom: false,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<DI: DisplayInterface, S, D: Driver, B: AsRef<[u8]> + AsMut<[u8]>, C: Clock>
+    Epd<DI, S, D, B, C>
+{
+    /// Consume the display and return the interface and framebuffer.
+    pub fn release(self) -> (DI, FrameBuffer<S, B>) {
+        (self.interface, self.framebuf)
+    }
+
+    pub fn init<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.frame_in_ram = false;
+        self.shadow_in_ram = false;
+        self.interface.set_phase(Phase::Init);
+        D::wake_up(&mut self.interface, delay)?;
+        let size = self.framebuf.native_size();
+        set_shape::<D, _>(
+            &mut self.interface,
+            size.width as _,
+            size.height as _,
+            self.framebuf.ram_offset(),
+        )?;
+        if let Some(color) = self.border {
+            D::set_border(&mut self.interface, color)?;
+        }
+        self.initialized = true;
+        self.asleep = false;
+        if self.otp_vcom {
+            self.apply_otp_vcom()?;
+        }
+        Ok(())
+    }
+
+    pub fn set_rotation(&mut self, rotation: i32) {
+        self.framebuf.set_rotation(rotation);
+    }
+
+    pub fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.framebuf.set_mirroring(mirroring);
+    }
+
+    /// Rotate the frame while it is sent instead of while drawing, see `FrameBuffer::set_flush_rotation`.
+    pub fn set_flush_rotation(&mut self, rotation: i32) -> Result<(), DisplayError> {
+        self.frame_in_ram = false;
+        self.framebuf.set_flush_rotation(rotation)
+    }
+
+    /// Flip the rows of `Rotate180` and vertical mirroring while the frame is sent instead of while drawing,
+    /// see `FrameBuffer::set_hardware_flip`.
+    ///
+    /// Drivers setting `Driver::DATA_ENTRY_FLIP` flip them in the controller, others while sending.
+    pub fn set_hardware_flip(&mut self, on: bool) {
+        self.frame_in_ram = false;
+        self.framebuf.set_hardware_flip(on);
+    }
+
+    /// Select the border color, kept over `sleep` and `wake_up`.
+    ///
+    /// The controller's default border is used until this is called.
+    pub fn set_border(&mut self, color: BorderColor) -> Result<(), D::Error> {
+        self.border = Some(color);
+        if self.initialized {
+            D::set_border(&mut self.interface, color)?;
+        }
+        Ok(())
+    }
+
+    /// Swap black and white for the whole display, e.g. for a dark mode.
+    ///
+    /// The current framebuffer content is inverted as well, drawing code stays unchanged.
+    pub fn set_inverted(&mut self, inverted: bool) {
+        self.frame_in_ram = false;
+        self.framebuf.set_inverted(inverted);
+    }
+
+    /// Keep a copy of the last frame sent in `shadow`, so that `display_frame` only sends the changed span
+    /// of each row, through RAM windows. `None` goes back to sending whole frames.
+    ///
+    /// Saves most of the transfer for small changes on large panels. The first frame is sent whole.
+    /// Requires a driver supporting `Driver::update_frame_window`, and a shadow as large as `framebuf`.
+    pub fn set_shadow(&mut self, shadow: Option<FrameBuffer<S, B>>) -> Result<(), DisplayError> {
+        if let Some(shadow) = &shadow {
+            if shadow.as_bytes().len() != self.framebuf.as_bytes().len() {
+                return Err(DisplayError::InvalidFormatError);
+            }
+        }
+        self.shadow = shadow;
+        self.shadow_in_ram = false;
+        Ok(())
+    }
+
+    /// Read back the first, middle and last rows of the controller RAM after `display_frame` sends the frame,
+    /// failing with `DisplayError::VerifyFailed` before the refresh if they differ from the framebuffer.
+    ///
+    /// Catches a loose or noisy bus. Requires a driver supporting `Driver::read_frame_row`,
+    /// and an interface that can read, only the first 64 bytes of each row are compared.
+    pub fn set_verify(&mut self, on: bool) {
+        self.verify = on;
+    }
+
+    /// Move the frame sent by `display_frame` on a schedule to avoid burn-in, see `PixelShift`.
+    /// `None` sends the frame as drawn again.
+    ///
+    /// Drawing code is unchanged, the framebuffer keeps the content as drawn. Shifted frames are sent whole,
+    /// without `set_shadow` or `set_verify`. Panels up to 2048 pixels wide are supported.
+    pub fn set_pixel_shift(&mut self, shift: Option<PixelShift>) {
+        self.pixel_shift = shift;
+    }
+
+    pub fn display_frame(&mut self) -> Result<(), D::Error> {
+        self.send_frame()?;
+        self.turn_on_display(false)
+    }
+
+    /// Send the framebuffer like `display_frame` and start the refresh without waiting for it to complete.
+    ///
+    /// `wait_refresh` must be called before using the display again, e.g. after starting the other
+    /// displays of an `EpdGroup`.
+    pub fn start_frame(&mut self) -> Result<(), D::Error> {
+        self.send_frame()?;
+        if !allow_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Full,
+        )? {
+            return Ok(());
+        }
+        progress(self.progress, RefreshStage::RefreshStarted);
+        self.interface.set_phase(Phase::Refresh);
+        D::start_refresh(&mut self.interface)?;
+        self.stats.full_refreshes += 1;
+        record_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Full,
+        );
+        Ok(())
+    }
+
+    /// Wait for the refresh started by `start_frame` to complete.
+    pub fn wait_refresh(&mut self) -> Result<(), D::Error> {
+        let start = now(&mut self.clock);
+        D::busy_wait(&mut self.interface)?;
+        self.stats.add_busy(start, now(&mut self.clock));
+        progress(self.progress, RefreshStage::BusyReleased);
+        Ok(())
+    }
+
+    fn send_frame(&mut self) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        self.frame_in_ram = false;
+        self.dirty = DirtyRects::default();
+        let size = self.framebuf.native_size();
+        let shift = self
+            .pixel_shift
+            .as_mut()
+            .map_or((0, 0), PixelShift::next_frame);
+        let flipped = self.framebuf.rows_flipped();
+        if shift != (0, 0) || flipped || self.framebuf.flush_rotated() {
+            self.shadow_in_ram = false;
+            if flipped && shift == (0, 0) && D::DATA_ENTRY_FLIP {
+                update_frame_flipped::<D, _>(
+                    &mut self.interface,
+                    size.width as _,
+                    size.height as _,
+                    self.framebuf.ram_offset(),
+                    self.framebuf.as_bytes(),
+                )?;
+            } else {
+                update_frame_native::<D, _, _, _>(&mut self.interface, &self.framebuf, shift)?;
+            }
+            self.frame_in_ram = true;
+            progress(self.progress, RefreshStage::FrameTransferred);
+            return Ok(());
+        }
+        match &mut self.shadow {
+            Some(shadow) if self.shadow_in_ram => update_frame_changes::<D, _>(
+                &mut self.interface,
+                size.width as _,
+                size.height as _,
+                self.framebuf.ram_offset(),
+                self.framebuf.as_bytes(),
+                shadow.as_bytes(),
+            )?,
+            _ => update_frame::<D, _>(
+                &mut self.interface,
+                size.width as _,
+                size.height as _,
+                self.framebuf.ram_offset(),
+                self.framebuf.as_bytes(),
+            )?,
+        }
+        if self.verify {
+            if let Err(e) = verify_frame::<D, _>(
+                &mut self.interface,
+                size.width as _,
+                self.framebuf.ram_offset(),
+                self.framebuf.as_bytes(),
+            ) {
+                self.shadow_in_ram = false;
+                return Err(e);
+            }
+        }
+        if let Some(shadow) = &mut self.shadow {
+            shadow.copy_from_slice(self.framebuf.as_bytes())?;
+            self.shadow_in_ram = true;
+        }
+        self.frame_in_ram = true;
+        progress(self.progress, RefreshStage::FrameTransferred);
+        Ok(())
+    }
+
+    /// Queue `area` to be sent by `present`, e.g. after redrawing a widget.
+    ///
+    /// Overlapping areas are merged, beyond 8 areas the closest ones are merged as well.
+    pub fn invalidate(&mut self, area: &Rectangle) {
+        if let Some(rect) = self.framebuf.native_rect(area) {
+            self.dirty.add(rect);
+        }
+    }
+
+    /// Send the areas queued by `invalidate` through RAM windows, then refresh the display once.
+    ///
+    /// Does nothing if nothing was queued. Requires a driver supporting `Driver::update_frame_window`.
+    /// With a `PixelShift`, a flush rotation or a hardware flip, the whole frame is sent by `display_frame` instead.
+    pub fn present(&mut self) -> Result<(), D::Error> {
+        if self.dirty.is_empty() {
+            return Ok(());
+        }
+        if self.pixel_shift.is_some()
+            || self.framebuf.flush_rotated()
+            || self.framebuf.rows_flipped()
+        {
+            return self.display_frame();
+        }
+        self.ensure_awake()?;
+        // the rest of the RAM may not match the framebuffer
+        self.frame_in_ram = false;
+        self.shadow_in_ram = false;
+        let size = self.framebuf.native_size();
+        let (width, height) = (size.width as usize, size.height as usize);
+        let offset = self.framebuf.ram_offset();
+        let stride = width.div_ceil(8);
+        self.interface.set_phase(Phase::FrameTransfer);
+        for (x0, y0, x1, y1) in self.dirty.take() {
+            let rows = self.framebuf.as_bytes().chunks_exact(stride);
+            D::update_frame_window(
+                &mut self.interface,
+                (offset.0 + x0) as _,
+                (offset.1 + y0) as _,
+                (x1 - x0) as _,
+                (y1 - y0) as _,
+                rows.skip(y0)
+                    .take(y1 - y0)
+                    .flat_map(|row| &row[x0 / 8..x1 / 8]),
+            )?;
+        }
+        set_shape::<D, _>(&mut self.interface, width, height, offset)?;
+        progress(self.progress, RefreshStage::FrameTransferred);
+        self.turn_on_display(true)
+    }
+
+    /// Display the framebuffer, without sending it again if the controller RAM still holds it,
+    /// e.g. after waking up from a `SleepMode` that retains RAM.
+    ///
+    /// Only drawing through the `Epd` itself is tracked, use `display_frame` after modifying `framebuf` directly.
+    pub fn refresh(&mut self) -> Result<(), D::Error> {
+        if !self.frame_in_ram {
+            return self.display_frame();
+        }
+        self.ensure_awake()?;
+        self.turn_on_display(false)
+    }
+
+    /// Fill the framebuffer with `color` and refresh the display.
+    pub fn clear_display(&mut self, color: BinaryColor) -> Result<(), D::Error> {
+        self.framebuf.clear(color).ok();
+        self.display_frame()
+    }
+
+    /// Display an externally prepared frame, bypassing `framebuf`.
+    ///
+    /// `data` is raw 1bpp data in native orientation, laid out like `framebuf`.
+    pub fn display_raw_frame(&mut self, data: &[u8]) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        self.frame_in_ram = false;
+        self.shadow_in_ram = false;
+        let size = self.framebuf.native_size();
+        update_frame::<D, _>(
+            &mut self.interface,
+            size.width as _,
+            size.height as _,
+            self.framebuf.ram_offset(),
+            data,
+        )?;
+        progress(self.progress, RefreshStage::FrameTransferred);
+        self.turn_on_display(false)
+    }
+
+    /// Render and display a frame strip by strip, so that the framebuffer can be much smaller than the panel.
+    ///
+    /// `draw` is called once per strip with the whole display as target, drawing outside of
+    /// the current strip is clipped. It must draw the same content on every call.
+    pub fn render_paged<F>(&mut self, mut draw: F) -> Result<(), D::Error>
+    where
+        F: FnMut(&mut FrameBuffer<S, B>),
+    {
+        self.ensure_awake()?;
+        self.frame_in_ram = false;
+        self.shadow_in_ram = false;
+        let size = self.framebuf.native_size();
+        let (width_in_byte, height) = ((size.width as usize).div_ceil(8), size.height as usize);
+        let rows = self.framebuf.rows();
+
+        let mut result = Ok(());
+        for y in (0..height).step_by(rows) {
+            self.framebuf.set_first_row(y);
+            self.framebuf.clear(BinaryColor::from(!D::BLACK_BIT)).ok();
+            draw(&mut self.framebuf);
+
+            let n = rows.min(height - y) * width_in_byte;
+            result = update_rows::<D, _>(
+                &mut self.interface,
+                size.width as _,
+                height,
+                self.framebuf.ram_offset(),
+                y,
+                &self.framebuf.as_bytes()[..n],
+            );
+            if result.is_err() {
+                break;
+            }
+        }
+        self.framebuf.set_first_row(0);
+        result?;
+
+        progress(self.progress, RefreshStage::FrameTransferred);
+        self.turn_on_display(false)
+    }
+
+    /// Read the panel temperature in °C from the controller's internal sensor.
+    ///
+    /// On SSD controllers this reloads the waveform from OTP, see `Driver::read_temperature`.
+    pub fn read_temperature(&mut self) -> Result<i8, D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::read_temperature(&mut self.interface)
+    }
+
+    /// Read the controller status, e.g. to report the display health over telemetry.
+    ///
+    /// `Status::panel_break` holds the result of the last `detect_panel_break`.
+    pub fn status(&mut self) -> Result<Status, D::Error> {
+        ensure_initialized(self.initialized)?;
+        let mut status = D::read_status(&mut self.interface)?;
+        status.panel_break = self.panel_state.map(|state| state == PanelState::Broken);
+        Ok(status)
+    }
+
+    /// Check the panel for cracks or a broken connection, see `Driver::detect_panel_break`.
+    pub fn detect_panel_break(&mut self) -> Result<PanelState, D::Error> {
+        ensure_initialized(self.initialized)?;
+        let state = D::detect_panel_break(&mut self.interface)?;
+        self.panel_state = Some(state);
+        Ok(state)
+    }
+
+    /// Set the ambient temperature in °C used to select the waveform, for panels without a sensor.
+    ///
+    /// Must be called again after `init` or `wake_up`.
+    pub fn set_temperature(&mut self, celsius: i8) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::set_temperature(&mut self.interface, celsius)
+    }
+
+    /// Set the gate and source driving voltages, must be called again after `init` or `wake_up`.
+    pub fn set_driving_voltages(&mut self, voltages: &DrivingVoltages) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::set_driving_voltages(&mut self.interface, voltages)
+    }
+
+    /// Read the VCOM and waveform version programmed in OTP by the panel vendor.
+    pub fn read_otp_info(&mut self) -> Result<OtpInfo, D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::read_otp_info(&mut self.interface)
+    }
+
+    /// Set the VCOM programmed in OTP instead of the one of the driver, must be called again after `init` or `wake_up`.
+    pub fn apply_otp_vcom(&mut self) -> Result<OtpInfo, D::Error> {
+        let info = self.read_otp_info()?;
+        D::set_vcom(&mut self.interface, info.vcom)?;
+        Ok(info)
+    }
+
+    /// Apply the VCOM programmed in OTP on every `init` and `wake_up`, including the automatic wake up of
+    /// `set_wake_delay`, instead of the VCOM of the driver.
+    ///
+    /// Takes effect from the next `init` or `wake_up`, which fail if the controller cannot read its OTP.
+    pub fn set_otp_vcom(&mut self, enable: bool) {
+        self.otp_vcom = enable;
+    }
+
+    pub fn sleep<DELAY>(&mut self, delay: &mut DELAY, mode: SleepMode) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.initialized = false;
+        self.frame_in_ram &= mode.retains_ram();
+        self.shadow_in_ram &= mode.retains_ram();
+        self.interface.set_phase(Phase::Sleep);
+        D::sleep(&mut self.interface, delay, mode)?;
+        self.asleep = true;
+        Ok(())
+    }
+
+    /// Re-initialize the controller after `sleep`, does nothing if the display is awake.
+    ///
+    /// If the `SleepMode` retained RAM, `refresh` skips sending an unchanged framebuffer again.
+    pub fn wake_up<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        if self.initialized {
+            return Ok(());
+        }
+        self.interface.set_phase(Phase::Init);
+        D::wake_up(&mut self.interface, delay)?;
+        let size = self.framebuf.native_size();
+        set_shape::<D, _>(
+            &mut self.interface,
+            size.width as _,
+            size.height as _,
+            self.framebuf.ram_offset(),
+        )?;
+        if let Some(color) = self.border {
+            D::set_border(&mut self.interface, color)?;
+        }
+        self.initialized = true;
+        self.asleep = false;
+        if self.otp_vcom {
+            self.apply_otp_vcom()?;
+        }
+        Ok(())
+    }
+
+    /// Enter the deep sleep mode retaining the controller RAM, `SleepMode::DeepSleep1`.
+    ///
+    /// For duty cycles with long sleeps, see `resume`.
+    pub fn hibernate<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.sleep(delay, SleepMode::DeepSleep1)
+    }
+
+    /// Wake up from `hibernate` and display the framebuffer, without uploading it again if unchanged.
+    ///
+    /// With a shadow set by `set_shadow`, only the rows changed while hibernating are sent.
+    pub fn resume<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.wake_up(delay)?;
+        self.refresh()
+    }
+
+    /// Put the display to sleep in `mode` after every `update`, `None` keeps it awake.
+    pub fn set_auto_sleep(&mut self, mode: Option<SleepMode>) {
+        self.auto_sleep = mode;
+    }
+
+    /// Let `display_frame` and `refresh` wake up a display put to sleep by `sleep`, delaying with `delay_us`.
+    ///
+    /// Without it they fail with `DisplayError::Uninitialized` until `wake_up` is called.
+    /// `delay_us` can be a closure without captures, e.g. `|us| cortex_m::asm::delay(us * 64)`.
+    pub fn set_wake_delay(&mut self, delay_us: Option<fn(u32)>) {
+        self.wake_delay = delay_us;
+    }
+
+    /// Wake up the display if it sleeps and a wake delay is set, then check that it is initialized.
+    fn ensure_awake(&mut self) -> Result<(), D::Error> {
+        if let (true, Some(delay_us)) = (self.asleep, self.wake_delay) {
+            self.wake_up(&mut FnDelay(delay_us))?;
+        }
+        ensure_initialized(self.initialized)?;
+        Ok(())
+    }
+
+    /// Call `hook` at each stage of a refresh, e.g. to drive a "refreshing" LED or to take timestamps.
+    pub fn set_progress_hook(&mut self, hook: Option<fn(RefreshStage)>) {
+        self.progress = hook;
+    }
+
+    /// Limit how often the display refreshes, all refreshes of an `Epd` being full ones. `None` never limits.
+    ///
+    /// A deferred frame is left in the controller RAM, `refresh` shows it later.
+    /// The limiter reads the time from the clock set by `with_clock`, refreshes fail with
+    /// `DisplayError::Unsupported` without one.
+    pub fn set_rate_limiter(&mut self, limiter: Option<RateLimiter>) {
+        self.rate_limiter = limiter;
+    }
+
+    /// Read the time from `clock`, e.g. a HAL timer, for the rate limiter and the busy time of `stats`.
+    ///
+    /// The clock becomes part of the display type.
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> Epd<DI, S, D, B, C2> {
+        Epd {
+            interface: self.interface,
+            framebuf: self.framebuf,
+            initialized: self.initialized,
+            auto_sleep: self.auto_sleep,
+            progress: self.progress,
+            frame_in_ram: self.frame_in_ram,
+            border: self.border,
+            shadow: self.shadow,
+            shadow_in_ram: self.shadow_in_ram,
+            verify: self.verify,
+            asleep: self.asleep,
+            wake_delay: self.wake_delay,
+            panel_state: self.panel_state,
+            pixel_shift: self.pixel_shift,
+            dirty: self.dirty,
+            rate_limiter: self.rate_limiter,
+            stats: self.stats,
+            clock: Some(clock),
+            otp_vcom: self.otp_vcom,
+            _phantom: self._phantom,
+        }
+    }
+
+    /// The limiter set by `set_rate_limiter`, e.g. to change its intervals or to ask if a refresh was deferred.
+    pub fn rate_limiter(&mut self) -> Option<&mut RateLimiter> {
+        self.rate_limiter.as_mut()
+    }
+
+    /// Refreshes and transfers since the display was created.
+    pub fn stats(&self) -> EpdStats {
+        EpdStats {
+            bytes_sent: self.interface.bytes_sent(),
+            ..self.stats
+        }
+    }
+
+    /// Refresh the display, after the whole frame was sent or only the windows of `present` if `partial`.
+    fn turn_on_display(&mut self, partial: bool) -> Result<(), D::Error> {
+        if !allow_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Full,
+        )? {
+            return Ok(());
+        }
+        progress(self.progress, RefreshStage::RefreshStarted);
+        self.interface.set_phase(Phase::Refresh);
+        let start = now(&mut self.clock);
+        D::turn_on_display(&mut self.interface)?;
+        self.stats.add_busy(start, now(&mut self.clock));
+        if partial {
+            self.stats.partial_refreshes += 1;
+        } else {
+            self.stats.full_refreshes += 1;
+        }
+        record_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Full,
+        );
+        progress(self.progress, RefreshStage::BusyReleased);
+        Ok(())
+    }
+
+    /// Display the framebuffer, waking the display up first if it sleeps, see `set_wake_delay`.
+    ///
+    /// With auto sleep enabled, the display is put back to sleep with `delay` once the refresh is done.
+    pub fn update<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.refresh()?;
+        if let Some(mode) = self.auto_sleep {
+            self.sleep(delay, mode)?;
+        }
+        Ok(())
+    }
+}
+
+impl<DI: DisplayInterface, S, D: PowerControl, B: AsRef<[u8]> + AsMut<[u8]>, C: Clock>
+    Epd<DI, S, D, B, C>
+{
+    /// Power on the booster ahead of a refresh.
+    pub fn power_on(&mut self) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::power_on(&mut self.interface)
+    }
+
+    /// Power off the booster, e.g. after a refresh when the next one is far away.
+    ///
+    /// The next refresh powers on again.
+    pub fn power_off(&mut self) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::power_off(&mut self.interface)
+    }
+}
+
+impl<I: DisplayInterface, S, D: Driver, B: AsRef<[u8]> + AsMut<[u8]>, C> Dimensions
+    for Epd<I, S, D, B, C>
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.framebuf.bounding_box()
+    }
+}
+
+impl<I: DisplayInterface, S, D: Driver, B: AsRef<[u8]> + AsMut<[u8]>, C> DrawTarget
+    for Epd<I, S, D, B, C>
+{
+    type Color = embedded_graphics::pixelcolor::BinaryColor;
+    type Error = DisplayError;
+
+    fn draw_iter<IP>(&mut self, pixels: IP) -> Result<(), Self::Error>
+    where
+        IP: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
+    {
+        self.frame_in_ram = false;
+        self.framebuf.draw_iter(pixels)
+    }
+
+    fn fill_contiguous<IC>(&mut self, area: &Rectangle, colors: IC) -> Result<(), Self::Error>
+    where
+        IC: IntoIterator<Item = Self::Color>,
+    {
+        self.frame_in_ram = false;
+        self.framebuf.fill_contiguous(area, colors)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.frame_in_ram = false;
+        self.framebuf.fill_solid(area, color)
+    }
+}
+
+/// Configure an `Epd` before it is constructed, e.g.
+/// `EpdBuilder::new(di).rotation(90).mirroring(Mirroring::Horizontal).build()`.
+pub struct EpdBuilder<I: DisplayInterface, S, D: Driver> {
+    interface: I,
+    rotation: i32,
+    mirroring: Mirroring,
+    inverted: bool,
+    fill: Option<BinaryColor>,
+    border: Option<BorderColor>,
+    auto_sleep: Option<SleepMode>,
+    wake_delay: Option<fn(u32)>,
+    otp_vcom: bool,
+    _phantom: PhantomData<(S, D)>,
+}
+
+impl<DI: DisplayInterface, S, D: Driver> EpdBuilder<DI, S, D> {
+    pub fn new(interface: DI) -> Self {
+        Self {
+            interface,
+            rotation: 0,
+            mirroring: Mirroring::None,
+            inverted: false,
+            fill: None,
+            border: None,
+            auto_sleep: None,
+            wake_delay: None,
+            otp_vcom: false,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Rotation in degrees, 0, 90, 180 or 270.
+    pub fn rotation(mut self, rotation: i32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn mirroring(mut self, mirroring: Mirroring) -> Self {
+        self.mirroring = mirroring;
+        self
+    }
+
+    /// Invert all pixels sent to the display.
+    pub fn inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    /// Initial content of the framebuffer, white by default.
+    pub fn fill(mut self, color: BinaryColor) -> Self {
+        self.fill = Some(color);
+        self
+    }
+
+    /// Border color, applied on `init`.
+    pub fn border(mut self, color: BorderColor) -> Self {
+        self.border = Some(color);
+        self
+    }
+
+    /// Sleep mode entered after every `update`.
+    pub fn auto_sleep(mut self, mode: SleepMode) -> Self {
+        self.auto_sleep = Some(mode);
+        self
+    }
+
+    /// Delay in µs used to wake up the display on refresh, see `Epd::set_wake_delay`.
+    pub fn wake_delay(mut self, delay_us: fn(u32)) -> Self {
+        self.wake_delay = Some(delay_us);
+        self
+    }
+
+    /// Apply the VCOM programmed in OTP on `init` and `wake_up`, see `Epd::set_otp_vcom`.
+    pub fn otp_vcom(mut self, enable: bool) -> Self {
+        self.otp_vcom = enable;
+        self
+    }
+
+    /// Build the display with the framebuffer in user supplied storage of `S::N` bytes.
+    pub fn build_in<B: AsRef<[u8]> + AsMut<[u8]>>(
+        self,
+        buf: B,
+    ) -> Result<Epd<DI, S, D, B>, DisplayError>
+    where
+        S: DisplaySize,
+    {
+        let framebuf = FrameBuffer::new_in(buf)?;
+        Ok(self.finish(framebuf))
+    }
+
+    fn finish<B: AsRef<[u8]> + AsMut<[u8]>>(self, framebuf: FrameBuffer<S, B>) -> Epd<DI, S, D, B> {
+        let mut epd = Epd::with_framebuffer(self.interface, framebuf);
+        epd.framebuf.clear(BinaryColor::from(!D::BLACK_BIT)).ok();
+        epd.set_inverted(self.inverted);
+        epd.set_rotation(self.rotation);
+        epd.set_mirroring(self.mirroring);
+        epd.border = self.border;
+        epd.auto_sleep = self.auto_sleep;
+        epd.wake_delay = self.wake_delay;
+        epd.otp_vcom = self.otp_vcom;
+        if let Some(color) = self.fill {
+            epd.framebuf.clear(color).ok();
+        }
+        epd
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<DI: DisplayInterface, S: DisplaySize, D: Driver> EpdBuilder<DI, S, D>
+where
+    [(); S::N]:,
+{
+    pub fn build(self) -> Epd<DI, S, D> {
+        let framebuf = FrameBuffer::new();
+        self.finish(framebuf)
+    }
+}
+
+/// EPD display backed by fast update LUT, both fast update and full update are supported.
+pub struct FastUpdateEpd<
+    I: DisplayInterface,
+    S,
+    D: FastUpdateDriver,
+    #[cfg(feature = "nightly")] B = ArrayStorage<S>,
+    #[cfg(not(feature = "nightly"))] B,
+    C = fn() -> u32,
+> {
+    pub interface: I,
+    pub framebuf: FrameBuffer<S, B>,
+    initialized: bool,
+    auto_sleep: Option<SleepMode>,
+    progress: Option<fn(RefreshStage)>,
+    /// Put to sleep by `sleep`, as opposed to never initialized
+    asleep: bool,
+    /// Delay in µs used to wake up a sleeping display on refresh, see `set_wake_delay`
+    wake_delay: Option<fn(u32)>,
+    policy: RefreshPolicy,
+    /// Pixels drawn since the last refresh
+    drawn: usize,
+    rate_limiter: Option<RateLimiter>,
+    stats: EpdStats,
+    clock: Option<C>,
+    _phantom: PhantomData<(S, D)>,
+}
+
+#[cfg(feature = "nightly")]
+impl<DI: DisplayInterface, S: DisplaySize, D: FastUpdateDriver> FastUpdateEpd<DI, S, D>
+where
+    [(); S::N]:,
+{
+    pub fn new(interface: DI) -> Self {
+        Self::from_framebuffer(
+            interface,
+            if !D::BLACK_BIT {
+                FrameBuffer::new_ones()
+            } else {
+                FrameBuffer::new()
+            },
+        )
+    }
+}
+
+impl<DI: DisplayInterface, S: DisplaySize, D: FastUpdateDriver, B: AsRef<[u8]> + AsMut<[u8]>>
+    FastUpdateEpd<DI, S, D, B>
+{
+    /// Create the display with the framebuffer in user supplied storage of `S::N` bytes.
+    pub fn new_in(interface: DI, buf: B) -> Result<Self, DisplayError> {
+        let mut framebuf = FrameBuffer::new_in(buf)?;
+        framebuf.clear(BinaryColor::from(!D::BLACK_BIT)).ok();
+        Ok(Self::from_framebuffer(interface, framebuf))
+    }
+}
+
+impl<DI: DisplayInterface, S, D: FastUpdateDriver, B: AsRef<[u8]> + AsMut<[u8]>>
+    FastUpdateEpd<DI, S, D, B>
+{
+    /// Create the display around an existing framebuffer, e.g. a runtime sized one.
+    pub fn with_framebuffer(interface: DI, framebuf: FrameBuffer<S, B>) -> Self {
+        Self::from_framebuffer(interface, framebuf)
+    }
+}
+
+impl<DI: DisplayInterface, S, D: FastUpdateDriver, B> FastUpdateEpd<DI, S, D, B> {
+    fn from_framebuffer(interface: DI, framebuf: FrameBuffer<S, B>) -> Self {
+        Self {
+            interface,
+            framebuf,
+            initialized: false,
+            auto_sleep: None,
+            progress: None,
+            asleep: false,
+            wake_delay: None,
+            policy: RefreshPolicy::new(),
+            drawn: 0,
+            rate_limiter: None,
+            stats: EpdStats::new(),
+            clock: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<DI: DisplayInterface, S, D: FastUpdateDriver, B: AsRef<[u8]> + AsMut<[u8]>, C: Clock>
+    FastUpdateEpd<DI, S, D, B, C>
+{
+    /// Consume the display and return the interface and framebuffer.
+    pub fn release(self) -> (DI, FrameBuffer<S, B>) {
+        (self.interface, self.framebuf)
+    }
+
+    pub fn init<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.interface.set_phase(Phase::Init);
+        D::wake_up(&mut self.interface, delay)?;
+        let size = self.framebuf.native_size();
+        set_shape::<D, _>(
+            &mut self.interface,
+            size.width as _,
+            size.height as _,
+            self.framebuf.ram_offset(),
+        )?;
+        self.interface.set_phase(Phase::LutLoad);
+        D::setup_fast_waveform(&mut self.interface)?;
+        self.initialized = true;
+        self.asleep = false;
+        Ok(())
+    }
+
+    pub fn set_rotation(&mut self, rotation: i32) {
+        self.framebuf.set_rotation(rotation);
+    }
+
+    pub fn display_frame(&mut self) -> Result<(), D::Error> {
+        self.refresh_as(RefreshKind::Fast)?;
+        Ok(())
+    }
+
+    pub fn display_frame_full_update(&mut self) -> Result<(), D::Error> {
+        self.refresh_as(RefreshKind::Full)?;
+        Ok(())
+    }
+
+    /// Send the framebuffer and refresh it with the waveform of `kind`, `Ok(false)` if the refresh was
+    /// deferred by the rate limiter.
+    fn refresh_as(&mut self, kind: RefreshKind) -> Result<bool, D::Error> {
+        self.ensure_awake()?;
+        if kind == RefreshKind::None
+            || !allow_refresh(&mut self.rate_limiter, now(&mut self.clock), kind)?
+        {
+            return Ok(false);
+        }
+        self.drawn = 0;
+        if kind == RefreshKind::Full {
+            self.interface.set_phase(Phase::LutLoad);
+            D::restore_normal_waveform(&mut self.interface)?;
+        }
+        let size = self.framebuf.native_size();
+        update_frame::<D, _>(
+            &mut self.interface,
+            size.width as _,
+            size.height as _,
+            self.framebuf.ram_offset(),
+            self.framebuf.as_bytes(),
+        )?;
+        progress(self.progress, RefreshStage::FrameTransferred);
+        self.turn_on_display()?;
+        record_refresh(&mut self.rate_limiter, now(&mut self.clock), kind);
+        if kind == RefreshKind::Full {
+            self.stats.full_refreshes += 1;
+            self.interface.set_phase(Phase::LutLoad);
+            D::setup_fast_waveform(&mut self.interface)?;
+        } else {
+            self.stats.fast_refreshes += 1;
+        }
+        Ok(true)
+    }
+
+    pub fn set_refresh_policy(&mut self, policy: RefreshPolicy) {
+        self.policy = policy;
+    }
+
+    /// Limit how often the display refreshes, `None` never limits.
+    ///
+    /// Deferred frames are sent again by the next refresh. The limiter reads the time from the clock set
+    /// by `with_clock`, refreshes fail with `DisplayError::Unsupported` without one.
+    pub fn set_rate_limiter(&mut self, limiter: Option<RateLimiter>) {
+        self.rate_limiter = limiter;
+    }
+
+    /// Read the time from `clock`, e.g. a HAL timer, for the refresh policy, the rate limiter and the
+    /// busy time of `stats`.
+    ///
+    /// The clock becomes part of the display type.
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> FastUpdateEpd<DI, S, D, B, C2> {
+        FastUpdateEpd {
+            interface: self.interface,
+            framebuf: self.framebuf,
+            initialized: self.initialized,
+            auto_sleep: self.auto_sleep,
+            progress: self.progress,
+            asleep: self.asleep,
+            wake_delay: self.wake_delay,
+            policy: self.policy,
+            drawn: self.drawn,
+            rate_limiter: self.rate_limiter,
+            stats: self.stats,
+            clock: Some(clock),
+            _phantom: self._phantom,
+        }
+    }
+
+    /// The limiter set by `set_rate_limiter`, e.g. to change its intervals or to ask if a refresh was deferred.
+    pub fn rate_limiter(&mut self) -> Option<&mut RateLimiter> {
+        self.rate_limiter.as_mut()
+    }
+
+    /// Refreshes and transfers since the display was created.
+    pub fn stats(&self) -> EpdStats {
+        EpdStats {
+            bytes_sent: self.interface.bytes_sent(),
+            ..self.stats
+        }
+    }
+
+    /// Display the framebuffer with a fast or full refresh as chosen by the refresh policy.
+    ///
+    /// The changed area is estimated from the pixels drawn through this `FastUpdateEpd` since the last refresh,
+    /// drawing to `framebuf` directly is not counted. The policy reads the time from the clock set by
+    /// `with_clock`, fails with `DisplayError::Unsupported` without one.
+    pub fn present(&mut self) -> Result<RefreshKind, D::Error> {
+        let now = now(&mut self.clock).ok_or(DisplayError::Unsupported)?;
+        let size = self.framebuf.native_size();
+        let total = (size.width * size.height) as usize;
+        let kind = self.policy.decide(now, self.drawn.min(total), total);
+        if !self.refresh_as(kind)? {
+            return Ok(RefreshKind::None);
+        }
+        self.policy.record(kind, now);
+        Ok(kind)
+    }
+
+    /// Fill the framebuffer with `color` and refresh the display with the full update waveform.
+    pub fn clear_display(&mut self, color: BinaryColor) -> Result<(), D::Error> {
+        self.framebuf.clear(color).ok();
+        self.display_frame_full_update()
+    }
+
+    /// Read the panel temperature in °C from the controller's internal sensor.
+    ///
+    /// On SSD controllers this reloads the waveform from OTP, see `Driver::read_temperature`.
+    pub fn read_temperature(&mut self) -> Result<i8, D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::read_temperature(&mut self.interface)
+    }
+
+    /// Set the ambient temperature in °C used to select the waveform, for panels without a sensor.
+    ///
+    /// Must be called again after `init` or `wake_up`.
+    pub fn set_temperature(&mut self, celsius: i8) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::set_temperature(&mut self.interface, celsius)
+    }
+
+    /// Set the gate and source driving voltages, must be called again after `init` or `wake_up`.
+    pub fn set_driving_voltages(&mut self, voltages: &DrivingVoltages) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::set_driving_voltages(&mut self.interface, voltages)
+    }
+
+    pub fn sleep<DELAY>(&mut self, delay: &mut DELAY, mode: SleepMode) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.initialized = false;
+        self.interface.set_phase(Phase::Sleep);
+        D::sleep(&mut self.interface, delay, mode)?;
+        self.asleep = true;
+        Ok(())
+    }
+
+    pub fn wake_up<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.interface.set_phase(Phase::Init);
+        D::wake_up(&mut self.interface, delay)?;
+        let size = self.framebuf.native_size();
+        set_shape::<D, _>(
+            &mut self.interface,
+            size.width as _,
+            size.height as _,
+            self.framebuf.ram_offset(),
+        )?;
+        self.interface.set_phase(Phase::LutLoad);
+        D::setup_fast_waveform(&mut self.interface)?;
+        self.initialized = true;
+        self.asleep = false;
+        Ok(())
+    }
+
+    /// Put the display to sleep in `mode` after every `update`, `None` keeps it awake.
+    pub fn set_auto_sleep(&mut self, mode: Option<SleepMode>) {
+        self.auto_sleep = mode;
+    }
+
+    /// Let the refreshes, `present` included, wake up a display put to sleep by `sleep`, as
+    /// `Epd::set_wake_delay` does.
+    pub fn set_wake_delay(&mut self, delay_us: Option<fn(u32)>) {
+        self.wake_delay = delay_us;
+    }
+
+    /// Wake up the display if it sleeps and a wake delay is set, then check that it is initialized.
+    fn ensure_awake(&mut self) -> Result<(), D::Error> {
+        if let (true, Some(delay_us)) = (self.asleep, self.wake_delay) {
+            self.wake_up(&mut FnDelay(delay_us))?;
+        }
+        ensure_initialized(self.initialized)?;
+        Ok(())
+    }
+
+    /// Call `hook` at each stage of a refresh, e.g. to drive a "refreshing" LED or to take timestamps.
+    pub fn set_progress_hook(&mut self, hook: Option<fn(RefreshStage)>) {
+        self.progress = hook;
+    }
+
+    fn turn_on_display(&mut self) -> Result<(), D::Error> {
+        progress(self.progress, RefreshStage::RefreshStarted);
+        self.interface.set_phase(Phase::Refresh);
+        let start = now(&mut self.clock);
+        <D as WaveformDriver>::turn_on_display(&mut self.interface)?;
+        self.stats.add_busy(start, now(&mut self.clock));
+        progress(self.progress, RefreshStage::BusyReleased);
+        Ok(())
+    }
+
+    /// Display the framebuffer, waking the display up first if it sleeps, see `set_wake_delay`.
+    ///
+    /// With auto sleep enabled, the display is put back to sleep with `delay` once the refresh is done.
+    pub fn update<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.display_frame()?;
+        if let Some(mode) = self.auto_sleep {
+            self.sleep(delay, mode)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I: DisplayInterface, S, D: FastUpdateDriver, B: AsRef<[u8]> + AsMut<[u8]>, C> Dimensions
+    for FastUpdateEpd<I, S, D, B, C>
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.framebuf.bounding_box()
+    }
+}
+
+impl<I: DisplayInterface, S, D: FastUpdateDriver, B: AsRef<[u8]> + AsMut<[u8]>, C> DrawTarget
+    for FastUpdateEpd<I, S, D, B, C>
+{
+    type Color = embedded_graphics::pixelcolor::BinaryColor;
+    type Error = DisplayError;
+
+    fn draw_iter<IP>(&mut self, pixels: IP) -> Result<(), Self::Error>
+    where
+        IP: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
+    {
+        let drawn = &mut self.drawn;
+        self.framebuf
+            .draw_iter(pixels.into_iter().inspect(|_| *drawn += 1))
+    }
+
+    fn fill_contiguous<IC>(&mut self, area: &Rectangle, colors: IC) -> Result<(), Self::Error>
+    where
+        IC: IntoIterator<Item = Self::Color>,
+    {
+        let drawn = self.framebuf.bounding_box().intersection(area).size;
+        self.drawn += (drawn.width * drawn.height) as usize;
+        self.framebuf.fill_contiguous(area, colors)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let drawn = self.framebuf.bounding_box().intersection(area).size;
+        self.drawn += (drawn.width * drawn.height) as usize;
+        self.framebuf.fill_solid(area, color)
+    }
+}
+
+pub struct TriColorEpd<
+    I: DisplayInterface,
+    S,
+    D: Driver,
+    #[cfg(feature = "nightly")] B = ArrayStorage<S>,
+    #[cfg(not(feature = "nightly"))] B,
+    C = fn() -> u32,
+> {
+    pub interface: I,
+    pub framebuf0: FrameBuffer<S, B>,
+    pub framebuf1: FrameBuffer<S, B>,
+    initialized: bool,
+    auto_sleep: Option<SleepMode>,
+    progress: Option<fn(RefreshStage)>,
+    /// Put to sleep by `sleep`, as opposed to never initialized
+    asleep: bool,
+    /// Delay in µs used to wake up a sleeping display on refresh, see `set_wake_delay`
+    wake_delay: Option<fn(u32)>,
+    /// Guard against too frequent refreshes, see `set_rate_limiter`
+    rate_limiter: Option<RateLimiter>,
+    /// Counters reported by `stats`
+    stats: EpdStats,
+    /// Time source of the rate limiter and of the busy time of `stats`, see `with_clock`
+    clock: Option<C>,
+    _phantom: PhantomData<(S, D)>,
+}
+
+#[cfg(feature = "nightly")]
+impl<DI: DisplayInterface, S: DisplaySize, D: MultiColorDriver> TriColorEpd<DI, S, D>
+where
+    [(); S::N]:,
+{
+    pub fn new(interface: DI) -> Self {
+        Self::from_framebuffers(
+            interface,
+            if D::WHITE_BIT {
+                FrameBuffer::new_ones()
+            } else {
+                FrameBuffer::new()
+            },
+            if D::CHROMATIC_BIT {
+                FrameBuffer::new()
+            } else {
+                FrameBuffer::new_ones()
+            },
+        )
+    }
+}
+
+impl<DI: DisplayInterface, S: DisplaySize, D: MultiColorDriver, B: AsRef<[u8]> + AsMut<[u8]>>
+    TriColorEpd<DI, S, D, B>
+{
+    /// Create the display with both framebuffers in user supplied storage of `S::N` bytes each.
+    pub fn new_in(interface: DI, buf0: B, buf1: B) -> Result<Self, DisplayError> {
+        let mut framebuf0 = FrameBuffer::new_in(buf0)?;
+        let mut framebuf1 = FrameBuffer::new_in(buf1)?;
+        framebuf0.clear(BinaryColor::from(D::WHITE_BIT)).ok();
+        framebuf1.clear(BinaryColor::from(!D::CHROMATIC_BIT)).ok();
+        Ok(Self::from_framebuffers(interface, framebuf0, framebuf1))
+    }
+}
+
+impl<DI: DisplayInterface, S, D: Driver, B> TriColorEpd<DI, S, D, B> {
+    fn from_framebuffers(
+        interface: DI,
+        framebuf0: FrameBuffer<S, B>,
+        framebuf1: FrameBuffer<S, B>,
+    ) -> Self {
+        Self {
+            interface,
+            framebuf0,
+            framebuf1,
+            initialized: false,
+            auto_sleep: None,
+            progress: None,
+            asleep: false,
+            wake_delay: None,
+            rate_limiter: None,
+            stats: EpdStats::new(),
+            clock: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<DI: DisplayInterface, S, D: MultiColorDriver, B: AsRef<[u8]> + AsMut<[u8]>>
+    TriColorEpd<DI, S, D, B>
+{
+    /// Create the display around existing black/white and color framebuffers of the same size.
+    pub fn with_framebuffers(
+        interface: DI,
+        framebuf0: FrameBuffer<S, B>,
+        framebuf1: FrameBuffer<S, B>,
+    ) -> Result<Self, DisplayError> {
+        if framebuf0.native_size() != framebuf1.native_size() {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        Ok(Self::from_framebuffers(interface, framebuf0, framebuf1))
+    }
+}
+
+impl<DI: DisplayInterface, S, D: MultiColorDriver, B: AsRef<[u8]> + AsMut<[u8]>, C: Clock>
+    TriColorEpd<DI, S, D, B, C>
+{
+    /// Consume the display and return the interface and both framebuffers.
+    pub fn release(self) -> (DI, FrameBuffer<S, B>, FrameBuffer<S, B>) {
+        (self.interface, self.framebuf0, self.framebuf1)
+    }
+
+    pub fn init<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.interface.set_phase(Phase::Init);
+        D::wake_up(&mut self.interface, delay)?;
+        let size = self.framebuf0.native_size();
+        set_shape::<D, _>(
+            &mut self.interface,
+            size.width as _,
+            size.height as _,
+            self.framebuf0.ram_offset(),
+        )?;
+        self.initialized = true;
+        self.asleep = false;
+        Ok(())
+    }
+
+    pub fn set_rotation(&mut self, rotation: i32) {
+        self.framebuf0.set_rotation(rotation);
+        self.framebuf1.set_rotation(rotation);
+    }
+
+    /// Draw colors of another kind into both framebuffers, as encoded by `E`, e.g. for a panel using
+    /// the two planes for four colors.
+    ///
+    /// The display itself draws `TriColor` with `TriColorEncoder`.
+    pub fn encoded_target<P: PixelColor, E: ColorEncoder<P, 2>>(
+        &mut self,
+    ) -> EncodedTarget<'_, S, B, P, E, 2> {
+        EncodedTarget::new([&mut self.framebuf0, &mut self.framebuf1])
+    }
+
+    pub fn display_frame(&mut self) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        if !allow_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Full,
+        )? {
+            return Ok(());
+        }
+        let size = self.framebuf0.native_size();
+        let offset = self.framebuf0.ram_offset();
+        for (plane, framebuf) in [
+            (Plane::BlackWhite, &self.framebuf0),
+            (Plane::Chromatic, &self.framebuf1),
+        ] {
+            update_channel::<D, _>(
+                &mut self.interface,
+                plane,
+                size.width as _,
+                size.height as _,
+                offset,
+                framebuf.as_bytes(),
+            )?;
+        }
+        progress(self.progress, RefreshStage::FrameTransferred);
+        self.turn_on_display()
+    }
+
+    /// Fill both framebuffers with `color` and refresh the display.
+    pub fn clear_display(&mut self, color: TriColor) -> Result<(), D::Error> {
+        self.clear(color).ok();
+        self.display_frame()
+    }
+
+    /// Display externally prepared black/white and color channels, bypassing the framebuffers.
+    pub fn display_raw_frame(&mut self, bw: &[u8], color: &[u8]) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        if !allow_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Full,
+        )? {
+            return Ok(());
+        }
+        self.write_raw_channel(Plane::BlackWhite, bw)?;
+        self.write_raw_channel(Plane::Chromatic, color)?;
+        progress(self.progress, RefreshStage::FrameTransferred);
+        self.turn_on_display()
+    }
+
+    /// Write a single RAM plane, then refresh the display.
+    ///
+    /// The other plane keeps the content of the display RAM.
+    pub fn display_raw_channel(&mut self, plane: Plane, data: &[u8]) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        if !allow_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Full,
+        )? {
+            return Ok(());
+        }
+        self.write_raw_channel(plane, data)?;
+        progress(self.progress, RefreshStage::FrameTransferred);
+        self.turn_on_display()
+    }
+
+    /// Write externally prepared data of the native size of the framebuffers to a RAM plane.
+    fn write_raw_channel(&mut self, plane: Plane, data: &[u8]) -> Result<(), D::Error> {
+        let size = self.framebuf0.native_size();
+        update_channel::<D, _>(
+            &mut self.interface,
+            plane,
+            size.width as _,
+            size.height as _,
+            self.framebuf0.ram_offset(),
+            data,
+        )
+    }
+
+    /// Write both planes of `area` only, through RAM windows, then refresh the whole panel.
+    ///
+    /// Saves the transfer of the unchanged parts, the refresh itself is still a full one.
+    /// Requires a driver supporting `MultiColorDriver::update_channel_window`.
+    pub fn display_partial_frame(&mut self, area: &Rectangle) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        if !allow_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Full,
+        )? {
+            return Ok(());
+        }
+        let Some((x0, y0, x1, y1)) = self.framebuf0.native_rect(area) else {
+            return Ok(());
+        };
+        let size = self.framebuf0.native_size();
+        let offset = self.framebuf0.ram_offset();
+        let stride = (size.width as usize).div_ceil(8);
+        let (first, last) = (x0 / 8, (x1 - 1) / 8);
+        let width_in_byte = last - first + 1;
+        let band_rows = stream_rows(width_in_byte)?;
+        let mut band = [0u8; STREAM_CHUNK];
+
+        let planes = [
+            (Plane::BlackWhite, &self.framebuf0),
+            (Plane::Chromatic, &self.framebuf1),
+        ];
+        self.interface.set_phase(Phase::FrameTransfer);
+        for (plane, framebuf) in planes {
+            for y in (y0..y1).step_by(band_rows) {
+                let rows = band_rows.min(y1 - y);
+                let src = framebuf.as_bytes().chunks_exact(stride).skip(y);
+                for (dst, row) in band.chunks_exact_mut(width_in_byte).take(rows).zip(src) {
+                    dst.copy_from_slice(&row[first..=last]);
+                }
+                D::update_channel_window(
+                    &mut self.interface,
+                    plane,
+                    (offset.0 + first * 8) as u16,
+                    (offset.1 + y) as u16,
+                    (width_in_byte * 8) as u16,
+                    rows as u16,
+                    &band[..rows * width_in_byte],
+                )?;
+            }
+        }
+        set_shape::<D, _>(
+            &mut self.interface,
+            size.width as _,
+            size.height as _,
+            offset,
+        )?;
+        progress(self.progress, RefreshStage::FrameTransferred);
+        self.turn_on_display()
+    }
+
+    /// Read the panel temperature in °C from the controller's internal sensor.
+    ///
+    /// On SSD controllers this reloads the waveform from OTP, see `Driver::read_temperature`.
+    pub fn read_temperature(&mut self) -> Result<i8, D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::read_temperature(&mut self.interface)
+    }
+
+    /// Set the ambient temperature in °C used to select the waveform, for panels without a sensor.
+    ///
+    /// Must be called again after `init` or `wake_up`.
+    pub fn set_temperature(&mut self, celsius: i8) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::set_temperature(&mut self.interface, celsius)
+    }
+
+    /// Set the gate and source driving voltages, must be called again after `init` or `wake_up`.
+    pub fn set_driving_voltages(&mut self, voltages: &DrivingVoltages) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::set_driving_voltages(&mut self.interface, voltages)
+    }
+
+    pub fn sleep<DELAY>(&mut self, delay: &mut DELAY, mode: SleepMode) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.initialized = false;
+        self.interface.set_phase(Phase::Sleep);
+        D::sleep(&mut self.interface, delay, mode)?;
+        self.asleep = true;
+        Ok(())
+    }
+
+    pub fn wake_up<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.interface.set_phase(Phase::Init);
+        D::wake_up(&mut self.interface, delay)?;
+        let size = self.framebuf0.native_size();
+        set_shape::<D, _>(
+            &mut self.interface,
+            size.width as _,
+            size.height as _,
+            self.framebuf0.ram_offset(),
+        )?;
+        self.initialized = true;
+        self.asleep = false;
+        Ok(())
+    }
+
+    /// Put the display to sleep in `mode` after every `update`, `None` keeps it awake.
+    pub fn set_auto_sleep(&mut self, mode: Option<SleepMode>) {
+        self.auto_sleep = mode;
+    }
+
+    /// Let the refreshes wake up a display put to sleep by `sleep`, as `Epd::set_wake_delay` does.
+    pub fn set_wake_delay(&mut self, delay_us: Option<fn(u32)>) {
+        self.wake_delay = delay_us;
+    }
+
+    /// Wake up the display if it sleeps and a wake delay is set, then check that it is initialized.
+    fn ensure_awake(&mut self) -> Result<(), D::Error> {
+        if let (true, Some(delay_us)) = (self.asleep, self.wake_delay) {
+            self.wake_up(&mut FnDelay(delay_us))?;
+        }
+        ensure_initialized(self.initialized)?;
+        Ok(())
+    }
+
+    /// Call `hook` at each stage of a refresh, e.g. to drive a "refreshing" LED or to take timestamps.
+    pub fn set_progress_hook(&mut self, hook: Option<fn(RefreshStage)>) {
+        self.progress = hook;
+    }
+
+    /// Limit how often the display refreshes, `None` never limits.
+    ///
+    /// Deferred frames are not sent, the next refresh sends the framebuffers again. The limiter reads the
+    /// time from the clock set by `with_clock`, refreshes fail with `DisplayError::Unsupported` without one.
+    pub fn set_rate_limiter(&mut self, limiter: Option<RateLimiter>) {
+        self.rate_limiter = limiter;
+    }
+
+    /// The limiter set by `set_rate_limiter`, e.g. to change its intervals or to ask if a refresh was deferred.
+    pub fn rate_limiter(&mut self) -> Option<&mut RateLimiter> {
+        self.rate_limiter.as_mut()
+    }
+
+    /// Read the time from `clock`, e.g. a HAL timer, for the rate limiter and the busy time of `stats`.
+    ///
+    /// The clock becomes part of the display type.
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> TriColorEpd<DI, S, D, B, C2> {
+        TriColorEpd {
+            interface: self.interface,
+            framebuf0: self.framebuf0,
+            framebuf1: self.framebuf1,
+            initialized: self.initialized,
+            auto_sleep: self.auto_sleep,
+            progress: self.progress,
+            asleep: self.asleep,
+            wake_delay: self.wake_delay,
+            rate_limiter: self.rate_limiter,
+            stats: self.stats,
+            clock: Some(clock),
+            _phantom: self._phantom,
+        }
+    }
+
+    /// Refreshes and transfers since the display was created.
+    pub fn stats(&self) -> EpdStats {
+        EpdStats {
+            bytes_sent: self.interface.bytes_sent(),
+            ..self.stats
+        }
+    }
+
+    fn turn_on_display(&mut self) -> Result<(), D::Error> {
+        progress(self.progress, RefreshStage::RefreshStarted);
+        self.interface.set_phase(Phase::Refresh);
+        let start = now(&mut self.clock);
+        D::turn_on_display(&mut self.interface)?;
+        self.stats.add_busy(start, now(&mut self.clock));
+        self.stats.full_refreshes += 1;
+        record_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Full,
+        );
+        progress(self.progress, RefreshStage::BusyReleased);
+        Ok(())
+    }
+
+    /// Display the framebuffer, waking the display up first if it sleeps, see `set_wake_delay`.
+    ///
+    /// With auto sleep enabled, the display is put back to sleep with `delay` once the refresh is done.
+    pub fn update<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.display_frame()?;
+        if let Some(mode) = self.auto_sleep {
+            self.sleep(delay, mode)?;
+        }
+        Ok(())
+    }
+}
+
+impl<
+        DI: DisplayInterface,
+        S,
+        D: MultiColorDriver + PowerControl,
+        B: AsRef<[u8]> + AsMut<[u8]>,
+        C: Clock,
+    > TriColorEpd<DI, S, D, B, C>
+{
+    /// Power on the booster ahead of a refresh.
+    pub fn power_on(&mut self) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::power_on(&mut self.interface)
+    }
+
+    /// Power off the booster, e.g. after a refresh when the next one is far away.
+    ///
+    /// The next refresh powers on again.
+    pub fn power_off(&mut self) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::power_off(&mut self.interface)
+    }
+}
+
+impl<DI: DisplayInterface, S, D: FastBlackWhiteDriver, B: AsRef<[u8]> + AsMut<[u8]>, C: Clock>
+    TriColorEpd<DI, S, D, B, C>
+{
+    /// Write the black/white framebuffer only and refresh it with the fast waveform, e.g. for a clock.
+    ///
+    /// The red RAM is left as written by the last `display_frame`, red pixels are not driven and stay red.
+    /// Changes to `framebuf1` show up on the next `display_frame`, which uses the full waveform again.
+    pub fn display_frame_fast_black_white(&mut self) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        if !allow_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Fast,
+        )? {
+            return Ok(());
+        }
+        let size = self.framebuf0.native_size();
+        update_channel::<D, _>(
+            &mut self.interface,
+            Plane::BlackWhite,
+            size.width as _,
+            size.height as _,
+            self.framebuf0.ram_offset(),
+            self.framebuf0.as_bytes(),
+        )?;
+        progress(self.progress, RefreshStage::FrameTransferred);
+        self.interface.set_phase(Phase::LutLoad);
+        D::setup_fast_black_white_waveform(&mut self.interface)?;
+        progress(self.progress, RefreshStage::RefreshStarted);
+        self.interface.set_phase(Phase::Refresh);
+        let start = now(&mut self.clock);
+        <D as WaveformDriver>::turn_on_display(&mut self.interface)?;
+        self.stats.add_busy(start, now(&mut self.clock));
+        self.stats.fast_refreshes += 1;
+        record_refresh(
+            &mut self.rate_limiter,
+            now(&mut self.clock),
+            RefreshKind::Fast,
+        );
+        progress(self.progress, RefreshStage::BusyReleased);
+        Ok(())
+    }
+}
+
+impl<I: DisplayInterface, S, D: MultiColorDriver, B: AsRef<[u8]> + AsMut<[u8]>, C> Dimensions
+    for TriColorEpd<I, S, D, B, C>
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.framebuf0.bounding_box()
+    }
+}
+
+impl<I: DisplayInterface, SIZE, D: MultiColorDriver, B: AsRef<[u8]> + AsMut<[u8]>, C: Clock>
+    DrawTarget for TriColorEpd<I, SIZE, D, B, C>
+{
+    type Color = TriColor;
+    type Error = DisplayError;
+
+    fn draw_iter<IP>(&mut self, pixels: IP) -> Result<(), Self::Error>
+    where
+        IP: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
+    {
+        self.encoded_target::<TriColor, TriColorEncoder<D>>()
+            .draw_iter(pixels)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.encoded_target::<TriColor, TriColorEncoder<D>>()
+            .fill_solid(area, color)
+    }
+}
+
+/// Stage of a refresh reported to the progress hook.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RefreshStage {
+    /// The frame was written to the controller RAM
+    FrameTransferred,
+    /// The refresh is about to be started
+    RefreshStarted,
+    /// The refresh is done, BUSY was released
+    BusyReleased,
+}
+
+fn progress(hook: Option<fn(RefreshStage)>, stage: RefreshStage) {
+    if let Some(hook) = hook {
+        hook(stage);
+    }
+}
+
+/// Usage counters of a display, e.g. for battery budgets or panel lifetime, see `Epd::stats`.
+///
+/// A gray scale frame counts as one full refresh, whatever the number of layers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EpdStats {
+    /// Refreshes with the full waveform
+    pub full_refreshes: u32,
+    /// Refreshes with the fast waveform
+    pub fast_refreshes: u32,
+    /// Refreshes after sending only the areas queued by `Epd::invalidate`
+    pub partial_refreshes: u32,
+    /// Time spent waiting for refreshes in ms, counted once a clock is set with `Epd::with_clock`
+    pub busy_ms: u64,
+    /// Bytes sent to the controller, commands included, `None` if the interface does not count them
+    pub bytes_sent: Option<u64>,
+}
+
+impl EpdStats {
+    pub const fn new() -> Self {
+        Self {
+            full_refreshes: 0,
+            fast_refreshes: 0,
+            partial_refreshes: 0,
+            busy_ms: 0,
+            bytes_sent: None,
+        }
+    }
+
+    fn add_busy(&mut self, start: Option<u32>, end: Option<u32>) {
+        if let (Some(start), Some(end)) = (start, end) {
+            self.busy_ms += end.wrapping_sub(start) as u64;
+        }
+    }
+}
+
+/// Time in ms of the display clock, `None` without one.
+fn now<C: Clock>(clock: &mut Option<C>) -> Option<u32> {
+    clock.as_mut().map(Clock::now_ms)
+}
+
+/// `DelayNs` over a function delaying in µs, see `Epd::set_wake_delay`.
+struct FnDelay(fn(u32));
+
+impl embedded_hal::delay::DelayNs for FnDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        (self.0)(ns.div_ceil(1000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        (self.0)(us);
+    }
+}
+
+/// Check a refresh of `kind` at time `now` against `limiter`, `Ok(false)` if it is deferred.
+///
+/// A limiter cannot tell the time without a clock.
+fn allow_refresh(
+    limiter: &mut Option<RateLimiter>,
+    now: Option<u32>,
+    kind: RefreshKind,
+) -> Result<bool, DisplayError> {
+    match (limiter, now) {
+        (Some(limiter), Some(now)) => limiter.allow(kind, now),
+        (Some(_), None) => Err(DisplayError::Unsupported),
+        (None, _) => Ok(true),
+    }
+}
+
+fn record_refresh(limiter: &mut Option<RateLimiter>, now: Option<u32>, kind: RefreshKind) {
+    if let (Some(limiter), Some(now)) = (limiter, now) {
+        limiter.record(kind, now);
+    }
+}
+
+/// Refreshing before `init`, or after `sleep`, would wait for BUSY forever.
+fn ensure_initialized(initialized: bool) -> Result<(), DisplayError> {
+    if initialized {
+        Ok(())
+    } else {
+        Err(DisplayError::Uninitialized)
+    }
+}
+
+/// Flip 1bpp data with set bits for white to the polarity of `D`, see `Driver::BLACK_BIT`.
+#[cfg(feature = "nightly")]
+fn to_driver_polarity<D: Driver>(data: &mut [u8]) {
+    if D::BLACK_BIT {
+        data.iter_mut().for_each(|b| *b = !*b);
+    }
+}
+
+/// Set the RAM size of a panel whose visible area starts at RAM column and row `offset`.
+fn set_shape<D: Driver, DI: DisplayInterface>(
+    di: &mut DI,
+    width: usize,
+    height: usize,
+    offset: (usize, usize),
+) -> Result<(), D::Error> {
+    D::set_shape(di, (width + offset.0) as _, (height + offset.1) as _)
+}
+
+/// Write a whole frame, through a RAM window if the panel is mapped at an offset.
+fn update_frame<D: Driver, DI: DisplayInterface>(
+    di: &mut DI,
+    width: usize,
+    height: usize,
+    offset: (usize, usize),
+    data: &[u8],
+) -> Result<(), D::Error> {
+    if offset == (0, 0) {
+        di.set_phase(Phase::FrameTransfer);
+        D::update_frame(di, data)
+    } else {
+        update_rows::<D, DI>(di, width, height, offset, 0, data)
+    }
+}
+
+/// Write a whole frame stored bottom-up, the controller flips it back, see `Driver::DATA_ENTRY_FLIP`.
+fn update_frame_flipped<D: Driver, DI: DisplayInterface>(
+    di: &mut DI,
+    width: usize,
+    height: usize,
+    offset: (usize, usize),
+    data: &[u8],
+) -> Result<(), D::Error> {
+    let width_in_byte = width.div_ceil(8);
+    di.set_phase(Phase::FrameTransfer);
+    D::update_frame_window_flipped(
+        di,
+        offset.0 as _,
+        offset.1 as _,
+        (width_in_byte * 8) as _,
+        height as _,
+        &data[..width_in_byte * height],
+    )?;
+    set_shape::<D, _>(di, width, height, offset)
+}
+
+/// Bytes of each row compared by `verify_frame`, the most `Driver::read_frame_row` reads at once.
+const VERIFY_CHUNK: usize = 64;
+
+/// Read back the first, middle and last rows of `data` from the controller RAM and compare them.
+fn verify_frame<D: Driver, DI: DisplayInterface>(
+    di: &mut DI,
+    width: usize,
+    offset: (usize, usize),
+    data: &[u8],
+) -> Result<(), D::Error> {
+    let width_in_byte = width.div_ceil(8);
+    let rows = data.len() / width_in_byte;
+    if rows == 0 {
+        return Ok(());
+    }
+    let len = width_in_byte.min(VERIFY_CHUNK);
+    let mut read = [0u8; VERIFY_CHUNK];
+    for y in [0, rows / 2, rows - 1] {
+        D::read_frame_row(di, offset.0 as _, (offset.1 + y) as _, &mut read[..len])?;
+        let start = y * width_in_byte;
+        if read[..len] != data[start..start + len] {
+            return Err(DisplayError::VerifyFailed.into());
+        }
+    }
+    Ok(())
+}
+
+/// Write the changed span of each row of `data` compared to `previous`, through RAM windows.
+///
+/// Consecutive rows with the same span share a window, the full frame window is restored at the end.
+fn update_frame_changes<D: Driver, DI: DisplayInterface>(
+    di: &mut DI,
+    width: usize,
+    height: usize,
+    offset: (usize, usize),
+    data: &[u8],
+    previous: &[u8],
+) -> Result<(), D::Error> {
+    let width_in_byte = width.div_ceil(8);
+    let rows = data.chunks_exact(width_in_byte);
+    let spans = rows
+        .clone()
+        .zip(previous.chunks_exact(width_in_byte))
+        .map(|(row, old)| {
+            let changed = |(a, b): (&u8, &u8)| a != b;
+            let first = row.iter().zip(old).position(changed)?;
+            let last = row.iter().zip(old).rposition(changed)?;
+            Some((first, last))
+        });
+
+    di.set_phase(Phase::FrameTransfer);
+    let mut written = false;
+    // first row and span of the window being gathered
+    let mut window: Option<(usize, (usize, usize))> = None;
+    for (y, span) in spans.chain(core::iter::once(None)).enumerate() {
+        if let Some((y0, (first, last))) = window {
+            if span == Some((first, last)) {
+                continue;
+            }
+            let data = rows
+                .clone()
+                .skip(y0)
+                .take(y - y0)
+                .flat_map(|row| &row[first..=last]);
+            D::update_frame_window(
+                di,
+                (offset.0 + first * 8) as _,
+                (offset.1 + y0) as _,
+                ((last - first + 1) * 8) as _,
+                (y - y0) as _,
+                data,
+            )?;
+            written = true;
+        }
+        window = span.map(|span| (y, span));
+    }
+
+    if written {
+        set_shape::<D, _>(di, width, height, offset)?;
+    }
+    Ok(())
+}
+
+/// Write whole native rows starting at `y`, through a RAM window if the panel is mapped at an offset.
+fn update_rows<D: Driver, DI: DisplayInterface>(
+    di: &mut DI,
+    width: usize,
+    height: usize,
+    offset: (usize, usize),
+    y: usize,
+    data: &[u8],
+) -> Result<(), D::Error> {
+    if offset == (0, 0) {
+        di.set_phase(Phase::FrameTransfer);
+        return D::update_frame_rows(di, y as u16, data);
+    }
+    let width_in_byte = width.div_ceil(8);
+    let rows = (data.len() / width_in_byte).min(height - y);
+    di.set_phase(Phase::FrameTransfer);
+    D::update_frame_window(
+        di,
+        offset.0 as _,
+        (offset.1 + y) as _,
+        (width_in_byte * 8) as _,
+        rows as _,
+        &data[..rows * width_in_byte],
+    )
+}
+
+/// Write a whole RAM plane, through a RAM window if the panel is mapped at an offset.
+fn update_channel<D: MultiColorDriver, DI: DisplayInterface>(
+    di: &mut DI,
+    plane: Plane,
+    width: usize,
+    height: usize,
+    offset: (usize, usize),
+    data: &[u8],
+) -> Result<(), D::Error> {
+    di.set_phase(Phase::FrameTransfer);
+    if offset == (0, 0) {
+        return D::update_channel_frame(di, plane, data);
+    }
+    let width_in_byte = width.div_ceil(8);
+    let rows = (data.len() / width_in_byte).min(height);
+    D::update_channel_window(
+        di,
+        plane,
+        offset.0 as _,
+        offset.1 as _,
+        (width_in_byte * 8) as _,
+        rows as _,
+        &data[..rows * width_in_byte],
+    )
+}
+
+/// Write whole native rows of a RAM plane starting at `y`, through a RAM window if the panel is mapped at
+/// an offset.
+#[cfg(feature = "nightly")]
+fn update_plane_rows<D: FourGrayDriver, DI: DisplayInterface>(
+    di: &mut DI,
+    plane: Plane,
+    width: usize,
+    offset: (usize, usize),
+    y: usize,
+    data: &[u8],
+) -> Result<(), D::Error> {
+    di.set_phase(Phase::FrameTransfer);
+    if offset == (0, 0) {
+        return D::update_plane_rows(di, plane, y as u16, data);
+    }
+    let width_in_byte = width.div_ceil(8);
+    D::update_plane_window(
+        di,
+        plane,
+        offset.0 as _,
+        (offset.1 + y) as _,
+        (width_in_byte * 8) as _,
+        (data.len() / width_in_byte) as _,
+        data,
+    )
+}
+
+/// Bytes sent at once when a frame is streamed in strips of rows.
+const STREAM_CHUNK: usize = 256;
+
+/// Rows of `width_in_byte` bytes in a strip of `STREAM_CHUNK` bytes.
+///
+/// Rows wider than a strip, of panels over 2048 pixels, are `OutOfRange`.
+fn stream_rows(width_in_byte: usize) -> Result<usize, DisplayError> {
+    match STREAM_CHUNK / width_in_byte {
+        0 => Err(DisplayError::OutOfRange),
+        rows => Ok(rows),
+    }
+}
+
+/// Write the frame of `framebuf` in native orientation, moved by `shift` pixels, in strips of
+/// `STREAM_CHUNK` bytes.
+fn update_frame_native<D: Driver, DI: DisplayInterface, S, B: AsRef<[u8]> + AsMut<[u8]>>(
+    di: &mut DI,
+    framebuf: &FrameBuffer<S, B>,
+    shift: (i32, i32),
+) -> Result<(), D::Error> {
+    let size = framebuf.native_size();
+    let (width, height) = (size.width as usize, size.height as usize);
+    let width_in_byte = width.div_ceil(8);
+    let rows = stream_rows(width_in_byte)?;
+    let mut chunk = [0u8; STREAM_CHUNK];
+
+    for y in (0..height).step_by(rows) {
+        let len = rows.min(height - y) * width_in_byte;
+        framebuf.native_rows(y, shift, &mut chunk[..len]);
+        update_rows::<D, _>(di, width, height, framebuf.ram_offset(), y, &chunk[..len])?;
+    }
+    Ok(())
+}
+
+/// EPD display without a framebuffer, frames are streamed from an iterator, e.g. an image in flash.
+pub struct StreamingEpd<I: DisplayInterface, S: DisplaySize, D: Driver> {
+    pub interface: I,
+    initialized: bool,
+    /// Put to sleep by `sleep`, as opposed to never initialized
+    asleep: bool,
+    /// Delay in µs used to wake up a sleeping display on refresh, see `set_wake_delay`
+    wake_delay: Option<fn(u32)>,
+    _phantom: PhantomData<(S, D)>,
+}
+
+impl<DI: DisplayInterface, S: DisplaySize, D: Driver> StreamingEpd<DI, S, D> {
+    pub fn new(interface: DI) -> Self {
+        Self {
+            interface,
+            initialized: false,
+            asleep: false,
+            wake_delay: None,
             _phantom: PhantomData,
         }
     }
 
+    /// Consume the display and return the interface.
+    pub fn release(self) -> DI {
+        self.interface
+    }
+
     pub fn init<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
     where
         DELAY: embedded_hal::delay::DelayNs,
     {
+        self.interface.set_phase(Phase::Init);
         D::wake_up(&mut self.interface, delay)?;
-        D::set_shape(&mut self.interface, S::WIDTH as _, S::HEIGHT as _)?;
+        set_shape::<D, _>(
+            &mut self.interface,
+            S::WIDTH,
+            S::HEIGHT,
+            (S::X_OFFSET, S::Y_OFFSET),
+        )?;
+        self.initialized = true;
+        self.asleep = false;
         Ok(())
     }
 
-    pub fn set_rotation(&mut self, rotation: i32) {
-        self.framebuf.set_rotation(rotation);
+    /// Send a frame of raw 1bpp data in native orientation and refresh the display.
+    ///
+    /// Bytes beyond the frame are ignored, a short iterator leaves the rest of the display RAM as is.
+    /// Panels up to 2048 pixels wide are supported.
+    pub fn display_frame_from_iter<I>(&mut self, data: I) -> Result<(), D::Error>
+    where
+        I: IntoIterator<Item = u8>,
+    {
+        self.ensure_awake()?;
+        let width_in_byte = S::WIDTH.div_ceil(8);
+        let rows = stream_rows(width_in_byte)?;
+        let mut data = data.into_iter().take(width_in_byte * S::HEIGHT);
+        let mut chunk = [0u8; STREAM_CHUNK];
+
+        for y in (0..S::HEIGHT).step_by(rows) {
+            let mut n = 0;
+            for (b, d) in chunk[..rows * width_in_byte].iter_mut().zip(&mut data) {
+                *b = d;
+                n += 1;
+            }
+            if n == 0 {
+                break;
+            }
+            update_rows::<D, _>(
+                &mut self.interface,
+                S::WIDTH,
+                S::HEIGHT,
+                (S::X_OFFSET, S::Y_OFFSET),
+                y,
+                &chunk[..n],
+            )?;
+        }
+        self.interface.set_phase(Phase::Refresh);
+        D::turn_on_display(&mut self.interface)
     }
 
-    pub fn display_frame(&mut self) -> Result<(), D::Error> {
-        D::update_frame(&mut self.interface, self.framebuf.as_bytes())?;
+    /// Draw straight into the controller RAM, see `DirectTarget`, then show it with `refresh`.
+    ///
+    /// `background` colors the pixels of a `fill_contiguous` area left over by a short color iterator.
+    pub fn draw_direct(
+        &mut self,
+        background: BinaryColor,
+    ) -> Result<DirectTarget<'_, DI, S, D>, D::Error> {
+        self.ensure_awake()?;
+        Ok(DirectTarget {
+            epd: self,
+            background,
+        })
+    }
+
+    /// Refresh the display with the current content of the controller RAM.
+    pub fn refresh(&mut self) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        self.interface.set_phase(Phase::Refresh);
         D::turn_on_display(&mut self.interface)
     }
 
-    pub fn sleep<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    /// Read the panel temperature in °C from the controller's internal sensor.
+    ///
+    /// On SSD controllers this reloads the waveform from OTP, see `Driver::read_temperature`.
+    pub fn read_temperature(&mut self) -> Result<i8, D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::read_temperature(&mut self.interface)
+    }
+
+    /// Set the ambient temperature in °C used to select the waveform, for panels without a sensor.
+    ///
+    /// Must be called again after `init` or `wake_up`.
+    pub fn set_temperature(&mut self, celsius: i8) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::set_temperature(&mut self.interface, celsius)
+    }
+
+    /// Set the gate and source driving voltages, must be called again after `init` or `wake_up`.
+    pub fn set_driving_voltages(&mut self, voltages: &DrivingVoltages) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::set_driving_voltages(&mut self.interface, voltages)
+    }
+
+    /// Let the refreshes wake up a display put to sleep by `sleep`, as `Epd::set_wake_delay` does.
+    pub fn set_wake_delay(&mut self, delay_us: Option<fn(u32)>) {
+        self.wake_delay = delay_us;
+    }
+
+    /// Wake up the display if it sleeps and a wake delay is set, then check that it is initialized.
+    fn ensure_awake(&mut self) -> Result<(), D::Error> {
+        if let (true, Some(delay_us)) = (self.asleep, self.wake_delay) {
+            self.wake_up(&mut FnDelay(delay_us))?;
+        }
+        ensure_initialized(self.initialized)?;
+        Ok(())
+    }
+
+    pub fn sleep<DELAY>(&mut self, delay: &mut DELAY, mode: SleepMode) -> Result<(), D::Error>
     where
         DELAY: embedded_hal::delay::DelayNs,
     {
-        D::sleep(&mut self.interface, delay)
+        self.initialized = false;
+        self.interface.set_phase(Phase::Sleep);
+        D::sleep(&mut self.interface, delay, mode)?;
+        self.asleep = true;
+        Ok(())
     }
 
     pub fn wake_up<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
     where
         DELAY: embedded_hal::delay::DelayNs,
     {
+        self.interface.set_phase(Phase::Init);
         D::wake_up(&mut self.interface, delay)?;
-        D::set_shape(&mut self.interface, S::WIDTH as _, S::HEIGHT as _)?;
+        set_shape::<D, _>(
+            &mut self.interface,
+            S::WIDTH,
+            S::HEIGHT,
+            (S::X_OFFSET, S::Y_OFFSET),
+        )?;
+        self.initialized = true;
+        self.asleep = false;
         Ok(())
     }
 }
 
-impl<I: DisplayInterface, S: DisplaySize, D: Driver> Dimensions for Epd<I, S, D>
-where
-    [(); S::N]:,
-{
-    fn bounding_box(&self) -> Rectangle {
-        self.framebuf.bounding_box()
-    }
-}
-
-impl<I: DisplayInterface, S: DisplaySize, D: Driver> DrawTarget for Epd<I, S, D>
-where
-    [(); S::N]:,
-{
-    type Color = embedded_graphics::pixelcolor::BinaryColor;
-    type Error = core::convert::Infallible;
-
-    fn draw_iter<IP>(&mut self, pixels: IP) -> Result<(), Self::Error>
-    where
-        IP: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
-    {
-        self.framebuf.draw_iter(pixels)
-    }
-}
-
-/// EPD display backed by fast update LUT, both fast update and full update are supported.
-pub struct FastUpdateEpd<I: DisplayInterface, S: DisplaySize, D: FastUpdateDriver>
-where
-    [(); S::N]:,
-{
+/// EPD display with a framebuffer, driven by a controller picked at runtime, see `DynDriver`.
+///
+/// Holds the same framebuffer as `Epd` for a size known at build time, or a runtime sized one.
+pub struct DynEpd<'d, I: DisplayInterface, S, B> {
     pub interface: I,
-    pub framebuf: FrameBuffer<S>,
-    _phantom: PhantomData<(S, D)>,
+    pub framebuf: FrameBuffer<S, B>,
+    driver: &'d dyn DynDriver<I>,
+    initialized: bool,
 }
 
-impl<DI: DisplayInterface, S: DisplaySize, D: FastUpdateDriver> FastUpdateEpd<DI, S, D>
-where
-    [(); S::N]:,
-{
-    pub fn new(interface: DI) -> Self {
+impl<'d, DI: DisplayInterface, S, B: AsRef<[u8]> + AsMut<[u8]>> DynEpd<'d, DI, S, B> {
+    /// Create the display around `framebuf`, cleared to white for `driver`.
+    pub fn new(
+        interface: DI,
+        driver: &'d dyn DynDriver<DI>,
+        mut framebuf: FrameBuffer<S, B>,
+    ) -> Self {
+        framebuf.clear(BinaryColor::from(!driver.black_bit())).ok();
         Self {
             interface,
-            framebuf: if D::BLACK_BIT == false {
-                FrameBuffer::new_ones()
-            } else {
-                FrameBuffer::new()
-            },
-            _phantom: PhantomData,
+            framebuf,
+            driver,
+            initialized: false,
         }
     }
 
-    pub fn init<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    /// Consume the display and return the interface and framebuffer.
+    pub fn release(self) -> (DI, FrameBuffer<S, B>) {
+        (self.interface, self.framebuf)
+    }
+
+    /// The driver picked for this display.
+    pub fn driver(&self) -> &'d dyn DynDriver<DI> {
+        self.driver
+    }
+
+    pub fn init<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), DisplayError>
     where
         DELAY: embedded_hal::delay::DelayNs,
     {
-        D::wake_up(&mut self.interface, delay)?;
-        D::set_shape(&mut self.interface, S::WIDTH as _, S::HEIGHT as _)?;
-        D::setup_fast_waveform(&mut self.interface)?;
+        self.interface.set_phase(Phase::Init);
+        self.driver.wake_up(&mut self.interface, delay)?;
+        let size = self.framebuf.native_size();
+        let offset = self.framebuf.ram_offset();
+        self.driver.set_shape(
+            &mut self.interface,
+            (size.width as usize + offset.0) as _,
+            (size.height as usize + offset.1) as _,
+        )?;
+        self.initialized = true;
         Ok(())
     }
 
@@ -150,166 +2398,294 @@ where
         self.framebuf.set_rotation(rotation);
     }
 
-    pub fn display_frame(&mut self) -> Result<(), D::Error> {
-        D::update_frame(&mut self.interface, self.framebuf.as_bytes())?;
-        <D as WaveformDriver>::turn_on_display(&mut self.interface)?;
-        Ok(())
+    pub fn set_mirroring(&mut self, mirroring: Mirroring) {
+        self.framebuf.set_mirroring(mirroring);
     }
 
-    pub fn display_frame_full_update(&mut self) -> Result<(), D::Error> {
-        D::restore_normal_waveform(&mut self.interface)?;
-        D::update_frame(&mut self.interface, self.framebuf.as_bytes())?;
-        <D as WaveformDriver>::turn_on_display(&mut self.interface)?;
-        D::setup_fast_waveform(&mut self.interface)?;
-        Ok(())
+    pub fn display_frame(&mut self) -> Result<(), DisplayError> {
+        ensure_initialized(self.initialized)?;
+        let size = self.framebuf.native_size();
+        let offset = self.framebuf.ram_offset();
+        self.interface.set_phase(Phase::FrameTransfer);
+        if offset == (0, 0) {
+            self.driver
+                .update_frame(&mut self.interface, self.framebuf.as_bytes())?;
+        } else {
+            let width_in_byte = (size.width as usize).div_ceil(8);
+            self.driver.update_frame_window(
+                &mut self.interface,
+                offset.0 as _,
+                offset.1 as _,
+                (width_in_byte * 8) as _,
+                size.height as _,
+                self.framebuf.as_bytes(),
+            )?;
+        }
+        self.interface.set_phase(Phase::Refresh);
+        self.driver.turn_on_display(&mut self.interface)
     }
 
-    pub fn sleep<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    pub fn sleep<DELAY>(&mut self, delay: &mut DELAY, mode: SleepMode) -> Result<(), DisplayError>
     where
         DELAY: embedded_hal::delay::DelayNs,
     {
-        D::sleep(&mut self.interface, delay)
+        self.initialized = false;
+        self.interface.set_phase(Phase::Sleep);
+        self.driver.sleep(&mut self.interface, delay, mode)
     }
 
-    pub fn wake_up<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    /// Re-initialize the controller after `sleep`, does nothing if the display is awake.
+    pub fn wake_up<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), DisplayError>
     where
         DELAY: embedded_hal::delay::DelayNs,
     {
-        D::wake_up(&mut self.interface, delay)?;
-        D::set_shape(&mut self.interface, S::WIDTH as _, S::HEIGHT as _)?;
-        Ok(())
+        if self.initialized {
+            return Ok(());
+        }
+        self.init(delay)
     }
 }
 
-impl<I: DisplayInterface, S: DisplaySize, D: FastUpdateDriver> Dimensions for FastUpdateEpd<I, S, D>
-where
-    [(); S::N]:,
-{
+impl<DI: DisplayInterface, S, B: AsRef<[u8]> + AsMut<[u8]>> Dimensions for DynEpd<'_, DI, S, B> {
     fn bounding_box(&self) -> Rectangle {
         self.framebuf.bounding_box()
     }
 }
 
-impl<I: DisplayInterface, S: DisplaySize, D: FastUpdateDriver> DrawTarget for FastUpdateEpd<I, S, D>
-where
-    [(); S::N]:,
-{
-    type Color = embedded_graphics::pixelcolor::BinaryColor;
-    type Error = core::convert::Infallible;
+impl<DI: DisplayInterface, S, B: AsRef<[u8]> + AsMut<[u8]>> DrawTarget for DynEpd<'_, DI, S, B> {
+    type Color = BinaryColor;
+    type Error = DisplayError;
 
     fn draw_iter<IP>(&mut self, pixels: IP) -> Result<(), Self::Error>
     where
-        IP: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
+        IP: IntoIterator<Item = Pixel<Self::Color>>,
     {
         self.framebuf.draw_iter(pixels)
     }
-}
 
-pub struct TriColorEpd<I: DisplayInterface, S: DisplaySize, D: Driver>
-where
-    [(); S::N]:,
-{
-    pub interface: I,
-    pub framebuf0: FrameBuffer<S>,
-    pub framebuf1: FrameBuffer<S>,
-    _phantom: PhantomData<(S, D)>,
-}
+    fn fill_contiguous<IC>(&mut self, area: &Rectangle, colors: IC) -> Result<(), Self::Error>
+    where
+        IC: IntoIterator<Item = Self::Color>,
+    {
+        self.framebuf.fill_contiguous(area, colors)
+    }
 
-impl<DI: DisplayInterface, S: DisplaySize, D: MultiColorDriver> TriColorEpd<DI, S, D>
-where
-    [(); S::N]:,
-{
-    pub fn new(interface: DI) -> Self {
-        Self {
-            interface,
-            framebuf0: FrameBuffer::new_ones(),
-            framebuf1: FrameBuffer::new(),
-            _phantom: PhantomData,
-        }
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.framebuf.fill_solid(area, color)
     }
+}
 
-    pub fn init<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
-    where
-        DELAY: embedded_hal::delay::DelayNs,
-    {
-        D::wake_up(&mut self.interface, delay)?;
-        D::set_shape(&mut self.interface, S::WIDTH as _, S::HEIGHT as _)?;
+/// Draw target writing through RAM windows as it draws, for targets without room for a framebuffer.
+///
+/// Coordinates are native, without rotation. The bits of the edge bytes outside of a drawn area or pixel
+/// run keep the content of the controller RAM, read back with `Driver::read_frame_row`: shapes that do not
+/// cover whole bytes need an interface that can read, they fail with `DisplayError::Unsupported` otherwise.
+/// Pixels of `draw_iter` are gathered while they stay in the same byte. Requires a driver supporting
+/// `Driver::update_frame_window`.
+pub struct DirectTarget<'a, DI: DisplayInterface, S: DisplaySize, D: Driver> {
+    epd: &'a mut StreamingEpd<DI, S, D>,
+    background: BinaryColor,
+}
 
-        Ok(())
+impl<DI: DisplayInterface, S: DisplaySize, D: Driver> DirectTarget<'_, DI, S, D> {
+    /// Write rows of `width_in_byte` bytes at native byte column `column` and row `y`.
+    fn write_window(
+        &mut self,
+        column: usize,
+        y: usize,
+        width_in_byte: usize,
+        data: &[u8],
+    ) -> Result<(), D::Error> {
+        self.epd.interface.set_phase(Phase::FrameTransfer);
+        D::update_frame_window(
+            &mut self.epd.interface,
+            (S::X_OFFSET + column * 8) as _,
+            (S::Y_OFFSET + y) as _,
+            (width_in_byte * 8) as _,
+            (data.len() / width_in_byte) as _,
+            data,
+        )
     }
 
-    pub fn set_rotation(&mut self, rotation: i32) {
-        self.framebuf0.set_rotation(rotation);
-        self.framebuf1.set_rotation(rotation);
+    /// Restore the full frame window, for the next refresh and stream.
+    fn restore_shape(&mut self) -> Result<(), D::Error> {
+        set_shape::<D, _>(
+            &mut self.epd.interface,
+            S::WIDTH,
+            S::HEIGHT,
+            (S::X_OFFSET, S::Y_OFFSET),
+        )
     }
 
-    pub fn display_frame(&mut self) -> Result<(), D::Error> {
-        defmt::info!("B/W {:?}", &self.framebuf0.as_bytes()[0..10]);
-        defmt::info!("RED {:?}", &self.framebuf1.as_bytes()[0..10]);
-        D::update_channel_frame(&mut self.interface, 0, self.framebuf0.as_bytes())?;
-        D::update_channel_frame(&mut self.interface, 1, self.framebuf1.as_bytes())?;
-        D::turn_on_display(&mut self.interface)
+    /// RAM content of the byte at native byte column `column` and row `y`.
+    fn read_byte(&mut self, column: usize, y: usize) -> Result<u8, D::Error> {
+        let mut byte = [0];
+        D::read_frame_row(
+            &mut self.epd.interface,
+            (S::X_OFFSET + column * 8) as _,
+            (S::Y_OFFSET + y) as _,
+            &mut byte,
+        )?;
+        Ok(byte[0])
     }
 
-    pub fn sleep<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
-    where
-        DELAY: embedded_hal::delay::DelayNs,
-    {
-        D::sleep(&mut self.interface, delay)
+    /// Write the bits of `mask` in `bits` to a single byte, keeping the other bits of the RAM.
+    fn write_byte(&mut self, column: usize, y: usize, bits: u8, mask: u8) -> Result<(), D::Error> {
+        let bits = if mask == 0xff {
+            bits
+        } else {
+            bits & mask | self.read_byte(column, y)? & !mask
+        };
+        self.write_window(column, y, 1, &[bits])
     }
 
-    pub fn wake_up<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
-    where
-        DELAY: embedded_hal::delay::DelayNs,
-    {
-        D::wake_up(&mut self.interface, delay)?;
-        D::set_shape(&mut self.interface, S::WIDTH as _, S::HEIGHT as _)?;
-        Ok(())
+    fn background_byte(&self) -> u8 {
+        if self.background.is_on() {
+            0xff
+        } else {
+            0x00
+        }
     }
 }
 
-impl<I: DisplayInterface, S: DisplaySize, D: Driver> Dimensions for TriColorEpd<I, S, D>
-where
-    [(); S::N]:,
-{
+impl<DI: DisplayInterface, S: DisplaySize, D: Driver> Dimensions for DirectTarget<'_, DI, S, D> {
     fn bounding_box(&self) -> Rectangle {
-        self.framebuf0.bounding_box()
+        Rectangle::new(Point::zero(), Size::new(S::WIDTH as _, S::HEIGHT as _))
     }
 }
 
-impl<I: DisplayInterface, SIZE: DisplaySize, D: Driver> DrawTarget for TriColorEpd<I, SIZE, D>
-where
-    [(); SIZE::N]:,
-{
-    type Color = TriColor;
-    type Error = core::convert::Infallible;
+impl<DI: DisplayInterface, S: DisplaySize, D: Driver> DrawTarget for DirectTarget<'_, DI, S, D> {
+    type Color = BinaryColor;
+    type Error = D::Error;
 
     fn draw_iter<IP>(&mut self, pixels: IP) -> Result<(), Self::Error>
     where
-        IP: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
+        IP: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        for Pixel(point, color) in pixels.into_iter() {
-            match color {
-                TriColor::White => {
-                    self.framebuf0.draw_iter([Pixel(point, BinaryColor::On)])?;
-                    self.framebuf1.draw_iter([Pixel(point, BinaryColor::Off)])?;
+        let area = self.bounding_box();
+        // byte column, row, bits and drawn bits of the byte being gathered
+        let mut pending: Option<(usize, usize, u8, u8)> = None;
+        for Pixel(point, color) in pixels {
+            if !area.contains(point) {
+                continue;
+            }
+            let (x, y) = (point.x as usize, point.y as usize);
+            let (column, mut bits, mut mask) = match pending {
+                Some((column, row, bits, mask)) if column == x / 8 && row == y => {
+                    (column, bits, mask)
+                }
+                _ => {
+                    if let Some((column, row, bits, mask)) = pending {
+                        self.write_byte(column, row, bits, mask)?;
+                    }
+                    (x / 8, 0, 0)
+                }
+            };
+            let bit = 0x80 >> (x % 8);
+            if color.is_on() {
+                bits |= bit;
+            } else {
+                bits &= !bit;
+            }
+            mask |= bit;
+            pending = Some((column, y, bits, mask));
+        }
+        match pending {
+            Some((column, row, bits, mask)) => {
+                self.write_byte(column, row, bits, mask)?;
+                self.restore_shape()
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn fill_contiguous<IC>(&mut self, area: &Rectangle, colors: IC) -> Result<(), Self::Error>
+    where
+        IC: IntoIterator<Item = Self::Color>,
+    {
+        let clipped = area.intersection(&self.bounding_box());
+        let Some(bottom_right) = clipped.bottom_right() else {
+            return Ok(());
+        };
+        let (x0, y0) = (clipped.top_left.x as usize, clipped.top_left.y as usize);
+        let (x1, y1) = (bottom_right.x as usize + 1, bottom_right.y as usize + 1);
+        let (first, last) = (x0 / 8, (x1 - 1) / 8);
+        let width_in_byte = last - first + 1;
+        let band_rows = stream_rows(width_in_byte)?;
+        let mut band = [0u8; STREAM_CHUNK];
+
+        let mut colors = area
+            .points()
+            .zip(colors)
+            .filter(|(point, _)| clipped.contains(*point))
+            .map(|(_, color)| color);
+        for y in (y0..y1).step_by(band_rows) {
+            let n = band_rows.min(y1 - y) * width_in_byte;
+            band[..n].fill(self.background_byte());
+            for (i, row) in band[..n].chunks_exact_mut(width_in_byte).enumerate() {
+                // the bits of the edge bytes outside of the area keep the RAM content
+                if x0 % 8 != 0 {
+                    row[0] = self.read_byte(first, y + i)?;
                 }
-                TriColor::Black => {
-                    self.framebuf0.draw_iter([Pixel(point, BinaryColor::Off)])?;
-                    self.framebuf1.draw_iter([Pixel(point, BinaryColor::Off)])?;
+                if x1 % 8 != 0 && (last != first || x0 % 8 == 0) {
+                    row[width_in_byte - 1] = self.read_byte(last, y + i)?;
                 }
-                TriColor::Red => {
-                    self.framebuf0.draw_iter([Pixel(point, BinaryColor::On)])?;
-                    self.framebuf1.draw_iter([Pixel(point, BinaryColor::On)])?;
+                for x in x0 - first * 8..x1 - first * 8 {
+                    if colors.next().unwrap_or(self.background).is_on() {
+                        row[x / 8] |= 0x80 >> (x % 8);
+                    } else {
+                        row[x / 8] &= !(0x80 >> (x % 8));
+                    }
                 }
             }
+            self.write_window(first, y, width_in_byte, &band[..n])?;
         }
-        Ok(())
+        self.restore_shape()
     }
 }
 
-pub struct GrayScaleEpd<C, I: DisplayInterface, SIZE: DisplaySize, D: GrayScaleDriver<C>>
-where
+impl<DI: DisplayInterface, S: DisplaySize, D: Driver<Error = DisplayError>> StreamingEpd<DI, S, D> {
+    /// Stream a frame from an external SRAM framebuffer and refresh the display.
+    ///
+    /// SRAM reads and display writes alternate chunk by chunk on the shared bus.
+    pub fn display_frame_from_sram<SPI: SpiDevice>(
+        &mut self,
+        sram: &mut SpiSramFrameBuffer<S, SPI>,
+    ) -> Result<(), DisplayError> {
+        self.ensure_awake()?;
+        let width_in_byte = S::WIDTH.div_ceil(8);
+        let rows = stream_rows(width_in_byte)?;
+        let mut chunk = [0u8; STREAM_CHUNK];
+
+        for y in (0..S::HEIGHT).step_by(rows) {
+            let n = rows.min(S::HEIGHT - y) * width_in_byte;
+            sram.read(y * width_in_byte, &mut chunk[..n])?;
+            update_rows::<D, _>(
+                &mut self.interface,
+                S::WIDTH,
+                S::HEIGHT,
+                (S::X_OFFSET, S::Y_OFFSET),
+                y,
+                &chunk[..n],
+            )?;
+        }
+        self.interface.set_phase(Phase::Refresh);
+        D::turn_on_display(&mut self.interface)
+    }
+}
+
+/// Most layers of `GrayScaleEpd::set_level_curve`, enough for `Gray4`.
+#[cfg(feature = "nightly")]
+const MAX_GRAY_LAYERS: usize = 15;
+
+#[cfg(feature = "nightly")]
+pub struct GrayScaleEpd<
+    C,
+    I: DisplayInterface,
+    SIZE: DisplaySize,
+    D: GrayScaleDriver<C>,
+    CLK = fn() -> u32,
+> where
     C: GrayColor + GrayColorInBits + PixelColor + From<<C as PixelColor>::Raw>,
     [(); SIZE::N]:,
     [(); C::BITS_PER_PIXEL]:,
@@ -317,11 +2693,26 @@ where
 {
     pub interface: I,
     pub framebuf: GrayFrameBuffer<SIZE, C>,
+    initialized: bool,
+    /// Put to sleep by `sleep`, as opposed to never initialized
+    asleep: bool,
+    /// Delay in µs used to wake up a sleeping display on refresh, see `set_wake_delay`
+    wake_delay: Option<fn(u32)>,
+    /// Refreshes of each layer, see `set_level_curve`
+    level_curve: [u8; MAX_GRAY_LAYERS],
+    /// Waveform of the black and white drive before the layers, see `set_pre_clear`
+    pre_clear: Option<&'static [u8]>,
+    /// Timing of the layers, the one of the driver if `None`, see `set_timing`
+    timing: Option<GrayScaleTiming>,
+    /// Counters reported by `stats`
+    stats: EpdStats,
+    /// Time source of the busy time of `stats`, see `with_clock`
+    clock: Option<CLK>,
     _phantom: PhantomData<D>,
 }
 
-impl<'a, C, I: DisplayInterface, SIZE: DisplaySize, D: GrayScaleDriver<C>>
-    GrayScaleEpd<C, I, SIZE, D>
+#[cfg(feature = "nightly")]
+impl<C, I: DisplayInterface, SIZE: DisplaySize, D: GrayScaleDriver<C>> GrayScaleEpd<C, I, SIZE, D>
 where
     C: GrayColor + GrayColorInBits + PixelColor + From<<C as PixelColor>::Raw>,
     [(); SIZE::N]:,
@@ -332,17 +2723,103 @@ where
         Self {
             interface,
             framebuf: GrayFrameBuffer::new(),
+            initialized: false,
+            asleep: false,
+            wake_delay: None,
+            level_curve: [1; MAX_GRAY_LAYERS],
+            pre_clear: None,
+            timing: None,
+            stats: EpdStats::new(),
+            clock: None,
             _phantom: PhantomData,
         }
     }
+}
+
+#[cfg(feature = "nightly")]
+impl<C, I: DisplayInterface, SIZE: DisplaySize, D: GrayScaleDriver<C>, CLK: Clock>
+    GrayScaleEpd<C, I, SIZE, D, CLK>
+where
+    C: GrayColor + GrayColorInBits + PixelColor + From<<C as PixelColor>::Raw>,
+    [(); SIZE::N]:,
+    [(); C::BITS_PER_PIXEL]:,
+    [(); SIZE::N * C::BITS_PER_PIXEL]:,
+{
+    /// Read the time from `clock`, e.g. a HAL timer, for the busy time of `stats`.
+    ///
+    /// The clock becomes part of the display type.
+    pub fn with_clock<CLK2: Clock>(self, clock: CLK2) -> GrayScaleEpd<C, I, SIZE, D, CLK2> {
+        GrayScaleEpd {
+            interface: self.interface,
+            framebuf: self.framebuf,
+            initialized: self.initialized,
+            asleep: self.asleep,
+            wake_delay: self.wake_delay,
+            level_curve: self.level_curve,
+            pre_clear: self.pre_clear,
+            timing: self.timing,
+            stats: self.stats,
+            clock: Some(clock),
+            _phantom: self._phantom,
+        }
+    }
+
+    /// Refreshes and transfers since the display was created.
+    pub fn stats(&self) -> EpdStats {
+        EpdStats {
+            bytes_sent: self.interface.bytes_sent(),
+            ..self.stats
+        }
+    }
+
+    /// Set how often each layer of `display_frame` is refreshed, to shape the perceived gray levels.
+    ///
+    /// `curve[n]` refreshes darken all pixels of luma `n` and below, a pixel of luma `l` gets the sum of
+    /// `curve[l..]`. 0 skips the layer. The curve must have `C::MAX_VALUE` entries, up to 15.
+    pub fn set_level_curve(&mut self, curve: &[u8]) -> Result<(), DisplayError> {
+        if curve.len() != C::MAX_VALUE as usize || curve.len() > MAX_GRAY_LAYERS {
+            return Err(DisplayError::InvalidFormatError);
+        }
+        self.level_curve[..curve.len()].copy_from_slice(curve);
+        Ok(())
+    }
+
+    /// Drive the whole panel black, then white, with the waveform `lut` before the layers of `display_frame`.
+    ///
+    /// Clears the ghost of the previous image, which the layers would otherwise darken further.
+    /// `lut` is in the format of `WaveformDriver::update_waveform`, a few short phases are enough.
+    /// Off by default, `display_partial_frame` never runs it.
+    pub fn set_pre_clear(&mut self, lut: Option<&'static [u8]>) {
+        self.pre_clear = lut;
+    }
+
+    /// Set the frames and repeats of each layer, `None` for the timing of the driver.
+    ///
+    /// Requires a driver supporting `GrayScaleDriver::setup_gray_scale_waveform_timed`, the timing is
+    /// checked by the next `display_frame`.
+    pub fn set_timing(&mut self, timing: Option<GrayScaleTiming>) {
+        self.timing = timing;
+    }
+
+    /// Consume the display and return the interface and framebuffer.
+    pub fn release(self) -> (I, GrayFrameBuffer<SIZE, C>) {
+        (self.interface, self.framebuf)
+    }
 
     pub fn init<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
     where
         DELAY: embedded_hal::delay::DelayNs,
     {
+        self.interface.set_phase(Phase::Init);
         D::wake_up(&mut self.interface, delay)?;
-        D::set_shape(&mut self.interface, SIZE::WIDTH as _, SIZE::HEIGHT as _)?;
-
+        set_shape::<D, _>(
+            &mut self.interface,
+            SIZE::WIDTH,
+            SIZE::HEIGHT,
+            (SIZE::X_OFFSET, SIZE::Y_OFFSET),
+        )?;
+        self.initialized = true;
+        self.asleep = false;
         Ok(())
     }
 
@@ -351,61 +2828,290 @@ where
     }
 
     pub fn display_frame(&mut self) -> Result<(), D::Error> {
-        D::setup_gray_scale_waveform(&mut self.interface)?;
+        self.ensure_awake()?;
+        if let Some(lut) = self.pre_clear {
+            self.interface.set_phase(Phase::LutLoad);
+            D::update_waveform(&mut self.interface, lut)?;
+            for color in [BinaryColor::Off, BinaryColor::On] {
+                self.write_solid(color)?;
+                self.turn_on_layer()?;
+            }
+        }
+        self.setup_layer_waveform()?;
+
+        self.stats.full_refreshes += 1;
+        self.display_layers(0..SIZE::WIDTH, 0..SIZE::HEIGHT, |di, y, rows| {
+            update_rows::<D, _>(
+                di,
+                SIZE::WIDTH,
+                SIZE::HEIGHT,
+                (SIZE::X_OFFSET, SIZE::Y_OFFSET),
+                y,
+                rows,
+            )
+        })
+    }
+
+    /// Show the frame in black and white with a single normal refresh, pixels below `threshold` are black.
+    ///
+    /// Much faster than `display_frame`, for quick updates in between gray scale renders.
+    pub fn display_frame_binary(&mut self, threshold: u8) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        self.interface.set_phase(Phase::LutLoad);
+        D::restore_normal_waveform(&mut self.interface)?;
 
-        let width_in_byte = SIZE::WIDTH / 8 + (SIZE::WIDTH % 8 != 0) as usize;
+        let width_in_byte = SIZE::WIDTH.div_ceil(8);
+        let rows = stream_rows(width_in_byte)?;
+        let mut band = [0u8; STREAM_CHUNK];
 
-        for i in (0..C::MAX_VALUE + 1).rev() {
-            defmt::debug!("display layer {}", i);
-            let mut tmp = [0xffu8; SIZE::N];
-            // extract gray channel and fill in the tmp buffer
-            for y in 0..SIZE::HEIGHT {
-                for x in 0..SIZE::WIDTH {
-                    let byte_offset = y * width_in_byte + x / 8;
-                    let bit_offset = 7 - x % 8;
+        for y in (0..SIZE::HEIGHT).step_by(rows) {
+            let n = rows.min(SIZE::HEIGHT - y) * width_in_byte;
+            self.framebuf
+                .layer_rows(y, 0..SIZE::WIDTH, threshold, &mut band[..n]);
+            to_driver_polarity::<D>(&mut band[..n]);
+            update_rows::<D, _>(
+                &mut self.interface,
+                SIZE::WIDTH,
+                SIZE::HEIGHT,
+                (SIZE::X_OFFSET, SIZE::Y_OFFSET),
+                y,
+                &band[..n],
+            )?;
+        }
+        self.turn_on_display()
+    }
 
-                    let pixel = self.framebuf.get_pixel_in_raw_pos(x, y);
+    /// Run the layered refresh of `display_frame` only for `area`, through a RAM window.
+    ///
+    /// Layers only darken pixels, the area must be white on the panel, e.g. after `clear_display`.
+    /// Requires a driver supporting `Driver::update_frame_window`.
+    pub fn display_partial_frame(&mut self, area: &Rectangle) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        let Some((x0, y0, x1, y1)) = self.framebuf.native_rect(area) else {
+            return Ok(());
+        };
+        self.setup_layer_waveform()?;
 
-                    let val = pixel.luma(); // 0, 1, 2, 3
-                                            // defmt::info!("x {} y {}  val {}", x, y, val);
+        // pixels outside of the window must not be driven
+        let (x_offset, y_offset) = (SIZE::X_OFFSET, SIZE::Y_OFFSET);
+        let width_in_byte = (SIZE::WIDTH + x_offset).div_ceil(8);
+        let white = if D::BLACK_BIT { 0x00 } else { 0xff };
+        self.interface.set_phase(Phase::FrameTransfer);
+        D::update_frame(
+            &mut self.interface,
+            core::iter::repeat_n(&white, width_in_byte * (SIZE::HEIGHT + y_offset)),
+        )?;
 
-                    if val == 7 {
-                        // defmt::info!("layer 7");
-                    }
-                    if val < i {
-                        tmp[byte_offset] &= !(1 << bit_offset);
-                        //tmp[byte_offset] |= (1 << bit_offset);
-                    }
-                }
+        let (first, last) = (x0 / 8, (x1 - 1) / 8);
+        let width = (last - first + 1) as u16 * 8;
+        self.stats.partial_refreshes += 1;
+        self.display_layers(x0..x1, y0..y1, |di, y, rows| {
+            let height = rows.len() / (width as usize / 8);
+            let (x, y) = (x_offset + first * 8, y_offset + y);
+            D::update_frame_window(di, x as u16, y as u16, width, height as u16, rows)
+        })?;
+
+        set_shape::<D, _>(
+            &mut self.interface,
+            SIZE::WIDTH,
+            SIZE::HEIGHT,
+            (SIZE::X_OFFSET, SIZE::Y_OFFSET),
+        )
+    }
+
+    fn setup_layer_waveform(&mut self) -> Result<(), D::Error> {
+        self.interface.set_phase(Phase::LutLoad);
+        match self.timing {
+            Some(timing) => D::setup_gray_scale_waveform_timed(&mut self.interface, timing),
+            None => D::setup_gray_scale_waveform(&mut self.interface),
+        }
+    }
+
+    /// Send and refresh every layer of `columns` x `rows` in bands, following the level curve.
+    fn display_layers(
+        &mut self,
+        columns: core::ops::Range<usize>,
+        rows: core::ops::Range<usize>,
+        mut send: impl FnMut(&mut I, usize, &[u8]) -> Result<(), D::Error>,
+    ) -> Result<(), D::Error> {
+        let width_in_byte = (columns.end - 1) / 8 - columns.start / 8 + 1;
+        let band_rows = stream_rows(width_in_byte)?;
+        let mut band = [0u8; STREAM_CHUNK];
+
+        // every layer darkens the pixels below its level a bit more, level 0 would change nothing
+        for level in (1..=C::MAX_VALUE).rev() {
+            let refreshes = self
+                .level_curve
+                .get(level as usize - 1)
+                .copied()
+                .unwrap_or(1);
+            debug!("display layer {} x{}", level, refreshes);
+            if refreshes == 0 {
+                continue;
+            }
+            self.interface.set_phase(Phase::FrameTransfer);
+            for y in rows.clone().step_by(band_rows) {
+                let n = band_rows.min(rows.end - y) * width_in_byte;
+                self.framebuf
+                    .layer_rows(y, columns.clone(), level, &mut band[..n]);
+                to_driver_polarity::<D>(&mut band[..n]);
+                send(&mut self.interface, y, &band[..n])?;
+            }
+            for _ in 0..refreshes {
+                self.turn_on_layer()?;
             }
-            println!("frame {}", tmp.iter().filter(|&&x| x != 0xff).count());
-            D::update_frame(&mut self.interface, &tmp)?;
-            <D as WaveformDriver>::turn_on_display(&mut self.interface)?;
         }
 
         Ok(())
     }
 
-    pub fn sleep<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
+    /// Refresh with the normal waveform.
+    fn turn_on_display(&mut self) -> Result<(), D::Error> {
+        self.interface.set_phase(Phase::Refresh);
+        let start = now(&mut self.clock);
+        <D as Driver>::turn_on_display(&mut self.interface)?;
+        self.stats.add_busy(start, now(&mut self.clock));
+        self.stats.full_refreshes += 1;
+        Ok(())
+    }
+
+    /// Refresh with the waveform loaded last, one of the layers of a frame.
+    fn turn_on_layer(&mut self) -> Result<(), D::Error> {
+        self.interface.set_phase(Phase::Refresh);
+        let start = now(&mut self.clock);
+        <D as WaveformDriver>::turn_on_display(&mut self.interface)?;
+        self.stats.add_busy(start, now(&mut self.clock));
+        Ok(())
+    }
+
+    /// Read the panel temperature in °C from the controller's internal sensor.
+    ///
+    /// On SSD controllers this reloads the waveform from OTP, see `Driver::read_temperature`.
+    pub fn read_temperature(&mut self) -> Result<i8, D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::read_temperature(&mut self.interface)
+    }
+
+    /// Set the ambient temperature in °C used to select the waveform, for panels without a sensor.
+    ///
+    /// Must be called again after `init` or `wake_up`.
+    pub fn set_temperature(&mut self, celsius: i8) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::set_temperature(&mut self.interface, celsius)
+    }
+
+    /// Set the gate and source driving voltages, must be called again after `init` or `wake_up`.
+    pub fn set_driving_voltages(&mut self, voltages: &DrivingVoltages) -> Result<(), D::Error> {
+        ensure_initialized(self.initialized)?;
+        D::set_driving_voltages(&mut self.interface, voltages)
+    }
+
+    pub fn sleep<DELAY>(&mut self, delay: &mut DELAY, mode: SleepMode) -> Result<(), D::Error>
+    where
+        DELAY: embedded_hal::delay::DelayNs,
+    {
+        self.initialized = false;
+        self.interface.set_phase(Phase::Sleep);
+        D::sleep(&mut self.interface, delay, mode)?;
+        self.asleep = true;
+        Ok(())
+    }
+
+    pub fn wake_up<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), D::Error>
     where
         DELAY: embedded_hal::delay::DelayNs,
     {
-        D::sleep(&mut self.interface, delay)
+        self.init(delay)
+    }
+
+    /// Let the refreshes wake up a display put to sleep by `sleep`, as `Epd::set_wake_delay` does.
+    pub fn set_wake_delay(&mut self, delay_us: Option<fn(u32)>) {
+        self.wake_delay = delay_us;
+    }
+
+    /// Wake up the display if it sleeps and a wake delay is set, then check that it is initialized.
+    fn ensure_awake(&mut self) -> Result<(), D::Error> {
+        if let (true, Some(delay_us)) = (self.asleep, self.wake_delay) {
+            self.wake_up(&mut FnDelay(delay_us))?;
+        }
+        ensure_initialized(self.initialized)?;
+        Ok(())
     }
 
     pub fn clear_display(&mut self, color: BinaryColor) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        self.interface.set_phase(Phase::LutLoad);
         D::restore_normal_waveform(&mut self.interface)?;
 
         self.framebuf.fill(color);
+        self.write_solid(color)?;
+        self.turn_on_display()
+    }
 
-        D::update_frame(&mut self.interface, self.framebuf.as_bytes())?;
-        <D as Driver>::turn_on_display(&mut self.interface)?;
+    /// Write the whole RAM with `color`, the framebuffer is left as is.
+    fn write_solid(&mut self, color: BinaryColor) -> Result<(), D::Error> {
+        let width_in_byte = SIZE::WIDTH.div_ceil(8);
+        let rows = stream_rows(width_in_byte)?;
+        let mut band = [if color.is_on() { 0xff } else { 0x00 }; STREAM_CHUNK];
+        to_driver_polarity::<D>(&mut band);
+
+        for y in (0..SIZE::HEIGHT).step_by(rows) {
+            let n = rows.min(SIZE::HEIGHT - y) * width_in_byte;
+            update_rows::<D, _>(
+                &mut self.interface,
+                SIZE::WIDTH,
+                SIZE::HEIGHT,
+                (SIZE::X_OFFSET, SIZE::Y_OFFSET),
+                y,
+                &band[..n],
+            )?;
+        }
         Ok(())
     }
 }
 
-impl<C, DI: DisplayInterface, S: DisplaySize, D: GrayScaleDriver<C>> DrawTarget
-    for GrayScaleEpd<C, DI, S, D>
+#[cfg(feature = "nightly")]
+impl<I: DisplayInterface, SIZE: DisplaySize, D: FourGrayDriver, CLK: Clock>
+    GrayScaleEpd<Gray2, I, SIZE, D, CLK>
+where
+    [(); SIZE::N]:,
+    [(); <Gray2 as GrayColorInBits>::BITS_PER_PIXEL]:,
+    [(); SIZE::N * <Gray2 as GrayColorInBits>::BITS_PER_PIXEL]:,
+{
+    /// Show the frame with the controller's 4 gray waveform in one refresh,
+    /// instead of one flash per level as `display_frame` does.
+    pub fn display_frame_four_gray(&mut self) -> Result<(), D::Error> {
+        self.ensure_awake()?;
+        self.interface.set_phase(Phase::LutLoad);
+        D::setup_four_gray_waveform(&mut self.interface)?;
+
+        let width_in_byte = SIZE::WIDTH.div_ceil(8);
+        let rows = stream_rows(width_in_byte)?;
+        let mut band = [0u8; STREAM_CHUNK];
+
+        for (bit, plane) in [(0, Plane::BlackWhite), (1, Plane::Chromatic)] {
+            for y in (0..SIZE::HEIGHT).step_by(rows) {
+                let n = rows.min(SIZE::HEIGHT - y) * width_in_byte;
+                self.framebuf.plane_rows(y, bit, &mut band[..n]);
+                to_driver_polarity::<D>(&mut band[..n]);
+                update_plane_rows::<D, _>(
+                    &mut self.interface,
+                    plane,
+                    SIZE::WIDTH,
+                    (SIZE::X_OFFSET, SIZE::Y_OFFSET),
+                    y,
+                    &band[..n],
+                )?;
+            }
+        }
+        self.stats.full_refreshes += 1;
+        self.turn_on_layer()
+    }
+}
+
+#[cfg(feature = "nightly")]
+impl<C, DI: DisplayInterface, S: DisplaySize, D: GrayScaleDriver<C>, CLK> DrawTarget
+    for GrayScaleEpd<C, DI, S, D, CLK>
 where
     [(); S::N]:,
     [(); C::BITS_PER_PIXEL]:,
@@ -427,8 +3133,9 @@ where
     }
 }
 
-impl<C, DI: DisplayInterface, S: DisplaySize, D: GrayScaleDriver<C>> Dimensions
-    for GrayScaleEpd<C, DI, S, D>
+#[cfg(feature = "nightly")]
+impl<C, DI: DisplayInterface, S: DisplaySize, D: GrayScaleDriver<C>, CLK> Dimensions
+    for GrayScaleEpd<C, DI, S, D, CLK>
 where
     [(); S::N]:,
     [(); C::BITS_PER_PIXEL]:,
@@ -0,0 +1,666 @@
+//! Waveform tables (LUTs) of the SSD controllers, as written by `WaveformDriver::update_waveform`.
+//!
+//! A waveform runs groups of phases in order. In each phase, every LUT drives the pixels it applies to
+//! with one voltage source (VS) for TP frames, and a group runs RP + 1 times. The types wrap the raw
+//! tables to read and change these fields, and print them as a table with `Debug` or `defmt`:
+//!
+//! - `Lut30`: SSD1608 and IL3895, 20 phases of one VS byte each and no repeats
+//! - `Lut70`: SSD1619A, 7 groups of 4 phases
+//! - `Lut105`: SSD1675B, 10 groups of 4 phases with frame rates
+//! - `Lut153`: SSD1680, 12 groups of 4 phases with state repeats, frame rates and gate scan selection
+//!
+//! `validate` catches obviously broken tables, the SSD drivers run it in debug builds before writing one.
+//! `Lut::scale_timing` stretches a waveform, e.g. for a cold panel, `WaveformDriver::scale_timing`
+//! writes a table scaled that way.
+
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+
+use crate::interface::DisplayError;
+
+/// Fields of a waveform table, by LUT, group and phase.
+pub trait Lut {
+    /// LUTs of the table, e.g. one per pixel transition
+    const LUTS: usize;
+    /// Groups of phases
+    const GROUPS: usize;
+    /// Phases of each group
+    const PHASES: usize;
+    /// Longest phase in frames
+    const MAX_TP: u8 = u8::MAX;
+
+    fn as_bytes(&self) -> &[u8];
+
+    /// Voltage source driving `lut` in `phase` of `group`: 0 for VSS, 1 for VSH1, 2 for VSL and 3 for VSH2.
+    fn vs(&self, lut: usize, group: usize, phase: usize) -> u8;
+
+    /// Length of `phase` of `group` in frames, 0 skips the phase.
+    fn tp(&self, group: usize, phase: usize) -> u8;
+
+    /// Set the length of `phase` of `group`, up to `MAX_TP` frames.
+    fn set_tp(&mut self, group: usize, phase: usize, frames: u8);
+
+    /// Scale the length of every phase to `percent`, e.g. 150 for a cold panel.
+    ///
+    /// Lengths are rounded and capped at `MAX_TP`, phases with frames keep at least one.
+    fn scale_timing(&mut self, percent: u16) {
+        for group in 0..Self::GROUPS {
+            for phase in 0..Self::PHASES {
+                let tp = self.tp(group, phase) as u32;
+                if tp != 0 {
+                    let scaled = (tp * percent as u32 + 50) / 100;
+                    self.set_tp(group, phase, scaled.clamp(1, Self::MAX_TP as u32) as u8);
+                }
+            }
+        }
+    }
+
+    /// Repeat count of `group`, `None` for tables without one, where every group runs once.
+    ///
+    /// The SSD controllers accept the whole byte, 0 to 255, so any value is a valid count.
+    fn rp(&self, _group: usize) -> Option<u8> {
+        None
+    }
+
+    /// Frame rate setting of `group`, `None` for tables without one.
+    fn fr(&self, _group: usize) -> Option<u8> {
+        None
+    }
+
+    /// Frames of the whole waveform, repeats included.
+    fn frames(&self) -> u32 {
+        (0..Self::GROUPS)
+            .map(|group| {
+                let phases: u32 = (0..Self::PHASES)
+                    .map(|phase| self.tp(group, phase) as u32)
+                    .sum();
+                phases * (self.rp(group).unwrap_or(0) as u32 + 1)
+            })
+            .sum()
+    }
+}
+
+/// Longest waveform accepted by `validate`, in frames, over a minute at 50 Hz.
+///
+/// Vendor waveforms stay far below, a longer one most likely has its repeat counts wrong.
+pub const MAX_FRAMES: u32 = 4000;
+
+/// Problem found by `validate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LutError {
+    /// No phase with frames drives any LUT, the refresh would do nothing
+    Empty,
+    /// The waveform runs longer than `MAX_FRAMES`
+    TooLong { frames: u32 },
+}
+
+/// Check a table for obvious mistakes: nothing driven or too many frames.
+///
+/// Repeat counts are unconstrained, every RP byte is valid, excessive ones show up in the length
+/// of the waveform instead.
+pub fn validate<L: Lut>(lut: &L) -> Result<(), LutError> {
+    let driven = (0..L::GROUPS).any(|group| {
+        (0..L::PHASES).any(|phase| {
+            lut.tp(group, phase) != 0 && (0..L::LUTS).any(|l| lut.vs(l, group, phase) != 0)
+        })
+    });
+    if !driven {
+        return Err(LutError::Empty);
+    }
+    let frames = lut.frames();
+    if frames > MAX_FRAMES {
+        return Err(LutError::TooLong { frames });
+    }
+    Ok(())
+}
+
+/// Copy of `bytes` with the phases scaled to `percent`, for `WaveformDriver::scale_timing`.
+#[cfg_attr(
+    not(any(
+        feature = "il3895",
+        feature = "ssd1608",
+        feature = "ssd1619a",
+        feature = "ssd1675b",
+        feature = "ssd1680"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn scaled<L>(bytes: &[u8], percent: u16) -> Result<L, DisplayError>
+where
+    L: Lut + for<'a> TryFrom<&'a [u8], Error = DisplayError>,
+{
+    let mut lut = L::try_from(bytes)?;
+    lut.scale_timing(percent);
+    debug_validate::<L>(lut.as_bytes())?;
+    Ok(lut)
+}
+
+/// `validate` a table written by `WaveformDriver::update_waveform` in debug builds, if it has the size of `L`.
+#[cfg_attr(
+    not(any(
+        feature = "il3895",
+        feature = "ssd1608",
+        feature = "ssd1619a",
+        feature = "ssd1675b",
+        feature = "ssd1680"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn debug_validate<L>(bytes: &[u8]) -> Result<(), DisplayError>
+where
+    L: Lut + for<'a> TryFrom<&'a [u8]>,
+{
+    if cfg!(debug_assertions) {
+        if let Ok(lut) = L::try_from(bytes) {
+            validate(&lut).map_err(DisplayError::InvalidLut)?;
+        }
+    }
+    Ok(())
+}
+
+macro_rules! lut_type {
+    ($(#[$attr:meta])* $name:ident, $len:expr) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        pub struct $name([u8; $len]);
+
+        impl $name {
+            /// Size of the table in bytes.
+            pub const LEN: usize = $len;
+
+            pub const fn new(bytes: [u8; $len]) -> Self {
+                Self(bytes)
+            }
+
+            /// Copy a table of exactly `LEN` bytes.
+            pub fn from_slice(bytes: &[u8]) -> Result<Self, DisplayError> {
+                Self::try_from(bytes)
+            }
+
+            pub fn into_bytes(self) -> [u8; $len] {
+                self.0
+            }
+        }
+
+        impl TryFrom<&[u8]> for $name {
+            type Error = DisplayError;
+
+            fn try_from(bytes: &[u8]) -> Result<Self, DisplayError> {
+                bytes
+                    .try_into()
+                    .map(Self)
+                    .map_err(|_| DisplayError::InvalidFormatError)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_table(self, stringify!($name), f)
+            }
+        }
+
+        #[cfg(feature = "defmt")]
+        impl defmt::Format for $name {
+            fn format(&self, f: defmt::Formatter<'_>) {
+                format_table(self, stringify!($name), f)
+            }
+        }
+    };
+}
+
+lut_type!(
+    /// Waveform of SSD1608 and IL3895: the VS of 4 LUTs for each of 20 phases, then 4 bits of TP per phase.
+    Lut30,
+    30
+);
+
+lut_type!(
+    /// Waveform of SSD1619A: the VS of 5 LUTs for 7 groups, then TP A to D and RP of each group.
+    Lut70,
+    70
+);
+
+lut_type!(
+    /// Waveform of SSD1675B: the VS of 5 LUTs for 10 groups, TP A to D and RP of each group,
+    /// then 4 bits of frame rate per group.
+    Lut105,
+    105
+);
+
+lut_type!(
+    /// Waveform of SSD1680: the VS of 5 LUTs for 12 groups, TP A and B, SR AB, TP C and D, SR CD and RP
+    /// of each group, 4 bits of frame rate per group, then the gate scan selection (XON).
+    ///
+    /// The voltages following the table in some vendor code are set with `Driver::set_driving_voltages`.
+    Lut153,
+    153
+);
+
+/// VS of `phase` in a byte holding 4 phases, phase A in the high bits.
+fn vs_bits(byte: u8, phase: usize) -> u8 {
+    (byte >> (6 - 2 * phase)) & 0b11
+}
+
+/// Nibble of `group` in bytes holding 2 groups, the even one in the high bits.
+fn group_nibble(bytes: &[u8], group: usize) -> u8 {
+    (bytes[group / 2] >> (4 * (1 - group % 2))) & 0x0f
+}
+
+impl Lut for Lut30 {
+    const LUTS: usize = 4;
+    // each phase is a group of its own, they cannot be repeated
+    const GROUPS: usize = 20;
+    const PHASES: usize = 1;
+    const MAX_TP: u8 = 0x0f;
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn vs(&self, lut: usize, group: usize, _phase: usize) -> u8 {
+        vs_bits(self.0[group], lut)
+    }
+
+    fn tp(&self, group: usize, _phase: usize) -> u8 {
+        // the even phase in the low nibble
+        (self.0[20 + group / 2] >> (4 * (group % 2))) & 0x0f
+    }
+
+    fn set_tp(&mut self, group: usize, _phase: usize, frames: u8) {
+        let shift = 4 * (group % 2);
+        let byte = &mut self.0[20 + group / 2];
+        *byte = (*byte & !(0x0f << shift)) | (frames.min(Self::MAX_TP) << shift);
+    }
+}
+
+impl Lut for Lut70 {
+    const LUTS: usize = 5;
+    const GROUPS: usize = 7;
+    const PHASES: usize = 4;
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn vs(&self, lut: usize, group: usize, phase: usize) -> u8 {
+        vs_bits(self.0[lut * Self::GROUPS + group], phase)
+    }
+
+    fn tp(&self, group: usize, phase: usize) -> u8 {
+        self.0[35 + group * 5 + phase]
+    }
+
+    fn set_tp(&mut self, group: usize, phase: usize, frames: u8) {
+        self.0[35 + group * 5 + phase] = frames;
+    }
+
+    fn rp(&self, group: usize) -> Option<u8> {
+        Some(self.0[35 + group * 5 + 4])
+    }
+}
+
+impl Lut for Lut105 {
+    const LUTS: usize = 5;
+    const GROUPS: usize = 10;
+    const PHASES: usize = 4;
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn vs(&self, lut: usize, group: usize, phase: usize) -> u8 {
+        vs_bits(self.0[lut * Self::GROUPS + group], phase)
+    }
+
+    fn tp(&self, group: usize, phase: usize) -> u8 {
+        self.0[50 + group * 5 + phase]
+    }
+
+    fn set_tp(&mut self, group: usize, phase: usize, frames: u8) {
+        self.0[50 + group * 5 + phase] = frames;
+    }
+
+    fn rp(&self, group: usize) -> Option<u8> {
+        Some(self.0[50 + group * 5 + 4])
+    }
+
+    fn fr(&self, group: usize) -> Option<u8> {
+        Some(group_nibble(&self.0[100..], group))
+    }
+}
+
+/// Offsets of TP A to D in a group of `Lut153`, SR AB and SR CD sit in between.
+const LUT153_TP: [usize; 4] = [0, 1, 3, 4];
+
+impl Lut153 {
+    /// State repeats of the phase pairs AB and CD of `group`.
+    pub fn sr(&self, group: usize) -> (u8, u8) {
+        let at = 60 + group * 7;
+        (self.0[at + 2], self.0[at + 5])
+    }
+
+    /// Gate scan selection (XON) of the groups, one bit per group.
+    pub fn xon(&self) -> [u8; 3] {
+        [self.0[150], self.0[151], self.0[152]]
+    }
+}
+
+impl Lut for Lut153 {
+    const LUTS: usize = 5;
+    const GROUPS: usize = 12;
+    const PHASES: usize = 4;
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn vs(&self, lut: usize, group: usize, phase: usize) -> u8 {
+        vs_bits(self.0[lut * Self::GROUPS + group], phase)
+    }
+
+    fn tp(&self, group: usize, phase: usize) -> u8 {
+        self.0[60 + group * 7 + LUT153_TP[phase]]
+    }
+
+    fn set_tp(&mut self, group: usize, phase: usize, frames: u8) {
+        self.0[60 + group * 7 + LUT153_TP[phase]] = frames;
+    }
+
+    fn rp(&self, group: usize) -> Option<u8> {
+        Some(self.0[60 + group * 7 + 6])
+    }
+
+    fn fr(&self, group: usize) -> Option<u8> {
+        Some(group_nibble(&self.0[144..], group))
+    }
+
+    fn frames(&self) -> u32 {
+        (0..Self::GROUPS)
+            .map(|group| {
+                let (sr_ab, sr_cd) = self.sr(group);
+                let ab = (self.tp(group, 0) as u32 + self.tp(group, 1) as u32) * (sr_ab as u32 + 1);
+                let cd = (self.tp(group, 2) as u32 + self.tp(group, 3) as u32) * (sr_cd as u32 + 1);
+                (ab + cd) * (self.rp(group).unwrap_or(0) as u32 + 1)
+            })
+            .sum()
+    }
+}
+
+/// One line per group: TP of each phase, RP, FR, and the VS of each LUT with one digit per phase.
+fn write_table<L: Lut>(lut: &L, name: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "{}, {} frames", name, lut.frames())?;
+    writeln!(f, "group | TP | RP | FR | VS")?;
+    for group in 0..L::GROUPS {
+        write!(f, "{:5} |", group)?;
+        for phase in 0..L::PHASES {
+            write!(f, " {:3}", lut.tp(group, phase))?;
+        }
+        match lut.rp(group) {
+            Some(rp) => write!(f, " | {:3}", rp)?,
+            None => write!(f, " |   -")?,
+        }
+        match lut.fr(group) {
+            Some(fr) => write!(f, " | {:2}", fr)?,
+            None => write!(f, " |  -")?,
+        }
+        write!(f, " |")?;
+        for l in 0..L::LUTS {
+            write!(f, " ")?;
+            for phase in 0..L::PHASES {
+                write!(f, "{}", lut.vs(l, group, phase))?;
+            }
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "defmt")]
+fn format_table<L: Lut>(lut: &L, name: &str, f: defmt::Formatter<'_>) {
+    defmt::write!(
+        f,
+        "{=str}, {=u32} frames\ngroup | TP | RP | FR | VS",
+        name,
+        lut.frames()
+    );
+    for group in 0..L::GROUPS {
+        defmt::write!(f, "\n{=usize} |", group);
+        for phase in 0..L::PHASES {
+            defmt::write!(f, " {=u8}", lut.tp(group, phase));
+        }
+        match lut.rp(group) {
+            Some(rp) => defmt::write!(f, " | {=u8}", rp),
+            None => defmt::write!(f, " | -"),
+        }
+        match lut.fr(group) {
+            Some(fr) => defmt::write!(f, " | {=u8}", fr),
+            None => defmt::write!(f, " | -"),
+        }
+        defmt::write!(f, " |");
+        for l in 0..L::LUTS {
+            defmt::write!(f, " ");
+            for phase in 0..L::PHASES {
+                defmt::write!(f, "{=u8}", lut.vs(l, group, phase));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    not(any(
+        feature = "ssd1608",
+        feature = "ssd1619a",
+        feature = "ssd1675b",
+        feature = "ssd1680"
+    )),
+    allow(dead_code)
+)]
+mod tests {
+    use embedded_hal::delay::DelayNs;
+
+    use super::*;
+    use crate::interface::DisplayInterface;
+
+    /// Interface keeping the last table written with the WriteLut command.
+    #[derive(Default)]
+    struct Capture {
+        command: u8,
+        lut: Option<([u8; 160], usize)>,
+    }
+
+    impl DisplayInterface for Capture {
+        fn send_command(&mut self, command: u8) -> Result<(), DisplayError> {
+            self.command = command;
+            Ok(())
+        }
+
+        fn send_data(&mut self, data: &[u8]) -> Result<(), DisplayError> {
+            if self.command == 0x32 {
+                let mut lut = [0; 160];
+                lut[..data.len()].copy_from_slice(data);
+                self.lut = Some((lut, data.len()));
+            }
+            Ok(())
+        }
+
+        fn send_data_from_iter<'a, I>(&mut self, iter: I) -> Result<usize, DisplayError>
+        where
+            I: IntoIterator<Item = &'a u8>,
+        {
+            Ok(iter.into_iter().count())
+        }
+
+        fn is_busy_on(&mut self) -> bool {
+            false
+        }
+
+        fn reset<D: DelayNs>(&mut self, _delay: &mut D, _initial_delay: u32, _duration: u32) {}
+    }
+
+    /// The table written by `write`, parsed as `L`.
+    fn written<L>(write: impl FnOnce(&mut Capture) -> Result<(), DisplayError>) -> L
+    where
+        L: Lut + for<'a> TryFrom<&'a [u8], Error = DisplayError>,
+    {
+        let mut capture = Capture::default();
+        write(&mut capture).unwrap();
+        let (lut, len) = capture.lut.unwrap();
+        L::try_from(&lut[..len]).unwrap()
+    }
+
+    /// `scale_timing(100)` is the identity, 200 doubles the phases up to `MAX_TP`.
+    fn check_scaling<L: Lut + Clone>(lut: &L) {
+        let mut same = lut.clone();
+        same.scale_timing(100);
+        assert_eq!(same.as_bytes(), lut.as_bytes());
+
+        let mut doubled = lut.clone();
+        doubled.scale_timing(200);
+        for group in 0..L::GROUPS {
+            for phase in 0..L::PHASES {
+                let tp = lut.tp(group, phase) as u32;
+                let expected = (tp * 2).min(L::MAX_TP as u32) as u8;
+                assert_eq!(doubled.tp(group, phase), expected);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ssd1608")]
+    fn lut30_ssd1608() {
+        use crate::drivers::{GrayScaleDriver, SSD1608};
+        use embedded_graphics::pixelcolor::Gray4;
+
+        let lut: Lut30 = written(<SSD1608 as GrayScaleDriver<Gray4>>::restore_normal_waveform);
+        // VS 0x50: LUT 0 and 1 drive VSH1 in the first phase
+        assert_eq!(
+            (lut.vs(0, 0, 0), lut.vs(1, 0, 0), lut.vs(2, 0, 0)),
+            (1, 1, 0)
+        );
+        // TP 0xff, 0xff, 0x1f: the even phase in the low nibble
+        assert!((0..5).all(|group| lut.tp(group, 0) == 0x0f));
+        assert_eq!((lut.tp(5, 0), lut.tp(6, 0)), (1, 0));
+        assert_eq!(lut.rp(0), None);
+        assert_eq!(lut.frames(), 5 * 15 + 1);
+        check_scaling(&lut);
+
+        let mut lut = Lut30::new([0; 30]);
+        lut.set_tp(1, 0, 3);
+        lut.set_tp(2, 0, 0x1f);
+        assert_eq!(&lut.as_bytes()[20..22], &[0x30, 0x0f]);
+    }
+
+    #[test]
+    #[cfg(feature = "ssd1619a")]
+    fn lut70_ssd1619a() {
+        use crate::drivers::{FastUpdateDriver, GrayScaleDriver, SSD1619A};
+        use embedded_graphics::pixelcolor::Gray4;
+
+        let lut: Lut70 = written(SSD1619A::setup_fast_waveform);
+        assert_eq!(
+            (lut.vs(0, 0, 0), lut.vs(1, 0, 0), lut.vs(0, 0, 1)),
+            (1, 2, 0)
+        );
+        assert_eq!(lut.tp(0, 0), 0x1f);
+        assert!((1..4).all(|phase| lut.tp(0, phase) == 0));
+        assert_eq!(lut.rp(0), Some(0));
+        assert_eq!(lut.fr(0), None);
+        assert_eq!(lut.frames(), 0x1f);
+        check_scaling(&lut);
+
+        let lut: Lut70 = written(<SSD1619A as GrayScaleDriver<Gray4>>::restore_normal_waveform);
+        // TP at 35 + group * 5
+        assert_eq!(
+            (lut.tp(0, 0), lut.tp(1, 0), lut.tp(2, 0)),
+            (0x0f, 0x14, 0x1a)
+        );
+        assert_eq!(
+            (lut.vs(0, 0, 3), lut.vs(0, 1, 3), lut.vs(1, 2, 0)),
+            (2, 1, 2)
+        );
+        assert_eq!(lut.frames(), 0x0f + 0x14 + 0x1a);
+        check_scaling(&lut);
+
+        let mut bytes = [0; 70];
+        bytes[35 + 3 * 5 + 2] = 7;
+        bytes[35 + 3 * 5 + 4] = 2;
+        let lut = Lut70::new(bytes);
+        assert_eq!((lut.tp(3, 2), lut.rp(3)), (7, Some(2)));
+        assert_eq!(lut.frames(), 21);
+    }
+
+    #[test]
+    #[cfg(feature = "ssd1675b")]
+    fn lut105_ssd1675b() {
+        use crate::drivers::{FastUpdateDriver, SSD1675B};
+
+        let lut: Lut105 = written(SSD1675B::restore_normal_waveform);
+        // TP at 50 + group * 5
+        assert_eq!(
+            [0, 1, 2, 3].map(|phase| lut.tp(0, phase)),
+            [0x00, 0x02, 0x03, 0x0a]
+        );
+        assert_eq!(
+            [0, 1, 2, 3].map(|phase| lut.tp(1, phase)),
+            [0x02, 0x06, 0x0a, 0x05]
+        );
+        assert_eq!((lut.rp(0), lut.rp(1)), (Some(0), Some(0)));
+        // VS 0x2a of LUT 0, group 0
+        assert_eq!([0, 1, 2, 3].map(|phase| lut.vs(0, 0, phase)), [0, 2, 2, 2]);
+        assert!((0..10).all(|group| lut.fr(group) == Some(2)));
+        assert_eq!(lut.frames(), 15 + 23);
+        check_scaling(&lut);
+
+        let mut bytes = [0; 105];
+        bytes[100] = 0x12;
+        bytes[104] = 0x09;
+        let lut = Lut105::new(bytes);
+        assert_eq!((lut.fr(0), lut.fr(1)), (Some(1), Some(2)));
+        assert_eq!((lut.fr(8), lut.fr(9)), (Some(0), Some(9)));
+    }
+
+    #[test]
+    #[cfg(feature = "ssd1680")]
+    fn lut153_ssd1680() {
+        use crate::drivers::{FastUpdateDriver, SSD1680};
+
+        let lut: Lut153 = written(SSD1680::setup_fast_waveform);
+        assert_eq!(
+            (lut.tp(0, 0), lut.rp(0), lut.fr(0)),
+            (0x7f, Some(0), Some(7))
+        );
+        assert_eq!(lut.fr(1), Some(0));
+        assert_eq!(lut.frames(), 0x7f);
+        check_scaling(&lut);
+
+        let lut: Lut153 = written(SSD1680::restore_normal_waveform);
+        assert_eq!([0, 1, 2, 3].map(|phase| lut.tp(0, phase)), [20, 8, 0, 0]);
+        assert_eq!([0, 1, 2, 3].map(|phase| lut.tp(1, phase)), [10, 10, 10, 10]);
+        assert_eq!([0, 1, 2, 3].map(|phase| lut.tp(8, phase)), [20, 8, 1, 0]);
+        assert_eq!(
+            (lut.rp(1), lut.rp(9), lut.sr(1)),
+            (Some(1), Some(1), (0, 0))
+        );
+        assert!((0..12).all(|group| lut.fr(group) == Some(4)));
+        assert_eq!(lut.xon(), [0; 3]);
+        assert_eq!(lut.frames(), 28 * 2 + 40 * 2 + 29 * 2);
+        check_scaling(&lut);
+
+        // TP A, TP B, SR AB, TP C, TP D, SR CD and RP of group 2
+        let mut bytes = [0; 153];
+        bytes[60 + 2 * 7..60 + 3 * 7].copy_from_slice(&[2, 3, 1, 4, 5, 2, 1]);
+        bytes[150] = 0x80;
+        let mut lut = Lut153::new(bytes);
+        assert_eq!([0, 1, 2, 3].map(|phase| lut.tp(2, phase)), [2, 3, 4, 5]);
+        assert_eq!((lut.sr(2), lut.rp(2)), ((1, 2), Some(1)));
+        assert_eq!(lut.xon(), [0x80, 0, 0]);
+        assert_eq!(lut.frames(), (5 * 2 + 9 * 3) * 2);
+        lut.set_tp(2, 2, 6);
+        assert_eq!(
+            &lut.as_bytes()[60 + 2 * 7..60 + 3 * 7],
+            &[2, 3, 1, 6, 5, 2, 1]
+        );
+    }
+}
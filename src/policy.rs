@@ -0,0 +1,241 @@
+//! Automatic choice between fast and full refreshes.
+
+use crate::interface::DisplayError;
+
+/// Kind of refresh chosen by a `RefreshPolicy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RefreshKind {
+    /// Nothing changed, the display is left as is
+    None,
+    /// Fast update waveform, leaves some ghosting behind
+    Fast,
+    /// Full update waveform, clears ghosting
+    Full,
+}
+
+/// Decide between fast and full refreshes, following the panel vendors' recommendations.
+///
+/// Time is given in ms from the clock of the display, as for the `RateLimiter`, see `FastUpdateEpd::present`.
+#[derive(Clone, Copy, Debug)]
+pub struct RefreshPolicy {
+    /// Fast updates in a row before a full refresh is forced
+    pub max_fast_updates: u32,
+    /// Minimum time between full refreshes triggered by large changes, in ms
+    pub min_full_interval_ms: u32,
+    /// Changed area in percent of the panel above which a full refresh is preferred
+    pub full_refresh_percent: u8,
+    fast_updates: u32,
+    last_full: Option<u32>,
+}
+
+impl RefreshPolicy {
+    pub const fn new() -> Self {
+        Self {
+            max_fast_updates: 5,
+            min_full_interval_ms: 180_000,
+            full_refresh_percent: 50,
+            fast_updates: 0,
+            last_full: None,
+        }
+    }
+
+    /// Choose the refresh for `changed` out of `total` pixels at time `now`.
+    pub fn decide(&self, now: u32, changed: usize, total: usize) -> RefreshKind {
+        let Some(last_full) = self.last_full else {
+            return RefreshKind::Full;
+        };
+        let large = changed * 100 >= total * self.full_refresh_percent as usize
+            && now.wrapping_sub(last_full) >= self.min_full_interval_ms;
+        if changed == 0 {
+            RefreshKind::None
+        } else if self.fast_updates >= self.max_fast_updates || large {
+            RefreshKind::Full
+        } else {
+            RefreshKind::Fast
+        }
+    }
+
+    /// Record a refresh done at time `now`.
+    pub fn record(&mut self, kind: RefreshKind, now: u32) {
+        match kind {
+            RefreshKind::None => {}
+            RefreshKind::Fast => self.fast_updates += 1,
+            RefreshKind::Full => {
+                self.fast_updates = 0;
+                self.last_full = Some(now);
+            }
+        }
+    }
+}
+
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Monotonic time source of a display, for its `RefreshPolicy`, its `RateLimiter` and the busy time of its stats.
+pub trait Clock {
+    /// Milliseconds since any point in the past, wrapping around.
+    fn now_ms(&mut self) -> u32;
+}
+
+/// A function or closure, e.g. reading a HAL timer.
+impl<F: FnMut() -> u32> Clock for F {
+    fn now_ms(&mut self) -> u32 {
+        self()
+    }
+}
+
+/// What a `RateLimiter` does with a refresh coming too early.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OnRateLimit {
+    /// Fail with `DisplayError::RateLimited`
+    Error,
+    /// Skip the refresh silently, the frame is shown by a later one
+    Defer,
+}
+
+/// Guard against refreshing more often than the panel vendors allow, e.g. in a buggy update loop.
+///
+/// Frequent full refreshes wear the panel, and fast ones without a full refresh in between leave
+/// charges behind that can damage it.
+///
+/// Time is given in ms from the clock of the display, see `Epd::with_clock`.
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimiter {
+    /// Minimum time between full refreshes, in ms
+    pub min_full_interval_ms: u32,
+    /// Minimum time between fast refreshes, and from a full refresh to a fast one, in ms
+    pub min_fast_interval_ms: u32,
+    pub on_limit: OnRateLimit,
+    last_full: Option<u32>,
+    last_refresh: Option<u32>,
+    deferred: bool,
+}
+
+impl RateLimiter {
+    /// A full refresh every 180s and a fast one every second at most, deferring the others.
+    pub const fn new() -> Self {
+        Self {
+            min_full_interval_ms: 180_000,
+            min_fast_interval_ms: 1_000,
+            on_limit: OnRateLimit::Defer,
+            last_full: None,
+            last_refresh: None,
+            deferred: false,
+        }
+    }
+
+    /// Time in ms until a refresh of `kind` is allowed at time `now`, 0 if it is.
+    pub fn wait_ms(&self, kind: RefreshKind, now: u32) -> u32 {
+        let (last, interval) = match kind {
+            RefreshKind::None => return 0,
+            RefreshKind::Fast => (self.last_refresh, self.min_fast_interval_ms),
+            RefreshKind::Full => (self.last_full, self.min_full_interval_ms),
+        };
+        last.map_or(0, |last| interval.saturating_sub(now.wrapping_sub(last)))
+    }
+
+    /// Whether a refresh of `kind` may start at time `now`: `Ok(false)` to defer it, an error with
+    /// `OnRateLimit::Error`.
+    pub fn allow(&mut self, kind: RefreshKind, now: u32) -> Result<bool, DisplayError> {
+        if self.wait_ms(kind, now) == 0 {
+            return Ok(true);
+        }
+        match self.on_limit {
+            OnRateLimit::Error => Err(DisplayError::RateLimited),
+            OnRateLimit::Defer => {
+                self.deferred = true;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Record a refresh of `kind` started at time `now`.
+    pub fn record(&mut self, kind: RefreshKind, now: u32) {
+        match kind {
+            RefreshKind::None => return,
+            RefreshKind::Fast => {}
+            RefreshKind::Full => self.last_full = Some(now),
+        }
+        self.last_refresh = Some(now);
+        self.deferred = false;
+    }
+
+    /// A refresh was deferred since the last one done, the display does not show the latest frame.
+    pub fn is_deferred(&self) -> bool {
+        self.deferred
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Offsets of `PixelShift` in units of the amplitude, a square orbit around the original position.
+const SHIFT_ORBIT: [(i32, i32); 9] = [
+    (0, 0),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+/// Move the displayed content by a pixel or two on a schedule, as e-readers do for status bars,
+/// so that static content does not burn in.
+///
+/// The offset goes around a square orbit of `amplitude` pixels, in native orientation.
+/// Pixels at the edges are repeated into the uncovered area.
+#[derive(Clone, Copy, Debug)]
+pub struct PixelShift {
+    /// Largest offset in pixels, 1 or 2, 0 disables the shift
+    pub amplitude: u8,
+    /// Frames displayed at each offset
+    pub frames: u16,
+    shown: u16,
+    step: usize,
+}
+
+impl PixelShift {
+    pub const fn new() -> Self {
+        Self {
+            amplitude: 1,
+            frames: 10,
+            shown: 0,
+            step: 0,
+        }
+    }
+
+    /// Offset of the next frame.
+    pub fn offset(&self) -> (i32, i32) {
+        let (x, y) = SHIFT_ORBIT[self.step];
+        let amplitude = self.amplitude.min(2) as i32;
+        (x * amplitude, y * amplitude)
+    }
+
+    /// Return the offset of a frame about to be displayed and advance the schedule.
+    pub fn next_frame(&mut self) -> (i32, i32) {
+        let offset = self.offset();
+        self.shown += 1;
+        if self.shown >= self.frames {
+            self.shown = 0;
+            self.step = (self.step + 1) % SHIFT_ORBIT.len();
+        }
+        offset
+    }
+}
+
+impl Default for PixelShift {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,34 @@
+//! The types most applications use, in one `use epd::prelude::*;`.
+//!
+//! Driver traits are left out, they are only needed to write generic code over drivers.
+
+pub use embedded_graphics::pixelcolor::BinaryColor;
+
+pub use crate::color::{Gray2, Gray3, Gray4, TriColor};
+pub use crate::display::{
+    DisplayRotation, DisplaySize, DisplaySize104x201, DisplaySize122x250, DisplaySize128x296,
+    DisplaySize152x152, DisplaySize200x200, DisplaySize200x300, DisplaySize212x104,
+    DisplaySize240x416, DisplaySize296x152, DisplaySize400x300, DisplaySize800x480, FrameBuffer,
+    Mirroring,
+};
+#[cfg(feature = "pd")]
+pub use crate::drivers::PervasiveDisplays;
+pub use crate::drivers::SleepMode;
+#[cfg(feature = "il3895")]
+pub use crate::drivers::IL3895;
+#[cfg(feature = "ssd1619a")]
+pub use crate::drivers::SSD1619A;
+#[cfg(feature = "ssd1675b")]
+pub use crate::drivers::SSD1675B;
+#[cfg(feature = "ssd1680")]
+pub use crate::drivers::SSD1680;
+#[cfg(feature = "uc8176")]
+pub use crate::drivers::UC8176;
+#[cfg(feature = "uc8179")]
+pub use crate::drivers::UC8179;
+#[cfg(feature = "ssd1608")]
+pub use crate::drivers::{SSD1608Fast, SSD1608};
+pub use crate::interface::{DisplayError, EpdInterface};
+#[cfg(feature = "nightly")]
+pub use crate::GrayScaleEpd;
+pub use crate::{DynEpd, Epd, EpdBuilder, FastUpdateEpd, StreamingEpd, TriColorEpd};
@@ -0,0 +1,181 @@
+//! A tiny run-length encoding for 1bpp image assets.
+//!
+//! Layout: width and height as little endian `u16`, followed by packets over the packed rows
+//! (MSB-first, each row padded to whole bytes, the same as `FrameBuffer::blit`):
+//!
+//! - `0x00..=0x7f`: the next `n + 1` bytes are literals
+//! - `0x80..=0xff`: the next byte is repeated `(n & 0x7f) + 1` times
+
+use embedded_graphics::prelude::Size;
+
+use crate::interface::DisplayError;
+
+/// Widest image that can be decoded, rows are buffered on the stack.
+pub const MAX_WIDTH: usize = 1024;
+
+/// Parse the header, returning the image size and the decoded bytes.
+pub fn decode(data: &[u8]) -> Result<(Size, Decoder<'_>), DisplayError> {
+    if data.len() < 4 {
+        return Err(DisplayError::InvalidFormatError);
+    }
+    let width = u16::from_le_bytes([data[0], data[1]]);
+    let height = u16::from_le_bytes([data[2], data[3]]);
+    if width as usize > MAX_WIDTH {
+        return Err(DisplayError::InvalidFormatError);
+    }
+    Ok((
+        Size::new(width as _, height as _),
+        Decoder {
+            data: &data[4..],
+            remaining: 0,
+            repeat: None,
+        },
+    ))
+}
+
+/// Iterator over decoded bytes, ends early on truncated data.
+#[derive(Clone, Debug)]
+pub struct Decoder<'a> {
+    data: &'a [u8],
+    remaining: usize,
+    repeat: Option<u8>,
+}
+
+impl Iterator for Decoder<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.remaining == 0 {
+            let (&packet, rest) = self.data.split_first()?;
+            self.data = rest;
+            self.remaining = (packet & 0x7f) as usize + 1;
+            self.repeat = if packet & 0x80 != 0 {
+                let (&value, rest) = self.data.split_first()?;
+                self.data = rest;
+                Some(value)
+            } else {
+                None
+            };
+        }
+
+        self.remaining -= 1;
+        match self.repeat {
+            Some(value) => Some(value),
+            None => {
+                let (&value, rest) = self.data.split_first()?;
+                self.data = rest;
+                Some(value)
+            }
+        }
+    }
+}
+
+/// Encode packed 1bpp rows, `data` must hold `ceil(width / 8) * height` bytes, extra bytes are ignored.
+#[cfg(feature = "std")]
+pub fn encode(data: &[u8], width: u16, height: u16) -> Result<std::vec::Vec<u8>, DisplayError> {
+    let len = (width as usize).div_ceil(8) * height as usize;
+    let data = data.get(..len).ok_or(DisplayError::InvalidFormatError)?;
+    let mut out = std::vec::Vec::new();
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+
+    let run_at = |i: usize| {
+        data[i..]
+            .iter()
+            .take(128)
+            .take_while(|&&b| b == data[i])
+            .count()
+    };
+
+    let mut i = 0;
+    let mut literal_start = 0;
+    while i < data.len() {
+        let run = run_at(i);
+        if run < 3 && i - literal_start < 128 {
+            i += 1;
+            continue;
+        }
+        // flush pending literals
+        for chunk in data[literal_start..i].chunks(128) {
+            out.push(chunk.len() as u8 - 1);
+            out.extend_from_slice(chunk);
+        }
+        if run >= 3 {
+            out.push(0x80 | (run as u8 - 1));
+            out.push(data[i]);
+            i += run;
+        }
+        literal_start = i;
+    }
+    for chunk in data[literal_start..].chunks(128) {
+        out.push(chunk.len() as u8 - 1);
+        out.extend_from_slice(chunk);
+    }
+    Ok(out)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+
+    fn round_trip(data: &[u8], width: u16, height: u16) -> Vec<u8> {
+        let encoded = encode(data, width, height).unwrap();
+        let (size, decoder) = decode(&encoded).unwrap();
+        assert_eq!(size, Size::new(width as _, height as _));
+        let decoded: Vec<u8> = decoder.collect();
+        assert_eq!(decoded, data);
+        encoded
+    }
+
+    #[test]
+    fn long_runs() {
+        // 300 bytes of one value take three repeat packets of 128, 128 and 44
+        let encoded = round_trip(&[0xaa; 300], 8, 300);
+        assert_eq!(&encoded[4..], &[0xff, 0xaa, 0xff, 0xaa, 0xab, 0xaa]);
+    }
+
+    #[test]
+    fn long_literals() {
+        // no byte repeats, literals are split in packets of 128
+        let data: Vec<u8> = (0..=255).chain(0..4).collect();
+        let encoded = round_trip(&data, 8, 260);
+        assert_eq!(encoded[4], 0x7f);
+        assert_eq!(encoded[4 + 129], 0x7f);
+        assert_eq!(encoded[4 + 258], 0x03);
+        assert_eq!(encoded.len(), 4 + 3 + 260);
+    }
+
+    #[test]
+    fn mixed() {
+        let mut data = Vec::new();
+        for i in 0..40u8 {
+            data.extend((0..i % 5).map(|j| i ^ j));
+            data.extend(core::iter::repeat_n(i, i as usize * 4));
+        }
+        let len = data.len() - data.len() % 3;
+        round_trip(&data[..len], 24, (len / 3) as _);
+    }
+
+    #[test]
+    fn short_input() {
+        // 12 pixels wide rows take 2 bytes
+        assert_eq!(
+            encode(&[0; 5], 12, 3),
+            Err(DisplayError::InvalidFormatError)
+        );
+        round_trip(&[0, 1, 2, 3, 4, 5], 12, 3);
+    }
+
+    #[test]
+    fn truncated() {
+        let encoded = encode(&[0x55; 200], 8, 200).unwrap();
+        for len in 0..4 {
+            assert!(decode(&encoded[..len]).is_err());
+        }
+        // the decoder ends early instead of making up bytes
+        let (_, decoder) = decode(&encoded[..encoded.len() - 1]).unwrap();
+        assert_eq!(decoder.count(), 128);
+    }
+}